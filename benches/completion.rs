@@ -0,0 +1,76 @@
+//! Keystroke latency of completing against 50 000 indexed candidates, and the time of the frames
+//! rebuilding the index.
+//!
+//! Run with `cargo bench --bench completion`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bevy_console::CompletionIndex;
+
+const CANDIDATES: usize = 50_000;
+const KEYSTROKES: u32 = 1_000;
+const MAX_KEYSTROKE_LATENCY: Duration = Duration::from_millis(1);
+
+const WORDS: [&str; 8] = [
+    "spawn",
+    "Despawn",
+    "teleport",
+    "set_speed",
+    "debug",
+    "Inventory",
+    "quest",
+    "render",
+];
+
+fn candidates() -> Vec<String> {
+    (0..CANDIDATES)
+        .map(|i| {
+            format!(
+                "{}_{}_{i}",
+                WORDS[i % WORDS.len()],
+                WORDS[i / 7 % WORDS.len()]
+            )
+        })
+        .collect()
+}
+
+fn main() {
+    let mut index = CompletionIndex::default();
+    index.set_source("commands", 1, candidates);
+
+    let mut frames = 0;
+    let mut slowest_frame = Duration::ZERO;
+    let start = Instant::now();
+    while !index.is_ready() {
+        let frame = Instant::now();
+        index.index_step();
+        slowest_frame = slowest_frame.max(frame.elapsed());
+        frames += 1;
+    }
+    println!(
+        "indexed {CANDIDATES} candidates in {frames} frames of up to {} candidates, {:?} in \
+         total, slowest frame {slowest_frame:?}",
+        index.budget,
+        start.elapsed(),
+    );
+
+    // Typing a command name one keystroke at a time, a line matching nothing by prefix which
+    // falls back to the fuzzy search, and a line matching nothing at all
+    let lines = ["inventory_quest_4999", "Teleport_D", "sdq9", "zzz"];
+    for line in lines {
+        for end in 1..=line.len() {
+            let typed = &line[..end];
+            let start = Instant::now();
+            for _ in 0..KEYSTROKES {
+                black_box(index.suggestions(black_box(typed), 10));
+            }
+            let latency = start.elapsed() / KEYSTROKES;
+            println!("{typed:>24}: {latency:?}");
+            assert!(
+                latency < MAX_KEYSTROKE_LATENCY,
+                "completing {typed:?} took {latency:?}"
+            );
+        }
+    }
+}
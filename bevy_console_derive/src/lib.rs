@@ -6,7 +6,10 @@ use syn::{parse_macro_input, DeriveInput};
 pub fn derive_clap_command(input: TokenStream) -> TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
 
-    let name_string = get_command_name(&derive_input);
+    let name_string = match get_command_name(&derive_input) {
+        Ok(name_string) => name_string,
+        Err(err) => return err.to_compile_error().into(),
+    };
     let name = &derive_input.ident;
     let generics = derive_input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
@@ -22,30 +25,61 @@ pub fn derive_clap_command(input: TokenStream) -> TokenStream {
     })
 }
 
-fn get_command_name(input: &DeriveInput) -> syn::LitStr {
-    input
-        .attrs
-        .iter()
-        .find_map(|attr| {
-            if attr.path.is_ident("command") {
-                if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
-                    return list.nested.iter().find_map(|meta| {
-                        if let syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) = meta {
-                            Some(nv.lit.clone())
-                        } else {
-                            None
-                        }
-                    });
-                }
+/// The `name` of the `#[command]` attribute, or the struct name in snake case without a
+/// trailing `Command` if there is none.
+fn get_command_name(input: &DeriveInput) -> syn::Result<syn::LitStr> {
+    let lit = input.attrs.iter().find_map(|attr| {
+        if attr.path.is_ident("command") {
+            if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+                return list.nested.iter().find_map(|meta| match meta {
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                        Some(nv.lit.clone())
+                    }
+                    _ => None,
+                });
             }
-            None
-        })
-        .map(|lit| {
-            if let syn::Lit::Str(str) = lit {
-                str
-            } else {
-                panic!("Expected string literal as command name");
+        }
+        None
+    });
+
+    match lit {
+        Some(syn::Lit::Str(str)) if str.value().trim().is_empty() => Err(syn::Error::new_spanned(
+            str,
+            "command name can't be empty, remove it to use the struct name",
+        )),
+        Some(syn::Lit::Str(str)) => Ok(str),
+        Some(lit) => Err(syn::Error::new_spanned(
+            lit,
+            "expected string literal as command name",
+        )),
+        None => {
+            let ident = input.ident.to_string();
+            let stem = match ident.strip_suffix("Command") {
+                Some(stem) if !stem.is_empty() => stem,
+                _ => &ident,
+            };
+            Ok(syn::LitStr::new(&snake_case(stem), input.ident.span()))
+        }
+    }
+}
+
+/// Converts a `CamelCase` name to `snake_case`, keeping acronyms together, e.g. `HTTPServer`
+/// becomes `http_server`.
+fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake = String::new();
+    for (index, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            let previous = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                snake.push('_');
             }
-        })
-        .unwrap_or_else(|| syn::LitStr::new(&input.ident.to_string(), input.ident.span()))
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
 }
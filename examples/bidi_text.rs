@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use bevy_console::{
+    ConsoleConfiguration, ConsoleOpen, ConsolePlugin, PrintConsoleLine, TextDirection,
+};
+
+/// Lines exercising right-to-left, mixed direction and combining text
+const TEST_LINES: &[&str] = &[
+    "Hebrew: שלום עולם",
+    "Arabic: مرحبا بالعالم",
+    "Mixed: player שחקן joined with 3 items",
+    "Hebrew points: בְּרֵאשִׁית",
+    "Arabic marks: مَرْحَبًا",
+    "Styled mark: \x1b[31me\x1b[0m\u{301}tude",
+    "Emoji modifier: 👍🏽 and joiner: 👩‍💻",
+];
+
+// egui's default fonts lack Hebrew and Arabic glyphs, register a font covering them and select
+// it with `FontChoice::Named` to see the text itself.
+// Run with `--rtl` to lay the console out right-to-left.
+fn main() {
+    let text_direction = if std::env::args().any(|arg| arg == "--rtl") {
+        TextDirection::RightToLeft
+    } else {
+        TextDirection::LeftToRight
+    };
+
+    App::new()
+        .add_plugins((DefaultPlugins, ConsolePlugin::new()))
+        .insert_resource(ConsoleConfiguration {
+            text_direction,
+            ..Default::default()
+        })
+        .insert_resource(ConsoleOpen {
+            open: true,
+            ..Default::default()
+        })
+        .add_systems(Startup, print_test_lines)
+        .run();
+}
+
+fn print_test_lines(mut console_line: EventWriter<PrintConsoleLine>) {
+    for line in TEST_LINES {
+        console_line.send(PrintConsoleLine::new((*line).into()));
+    }
+}
@@ -1,9 +1,9 @@
 use bevy::prelude::*;
-use bevy_console::{ConsoleConfiguration, ConsolePlugin, ToggleConsoleKey};
+use bevy_console::{not_typing_in_console, ConsoleConfiguration, ConsolePlugin, ToggleConsoleKey};
 
 fn main() {
     App::new()
-        .add_plugins((DefaultPlugins, ConsolePlugin))
+        .add_plugins((DefaultPlugins, ConsolePlugin::new()))
         .insert_resource(ConsoleConfiguration {
             keys: vec![
                 ToggleConsoleKey::ScanCode(41), // Console key on a swedish keyboard
@@ -12,5 +12,13 @@ fn main() {
             ],
             ..Default::default()
         })
+        // Typing a space in the console doesn't jump
+        .add_systems(Update, jump.run_if(not_typing_in_console))
         .run();
 }
+
+fn jump(keys: Res<Input<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Space) {
+        info!("Jump!");
+    }
+}
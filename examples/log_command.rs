@@ -4,7 +4,7 @@ use clap::Parser;
 
 fn main() {
     App::new()
-        .add_plugins((DefaultPlugins, ConsolePlugin))
+        .add_plugins((DefaultPlugins, ConsolePlugin::new()))
         .add_console_command::<LogCommand, _>(log_command)
         .run();
 }
@@ -20,13 +20,16 @@ struct LogCommand {
 }
 
 fn log_command(mut log: ConsoleCommand<LogCommand>) {
-    if let Some(Ok(LogCommand { msg, num })) = log.take() {
-        let repeat_count = num.unwrap_or(1);
+    // Handle every time the command was entered since the last frame
+    while let Some(result) = log.take() {
+        if let Ok(LogCommand { msg, num }) = result {
+            let repeat_count = num.unwrap_or(1);
 
-        for _ in 0..repeat_count {
-            reply!(log, "{msg}");
-        }
+            for _ in 0..repeat_count {
+                reply!(log, "{msg}");
+            }
 
-        log.ok();
+            log.ok();
+        }
     }
 }
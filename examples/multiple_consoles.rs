@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+use bevy_console::instance::{self, PrintConsoleLine};
+use bevy_console::{
+    reply, AddConsoleCommand, ConsoleCommand, ConsoleLayout, ConsolePlugin, ToggleConsoleKey,
+};
+use clap::Parser;
+
+/// Marker of the console listing the loaded assets, opened with F2
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct AssetConsole;
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            ConsolePlugin::new(),
+            ConsolePlugin::<AssetConsole>::default(),
+        ))
+        .insert_resource(instance::ConsoleConfiguration::<AssetConsole> {
+            keys: vec![ToggleConsoleKey::KeyCode(KeyCode::F2)],
+            layout: ConsoleLayout::BottomPanel,
+            title: Some("Assets".to_owned()),
+            ..Default::default()
+        })
+        // Only entered in the default console
+        .add_console_command::<GreetCommand, _>(greet_command)
+        // Only entered in the asset console
+        .add_console_command_for::<AssetConsole, ReloadCommand, _>(reload_command)
+        .add_systems(Startup, print_assets)
+        .run();
+}
+
+/// Says hello
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "greet")]
+struct GreetCommand;
+
+fn greet_command(mut greet: ConsoleCommand<GreetCommand>) {
+    while let Some(Ok(GreetCommand)) = greet.take() {
+        reply!(greet, "Hello from the game console");
+        greet.ok();
+    }
+}
+
+/// Reloads an asset
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "reload")]
+struct ReloadCommand {
+    /// Path of the asset
+    path: String,
+}
+
+fn reload_command(mut reload: ConsoleCommand<ReloadCommand, AssetConsole>) {
+    while let Some(Ok(ReloadCommand { path })) = reload.take() {
+        reply!(reload, "Reloading {path}");
+        reload.ok();
+    }
+}
+
+fn print_assets(mut console_line: EventWriter<PrintConsoleLine<AssetConsole>>) {
+    for path in ["level.gltf", "player.png", "music.ogg"] {
+        console_line.send(PrintConsoleLine::new(format!("loaded {path}").into()));
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+use bevy_console::{reply, reply_ok, AddConsoleCommand, ConsoleCommand, ConsolePlugin};
+use clap::{Parser, Subcommand, ValueEnum};
+
+fn main() {
+    App::new()
+        .add_plugins((DefaultPlugins, ConsolePlugin::new()))
+        .add_console_command::<EntityCommand, _>(entity_command)
+        .run();
+}
+
+/// Spawns, despawns and lists entities
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "entity")]
+struct EntityCommand {
+    #[command(subcommand)]
+    action: EntityAction,
+}
+
+#[derive(Subcommand)]
+enum EntityAction {
+    /// Spawns an entity with a mesh
+    Spawn {
+        /// Shape of the mesh
+        shape: Shape,
+    },
+    /// Despawns an entity
+    Despawn {
+        /// Index of the entity, as printed by `entity list`
+        index: u32,
+    },
+    /// Lists the spawned entities
+    List,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Shape {
+    Cube,
+    Sphere,
+}
+
+#[derive(Component)]
+struct Spawned(Shape);
+
+fn entity_command(
+    mut entity: ConsoleCommand<EntityCommand>,
+    mut commands: Commands,
+    spawned: Query<(Entity, &Spawned)>,
+) {
+    // `entity` alone prints the help listing the subcommands, `help entity spawn` the help of one
+    while let Some(Ok(EntityCommand { action })) = entity.take() {
+        match action {
+            EntityAction::Spawn { shape } => {
+                let id = commands.spawn(Spawned(shape)).id();
+                reply_ok!(entity, "Spawned {shape:?} {}", id.index());
+            }
+            EntityAction::Despawn { index } => {
+                match spawned.iter().find(|(id, _)| id.index() == index) {
+                    Some((id, _)) => {
+                        commands.entity(id).despawn();
+                        entity.ok();
+                    }
+                    None => entity.reply_failed(format!("No entity {index}")),
+                }
+            }
+            EntityAction::List => {
+                for (id, Spawned(shape)) in &spawned {
+                    reply!(entity, "{} {shape:?}", id.index());
+                }
+            }
+        }
+    }
+}
@@ -1,17 +1,32 @@
+use std::fmt::Write;
+
 use bevy::prelude::*;
-use bevy_console::{ConsolePlugin, ConsoleSet, PrintConsoleLine};
+use bevy_console::{Console, ConsolePlugin, ConsoleSet, PrintConsoleLine};
 
 fn main() {
     App::new()
-        .add_plugins((DefaultPlugins, ConsolePlugin))
+        .add_plugins((DefaultPlugins, ConsolePlugin::new()))
         // NOTE: this wouldn't work for this particular case,
         // systems in the [`ConsoleSet::Commands`] do not run if there are no console commands entered
         // .add_systems(Update, write_to_console.in_set(ConsoleSet::Commands))
-        // the below is the equivalent but without run conditions
-        .add_systems(Update, write_to_console.after(ConsoleSet::ConsoleUI))
+        // the below is the equivalent but without run conditions, and its lines are added to the
+        // scrollback in the same frame
+        .add_systems(
+            Update,
+            write_to_console
+                .after(ConsoleSet::ConsoleUI)
+                .before(ConsoleSet::ReceiveLines),
+        )
+        .add_systems(Startup, write_with_console)
         .run();
 }
 
 fn write_to_console(mut console_line: EventWriter<PrintConsoleLine>) {
     console_line.send(PrintConsoleLine::new("Hello".into()));
 }
+
+// `Console` prints without building `PrintConsoleLine`s, and supports `write!`
+fn write_with_console(mut console: Console) {
+    writeln!(console, "Started at {:?}", std::time::SystemTime::now()).unwrap();
+    console.warn("This is a warning");
+}
@@ -0,0 +1,350 @@
+use bevy_egui::egui::{text::LayoutJob, Color32, FontId, Stroke, TextFormat};
+
+use crate::ConsoleTheme;
+
+/// Text attributes set by ANSI SGR sequences.
+#[derive(Clone, Copy, Default)]
+struct SgrState {
+    foreground: Option<Color32>,
+    /// Index of the foreground in the basic 8 color palette, bold text uses its bright variant
+    basic_foreground: Option<u8>,
+    background: Option<Color32>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+}
+
+impl SgrState {
+    /// Applies the parameters of a single SGR sequence, e.g. `1;32`.
+    fn apply(&mut self, params: &str, theme: &ConsoleTheme) {
+        let mut codes = params
+            .split(';')
+            .map(|code| code.parse::<u8>().unwrap_or(0));
+
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = SgrState::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                9 => self.strikethrough = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                29 => self.strikethrough = false,
+                30..=37 => self.set_foreground(Some(code - 30), theme_color(code - 30, theme)),
+                38 => {
+                    if let Some(color) = extended_color(&mut codes) {
+                        self.set_foreground(None, color);
+                    }
+                }
+                39 => {
+                    self.foreground = None;
+                    self.basic_foreground = None;
+                }
+                40..=47 => self.background = Some(ansi_color(code - 40)),
+                48 => self.background = extended_color(&mut codes).or(self.background),
+                49 => self.background = None,
+                90..=97 => self.set_foreground(None, ansi_color(code - 90 + 8)),
+                100..=107 => self.background = Some(ansi_color(code - 100 + 8)),
+                _ => {}
+            }
+        }
+    }
+
+    fn set_foreground(&mut self, basic: Option<u8>, color: Color32) {
+        self.foreground = Some(color);
+        self.basic_foreground = basic;
+    }
+
+    fn text_format(&self, font_id: &FontId, default_color: Color32) -> TextFormat {
+        let mut color = match (self.basic_foreground, self.foreground) {
+            (Some(index), _) if self.bold => ansi_color(index + 8),
+            (_, Some(color)) => color,
+            (_, None) if self.bold => brighten(default_color),
+            (_, None) => default_color,
+        };
+        if self.dim {
+            color = color.gamma_multiply(0.6);
+        }
+
+        let line = |enabled: bool| {
+            if enabled {
+                Stroke::new(1.0, color)
+            } else {
+                Stroke::NONE
+            }
+        };
+
+        TextFormat {
+            font_id: font_id.clone(),
+            color,
+            background: self.background.unwrap_or(Color32::TRANSPARENT),
+            italics: self.italic,
+            underline: line(self.underline),
+            strikethrough: line(self.strikethrough),
+            ..Default::default()
+        }
+    }
+}
+
+/// Appends text containing ANSI escape sequences to `job` as styled runs.
+///
+/// Colors, bold, dim, italic, underline and strikethrough are supported.
+/// Other escape sequences are dropped.
+/// Red, yellow and green text use the matching [`ConsoleTheme`] colors.
+pub(crate) fn append_ansi(
+    job: &mut LayoutJob,
+    text: &str,
+    font_id: &FontId,
+    default_color: Color32,
+    theme: &ConsoleTheme,
+) {
+    let mut state = SgrState::default();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("\x1b[") {
+        if start > 0 {
+            append_run(
+                job,
+                &rest[..start],
+                state.text_format(font_id, default_color),
+            );
+        }
+
+        let sequence = &rest[start + 2..];
+        match sequence.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(end) => {
+                // Only SGR sequences (ending in `m`) affect text
+                if sequence.as_bytes()[end] == b'm' {
+                    state.apply(&sequence[..end], theme);
+                }
+                rest = &sequence[end + 1..];
+            }
+            None => rest = "",
+        }
+    }
+
+    append_run(job, rest, state.text_format(font_id, default_color));
+
+    // An empty text still gets a section so the line keeps the height of its font
+    if job.sections.is_empty() {
+        job.append("", 0f32, state.text_format(font_id, default_color));
+    }
+}
+
+/// Cuts text containing ANSI escape sequences after `max_chars` visible characters.
+///
+/// Returns the kept prefix and the number of visible characters cut off, or `None` if the text
+/// is short enough. Escape sequences are not counted and the cut never splits a grapheme from its
+/// combining marks.
+pub(crate) fn truncate_ansi(text: &str, max_chars: usize) -> Option<(&str, usize)> {
+    let mut visible = 0;
+    let mut cut = None;
+    let mut chars = text.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        if c == '\x1b' && text[index + 1..].starts_with('[') {
+            chars.next();
+            for (_, c) in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if cut.is_none() && visible >= max_chars && !is_combining(c) {
+            cut = Some((index, visible));
+        }
+        visible += 1;
+    }
+
+    cut.map(|(index, kept)| (&text[..index], visible - kept))
+}
+
+/// Appends a styled run, keeping combining marks at its start with the preceding character.
+///
+/// Splitting a grapheme across runs would shape the mark on its own, detached from its base.
+fn append_run(job: &mut LayoutJob, text: &str, format: TextFormat) {
+    let marks_end = text.find(|c: char| !is_combining(c)).unwrap_or(text.len());
+
+    let text = match job.sections.last_mut() {
+        Some(previous) if marks_end > 0 => {
+            job.text.push_str(&text[..marks_end]);
+            previous.byte_range.end = job.text.len();
+            &text[marks_end..]
+        }
+        _ => text,
+    };
+
+    if !text.is_empty() {
+        job.append(text, 0f32, format);
+    }
+}
+
+/// Whether `c` attaches to the preceding character rather than starting a grapheme.
+///
+/// Covers the combining marks of Latin, Cyrillic, Hebrew and Arabic text, joiners,
+/// variation selectors and emoji modifiers.
+fn is_combining(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{0483}'..='\u{0489}'
+            | '\u{0591}'..='\u{05BD}'
+            | '\u{05BF}'
+            | '\u{05C1}'..='\u{05C2}'
+            | '\u{05C4}'..='\u{05C5}'
+            | '\u{05C7}'
+            | '\u{0610}'..='\u{061A}'
+            | '\u{064B}'..='\u{065F}'
+            | '\u{0670}'
+            | '\u{06D6}'..='\u{06DC}'
+            | '\u{06DF}'..='\u{06E4}'
+            | '\u{06E7}'..='\u{06E8}'
+            | '\u{06EA}'..='\u{06ED}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{200C}'..='\u{200D}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{1F3FB}'..='\u{1F3FF}'
+            | '\u{E0100}'..='\u{E01EF}'
+    )
+}
+
+/// Parses the remainder of a `38;5;n` or `38;2;r;g;b` color sequence.
+fn extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<Color32> {
+    match codes.next()? {
+        5 => Some(ansi_color(codes.next()?)),
+        2 => Some(Color32::from_rgb(
+            codes.next()?,
+            codes.next()?,
+            codes.next()?,
+        )),
+        _ => None,
+    }
+}
+
+/// Moves a color halfway towards white, used for bold text without an explicit color.
+fn brighten(color: Color32) -> Color32 {
+    let channel = |value: u8| value + (255 - value) / 2;
+    Color32::from_rgb(channel(color.r()), channel(color.g()), channel(color.b()))
+}
+
+/// Maps an index of the basic 8 color ANSI palette to a color, using the theme where it has one.
+fn theme_color(index: u8, theme: &ConsoleTheme) -> Color32 {
+    match index {
+        1 => theme.error,
+        2 => theme.success,
+        3 => theme.warning,
+        _ => ansi_color(index),
+    }
+}
+
+/// Maps an index of the 256 color ANSI palette to a color.
+fn ansi_color(index: u8) -> Color32 {
+    const BASE: [Color32; 16] = [
+        Color32::from_rgb(0, 0, 0),
+        Color32::from_rgb(205, 49, 49),
+        Color32::from_rgb(13, 188, 121),
+        Color32::from_rgb(229, 229, 16),
+        Color32::from_rgb(36, 114, 200),
+        Color32::from_rgb(188, 63, 188),
+        Color32::from_rgb(17, 168, 205),
+        Color32::from_rgb(229, 229, 229),
+        Color32::from_rgb(102, 102, 102),
+        Color32::from_rgb(241, 76, 76),
+        Color32::from_rgb(35, 209, 139),
+        Color32::from_rgb(245, 245, 67),
+        Color32::from_rgb(59, 142, 234),
+        Color32::from_rgb(214, 112, 214),
+        Color32::from_rgb(41, 184, 219),
+        Color32::from_rgb(255, 255, 255),
+    ];
+
+    match index {
+        0..=15 => BASE[index as usize],
+        16..=231 => {
+            let cube = index - 16;
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            Color32::from_rgb(level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+        }
+        _ => Color32::from_gray(8 + (index - 232) * 10),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ansi_layout(
+        text: &str,
+        font_id: &FontId,
+        default_color: Color32,
+        theme: &ConsoleTheme,
+    ) -> LayoutJob {
+        let mut job = LayoutJob::default();
+        append_ansi(&mut job, text, font_id, default_color, theme);
+        job
+    }
+
+    #[test]
+    fn test_ansi_layout_splits_styled_runs() {
+        let font_id = FontId::monospace(14.0);
+        let theme = ConsoleTheme::dark();
+        let job = ansi_layout(
+            "plain \x1b[32mgreen\x1b[0m \x1b[1;4mheader\x1b[0m",
+            &font_id,
+            theme.text,
+            &theme,
+        );
+
+        assert_eq!(job.text, "plain green header");
+        assert_eq!(job.sections.len(), 4);
+        assert_eq!(job.sections[0].format.color, theme.text);
+        assert_eq!(job.sections[1].format.color, theme.success);
+        assert_eq!(job.sections[2].format.color, theme.text);
+        assert_eq!(job.sections[3].format.color, brighten(theme.text));
+        assert_ne!(job.sections[3].format.underline, Stroke::NONE);
+    }
+
+    #[test]
+    fn test_ansi_layout_drops_unterminated_sequence() {
+        let font_id = FontId::monospace(14.0);
+        let theme = ConsoleTheme::dark();
+        let job = ansi_layout("text\x1b[3", &font_id, theme.text, &theme);
+
+        assert_eq!(job.text, "text");
+    }
+
+    #[test]
+    fn test_ansi_layout_keeps_combining_marks_with_base() {
+        let font_id = FontId::monospace(14.0);
+        let theme = ConsoleTheme::dark();
+        let job = ansi_layout("\x1b[31me\x1b[0m\u{301}tude", &font_id, theme.text, &theme);
+
+        assert_eq!(job.text, "e\u{301}tude");
+        assert_eq!(job.sections.len(), 2);
+        assert_eq!(&job.text[job.sections[0].byte_range.clone()], "e\u{301}");
+        assert_eq!(&job.text[job.sections[1].byte_range.clone()], "tude");
+    }
+
+    #[test]
+    fn test_truncate_ansi_counts_visible_chars() {
+        assert_eq!(truncate_ansi("short", 10), None);
+        assert_eq!(
+            truncate_ansi("\x1b[31mgrüße\x1b[0m welt", 4),
+            Some(("\x1b[31mgrüß", 6))
+        );
+        assert_eq!(truncate_ansi("ae\u{301}b", 2), Some(("ae\u{301}", 1)));
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+#[cfg(feature = "default_commands")]
+use bevy::reflect::{DynamicEnum, DynamicVariant, TypeInfo, Typed};
+
+use crate::console::CommandQueue;
+#[cfg(feature = "default_commands")]
+use crate::localization::fill;
+use crate::ConsoleOpen;
+#[cfg(feature = "default_commands")]
+use crate::{ConsoleLocalization, ToggleConsoleKey};
+
+/// Command lines bound to keys with the built-in `bind` command.
+///
+/// Pressing a bound key while the console is closed runs its command line as if it was entered,
+/// so systems reading [`ConsoleCommandEntered`](crate::ConsoleCommandEntered) see no difference.
+///
+/// Bindings only last for the running session and aren't saved. To bind keys on every run, put
+/// the `bind` commands in the [`ConsoleConfiguration::autoexec`](crate::ConsoleConfiguration::autoexec)
+/// script.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct ConsoleBindings {
+    /// Keys mapped to the command lines they run
+    pub bindings: BTreeMap<KeyCode, String>,
+}
+
+/// Keys of the shortcuts handled by the console itself, read by the console every frame.
+///
+/// The built-in `shortcuts` command lists them, and `bind` refuses keys of shortcuts which work
+/// while the console is closed unless given `--force`, since bindings run then too. The keys
+/// toggling the console come from [`ConsoleConfiguration::keys`](crate::ConsoleConfiguration::keys)
+/// instead. Apps with global shortcuts of their own can add them to [`ConsoleShortcuts::global`]
+/// to warn about bindings of the same keys.
+#[derive(Clone, Debug, Resource)]
+pub struct ConsoleShortcuts {
+    /// Runs the input line, or completes the selected suggestion if it isn't typed in full
+    pub submit: KeyCode,
+    /// Completes the selected suggestion
+    pub complete: KeyCode,
+    /// Hides the suggestions
+    pub hide_suggestions: KeyCode,
+    /// Selects the previous suggestion or history entry
+    pub previous: KeyCode,
+    /// Selects the next suggestion or history entry
+    pub next: KeyCode,
+    /// Accepts the completion hint with the caret at the end of the input
+    pub accept_hint: Vec<KeyCode>,
+    /// Scrolls the scrollback up by a page
+    pub page_up: KeyCode,
+    /// Scrolls the scrollback down by a page
+    pub page_down: KeyCode,
+    /// Clears the scrollback, pressed with Ctrl
+    pub clear: KeyCode,
+    /// Shortcuts of the app working while the console is closed
+    pub global: Vec<ConsoleShortcut>,
+}
+
+impl Default for ConsoleShortcuts {
+    fn default() -> Self {
+        Self {
+            submit: KeyCode::Return,
+            complete: KeyCode::Tab,
+            hide_suggestions: KeyCode::Escape,
+            previous: KeyCode::Up,
+            next: KeyCode::Down,
+            accept_hint: vec![KeyCode::Right, KeyCode::End],
+            page_up: KeyCode::PageUp,
+            page_down: KeyCode::PageDown,
+            clear: KeyCode::L,
+            global: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "default_commands")]
+impl ConsoleShortcuts {
+    /// Every shortcut, the keys toggling the console first, described with `localization`.
+    pub(crate) fn list(
+        &self,
+        toggle_keys: &[ToggleConsoleKey],
+        localization: &ConsoleLocalization,
+    ) -> Vec<ConsoleShortcut> {
+        let toggle = &localization.shortcut_toggle;
+        let toggles = toggle_keys.iter().map(|key| match *key {
+            ToggleConsoleKey::KeyCode(key) => ConsoleShortcut::global(key, toggle),
+            ToggleConsoleKey::ScanCode(code) => {
+                let keys = fill(&localization.shortcut_scan_code, &[("code", &code)]);
+                ConsoleShortcut::new(keys, toggle)
+            }
+        });
+        let keys = |keys: &[KeyCode]| {
+            let names = keys.iter().map(|key| format!("{key:?}"));
+            names.collect::<Vec<_>>().join("/")
+        };
+        let console = [
+            (keys(&[self.submit]), &localization.shortcut_submit),
+            (keys(&[self.complete]), &localization.shortcut_complete),
+            (
+                keys(&[self.hide_suggestions]),
+                &localization.shortcut_hide_suggestions,
+            ),
+            (
+                keys(&[self.previous, self.next]),
+                &localization.shortcut_browse,
+            ),
+            (keys(&self.accept_hint), &localization.shortcut_accept_hint),
+            (
+                keys(&[self.page_up, self.page_down]),
+                &localization.shortcut_scroll,
+            ),
+            (
+                format!("Ctrl+{:?}", self.clear),
+                &localization.shortcut_clear,
+            ),
+            ("Ctrl+1-9".to_owned(), &localization.shortcut_recall),
+        ]
+        .map(|(keys, action)| ConsoleShortcut::new(keys, action));
+        toggles
+            .chain(console)
+            .chain(self.global.iter().cloned())
+            .collect()
+    }
+
+    /// The shortcut which also runs when `key` is pressed while the console is closed.
+    pub(crate) fn conflict(
+        &self,
+        key: KeyCode,
+        toggle_keys: &[ToggleConsoleKey],
+        localization: &ConsoleLocalization,
+    ) -> Option<ConsoleShortcut> {
+        self.list(toggle_keys, localization)
+            .into_iter()
+            .find(|shortcut| shortcut.key == Some(key))
+    }
+}
+
+/// A keyboard shortcut, as listed by the built-in `shortcuts` command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsoleShortcut {
+    /// Keys to press, like `Ctrl+L`
+    pub keys: String,
+    /// What pressing the keys does
+    pub action: String,
+    /// Key of a shortcut which works while the console is closed, where bindings of it run too
+    pub key: Option<KeyCode>,
+}
+
+impl ConsoleShortcut {
+    /// A shortcut working while the console is open.
+    pub fn new(keys: impl Into<String>, action: impl Into<String>) -> Self {
+        Self {
+            keys: keys.into(),
+            action: action.into(),
+            key: None,
+        }
+    }
+
+    /// A shortcut of a single key working while the console is closed.
+    pub fn global(key: KeyCode, action: impl Into<String>) -> Self {
+        Self {
+            keys: format!("{key:?}"),
+            action: action.into(),
+            key: Some(key),
+        }
+    }
+}
+
+#[cfg(feature = "default_commands")]
+/// Parses a key name, the name of a [`KeyCode`] variant ignoring case, e.g. `F5` or `key1`.
+pub(crate) fn parse_key(name: &str, localization: &ConsoleLocalization) -> Result<KeyCode, String> {
+    let TypeInfo::Enum(info) = KeyCode::type_info() else {
+        unreachable!("KeyCode is an enum");
+    };
+    info.variant_names()
+        .iter()
+        .find(|variant| variant.eq_ignore_ascii_case(name))
+        .and_then(|variant| {
+            KeyCode::from_reflect(&DynamicEnum::new(*variant, DynamicVariant::Unit))
+        })
+        .ok_or_else(|| {
+            let keys = info.variant_names().join(", ");
+            fill(
+                &localization.unknown_key,
+                &[("name", &name), ("keys", &keys)],
+            )
+        })
+}
+
+/// Queues the command lines of the bound keys pressed while the console is closed.
+pub(crate) fn run_key_bindings(
+    keys: Res<Input<KeyCode>>,
+    console_open: Res<ConsoleOpen>,
+    bindings: Res<ConsoleBindings>,
+    mut queue: ResMut<CommandQueue>,
+) {
+    if console_open.open || bindings.bindings.is_empty() {
+        return;
+    }
+    for key in keys.get_just_pressed() {
+        if let Some(command) = bindings.bindings.get(key) {
+            queue.lines.push_back(command.clone().into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "default_commands")]
+    #[test]
+    fn test_parse_key() {
+        let localization = ConsoleLocalization::default();
+        assert_eq!(parse_key("F5", &localization), Ok(KeyCode::F5));
+        assert_eq!(parse_key("key1", &localization), Ok(KeyCode::Key1));
+        assert!(parse_key("F99", &localization)
+            .unwrap_err()
+            .starts_with("unknown key 'F99', valid keys are: Key1, Key2"));
+    }
+
+    #[cfg(feature = "default_commands")]
+    #[test]
+    fn test_shortcuts_list_their_keys() {
+        let localization = ConsoleLocalization::default();
+        let mut shortcuts = ConsoleShortcuts {
+            clear: KeyCode::K,
+            ..default()
+        };
+        shortcuts
+            .global
+            .push(ConsoleShortcut::global(KeyCode::F12, "take a screenshot"));
+        let toggle_keys = [ToggleConsoleKey::KeyCode(KeyCode::Grave)];
+
+        let listed = shortcuts.list(&toggle_keys, &localization);
+        let keys = listed
+            .iter()
+            .map(|shortcut| shortcut.keys.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "Grave",
+                "Return",
+                "Tab",
+                "Escape",
+                "Up/Down",
+                "Right/End",
+                "PageUp/PageDown",
+                "Ctrl+K",
+                "Ctrl+1-9",
+                "F12"
+            ]
+        );
+        assert_eq!(listed[7].action, localization.shortcut_clear);
+        let conflict = shortcuts.conflict(KeyCode::F12, &toggle_keys, &localization);
+        assert_eq!(conflict.unwrap().action, "take a screenshot");
+        assert!(shortcuts
+            .conflict(KeyCode::L, &toggle_keys, &localization)
+            .is_none());
+    }
+
+    #[test]
+    fn test_bound_keys_run_while_closed() {
+        let mut app = App::new();
+        app.init_resource::<Input<KeyCode>>()
+            .init_resource::<ConsoleOpen>()
+            .init_resource::<ConsoleBindings>()
+            .init_resource::<CommandQueue>()
+            .add_systems(Update, run_key_bindings);
+        app.world
+            .resource_mut::<ConsoleBindings>()
+            .bindings
+            .insert(KeyCode::F5, "save quick".to_owned());
+
+        app.world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::F5);
+        app.update();
+        assert_eq!(app.world.resource::<CommandQueue>().lines, ["save quick"]);
+
+        app.world.resource_mut::<CommandQueue>().lines.clear();
+        app.world.resource_mut::<ConsoleOpen>().open = true;
+        let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+        keys.reset_all();
+        keys.press(KeyCode::F5);
+        app.update();
+        assert!(app.world.resource::<CommandQueue>().lines.is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::localization::fill;
+use crate::{reply, reply_ok, ConsoleAliases, ConsoleCommand, ConsoleConfiguration};
+
+/// Defines an alias for a command line, or lists the aliases without arguments
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "alias")]
+pub(crate) struct AliasCommand {
+    /// Name of the alias
+    name: Option<String>,
+    /// Command line the alias expands to, shows the alias if left out
+    expansion: Option<String>,
+}
+
+pub(crate) fn alias_command(
+    mut alias: ConsoleCommand<AliasCommand>,
+    mut aliases: ResMut<ConsoleAliases>,
+    config: Res<ConsoleConfiguration>,
+) {
+    while let Some(result) = alias.take() {
+        match result {
+            Ok(AliasCommand {
+                name: Some(name),
+                expansion: Some(expansion),
+            }) => {
+                if config.resolve_command(&name).is_some() {
+                    let localization = &config.localization;
+                    alias.reply_failed(fill(&localization.alias_is_command, &[("name", &name)]));
+                } else {
+                    reply_ok!(alias, "{name} = {expansion}");
+                    aliases.aliases.insert(name, expansion);
+                }
+            }
+            Ok(AliasCommand {
+                name: Some(name),
+                expansion: None,
+            }) => match aliases.aliases.get(&name) {
+                Some(expansion) => reply!(alias, "{name} = {expansion}"),
+                None => alias.reply(fill(&config.localization.no_alias, &[("name", &name)])),
+            },
+            Ok(AliasCommand { name: None, .. }) => {
+                if aliases.aliases.is_empty() {
+                    alias.reply(config.localization.no_aliases.clone());
+                }
+                for (name, expansion) in &aliases.aliases {
+                    reply!(alias, "{name} = {expansion}");
+                }
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// Removes an alias
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "unalias")]
+pub(crate) struct UnaliasCommand {
+    /// Name of the alias
+    name: String,
+}
+
+pub(crate) fn unalias_command(
+    mut unalias: ConsoleCommand<UnaliasCommand>,
+    mut aliases: ResMut<ConsoleAliases>,
+    config: Res<ConsoleConfiguration>,
+) {
+    while let Some(result) = unalias.take() {
+        if let Ok(UnaliasCommand { name }) = result {
+            if aliases.aliases.remove(&name).is_some() {
+                unalias.ok();
+            } else {
+                unalias.reply_failed(fill(&config.localization.no_alias, &[("name", &name)]));
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+use clap::builder::styling::AnsiColor;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::console::{colored, CommandOrigin, CommandQueue};
+use crate::script::queue_script;
+use crate::{ConsoleCommand, ConsoleConfiguration};
+
+/// Runs the autoexec script again
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "autoexec")]
+pub(crate) struct AutoexecCommand;
+
+pub(crate) fn autoexec_command(
+    mut autoexec: ConsoleCommand<AutoexecCommand>,
+    config: Res<ConsoleConfiguration>,
+    mut queue: ResMut<CommandQueue>,
+) {
+    while let Some(result) = autoexec.take() {
+        if result.is_err() {
+            continue;
+        }
+        let Some(path) = &config.autoexec else {
+            autoexec.reply_failed(config.localization.no_autoexec.clone());
+            continue;
+        };
+        let origin = autoexec.entered().map(|entered| entered.origin.script());
+        let origin = origin.unwrap_or(CommandOrigin::Script);
+        let localization = &config.localization;
+        if let Err(err) = queue_script(path, false, origin, localization, &mut queue) {
+            autoexec.reply_failed(colored(AnsiColor::Red, &err));
+        }
+    }
+}
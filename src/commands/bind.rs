@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::bind::parse_key;
+use crate::localization::fill;
+use crate::{
+    reply, reply_failed, reply_ok, ConsoleBindings, ConsoleCommand, ConsoleConfiguration,
+    ConsoleShortcuts,
+};
+
+/// Binds a key to a command line run when it's pressed outside the console, or shows the binding
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "bind")]
+pub(crate) struct BindCommand {
+    /// Name of the key, e.g. F5
+    key: String,
+    /// Command line to run, shows the binding if left out
+    command: Option<String>,
+    /// Bind the key even if a console shortcut uses it
+    #[arg(long)]
+    force: bool,
+}
+
+pub(crate) fn bind_command(
+    mut bind: ConsoleCommand<BindCommand>,
+    mut bindings: ResMut<ConsoleBindings>,
+    shortcuts: Res<ConsoleShortcuts>,
+    config: Res<ConsoleConfiguration>,
+) {
+    while let Some(result) = bind.take() {
+        let Ok(BindCommand {
+            key,
+            command,
+            force,
+        }) = result
+        else {
+            continue;
+        };
+        let key = match parse_key(&key, &config.localization) {
+            Ok(key) => key,
+            Err(err) => {
+                reply_failed!(bind, "{err}");
+                continue;
+            }
+        };
+        match command {
+            Some(command) => {
+                let conflict = shortcuts.conflict(key, &config.keys, &config.localization);
+                if let Some(shortcut) = conflict.filter(|_| !force) {
+                    bind.reply_failed(fill(
+                        &config.localization.bind_conflict,
+                        &[("key", &shortcut.keys), ("action", &shortcut.action)],
+                    ));
+                    continue;
+                }
+                reply_ok!(bind, "{key:?} = {command}");
+                bindings.bindings.insert(key, command);
+            }
+            None => match bindings.bindings.get(&key) {
+                Some(command) => reply!(bind, "{key:?} = {command}"),
+                None => bind.reply(fill(
+                    &config.localization.key_not_bound,
+                    &[("key", &format!("{key:?}"))],
+                )),
+            },
+        }
+    }
+}
+
+/// Removes the binding of a key
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "unbind")]
+pub(crate) struct UnbindCommand {
+    /// Name of the key
+    key: String,
+}
+
+pub(crate) fn unbind_command(
+    mut unbind: ConsoleCommand<UnbindCommand>,
+    mut bindings: ResMut<ConsoleBindings>,
+    config: Res<ConsoleConfiguration>,
+) {
+    while let Some(result) = unbind.take() {
+        if let Ok(UnbindCommand { key }) = result {
+            match parse_key(&key, &config.localization) {
+                Ok(key) => {
+                    if bindings.bindings.remove(&key).is_some() {
+                        unbind.ok();
+                    } else {
+                        unbind.reply_failed(fill(
+                            &config.localization.key_not_bound,
+                            &[("key", &format!("{key:?}"))],
+                        ));
+                    }
+                }
+                Err(err) => reply_failed!(unbind, "{err}"),
+            }
+        }
+    }
+}
+
+/// Lists the key bindings
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "bindlist")]
+pub(crate) struct BindListCommand;
+
+pub(crate) fn bindlist_command(
+    mut bindlist: ConsoleCommand<BindListCommand>,
+    bindings: Res<ConsoleBindings>,
+    config: Res<ConsoleConfiguration>,
+) {
+    while let Some(result) = bindlist.take() {
+        if result.is_ok() {
+            if bindings.bindings.is_empty() {
+                bindlist.reply(config.localization.no_bindings.clone());
+            }
+            for (key, command) in &bindings.bindings {
+                reply!(bindlist, "{key:?} = {command}");
+            }
+        }
+    }
+}
+
+/// Lists the console's shortcuts and the key bindings, marking bindings of keys the console uses
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "shortcuts")]
+pub(crate) struct ShortcutsCommand;
+
+pub(crate) fn shortcuts_command(
+    mut shortcuts: ConsoleCommand<ShortcutsCommand>,
+    bindings: Res<ConsoleBindings>,
+    registry: Res<ConsoleShortcuts>,
+    config: Res<ConsoleConfiguration>,
+) {
+    while let Some(result) = shortcuts.take() {
+        if result.is_err() {
+            continue;
+        }
+        let localization = &config.localization;
+        let console = registry.list(&config.keys, localization);
+        let mut rows = console
+            .iter()
+            .map(|shortcut| {
+                [
+                    shortcut.keys.clone(),
+                    shortcut.action.clone(),
+                    localization.shortcut_console.clone(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        rows.extend(bindings.bindings.iter().map(|(key, command)| {
+            let clashes = console.iter().any(|shortcut| shortcut.key == Some(*key));
+            let source = if clashes {
+                &localization.shortcut_conflict
+            } else {
+                &localization.shortcut_bind
+            };
+            [format!("{key:?}"), command.clone(), source.clone()]
+        }));
+        // Sorted by keys so a binding shows right after the console shortcut it clashes with
+        rows.sort_by(|a, b| a[0].cmp(&b[0]));
+        shortcuts.reply_table(localization.shortcuts_headers.clone(), rows);
+    }
+}
@@ -1,8 +1,7 @@
 use bevy::prelude::*;
 
 use crate as bevy_console;
-use crate::console::ConsoleState;
-use crate::ConsoleCommand;
+use crate::{ClearConsole, ConsoleCommand};
 use clap::Parser;
 
 /// Clears the console
@@ -12,9 +11,11 @@ pub(crate) struct ClearCommand;
 
 pub(crate) fn clear_command(
     mut clear: ConsoleCommand<ClearCommand>,
-    mut state: ResMut<ConsoleState>,
+    mut clear_console: EventWriter<ClearConsole>,
 ) {
-    if let Some(Ok(_)) = clear.take() {
-        state.scrollback.clear();
+    while let Some(result) = clear.take() {
+        if result.is_ok() {
+            clear_console.send(ClearConsole);
+        }
     }
 }
@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use clap::{Parser, Subcommand};
+
+use crate as bevy_console;
+use crate::localization::fill;
+use crate::{ConsoleCommand, ConsoleConfiguration};
+
+/// Inspects the console configuration
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "console_config")]
+pub(crate) struct ConsoleConfigCommand {
+    #[command(subcommand)]
+    action: ConfigAction,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Prints the current configuration in RON, without the registered commands
+    Dump,
+}
+
+pub(crate) fn console_config_command(
+    mut console_config: ConsoleCommand<ConsoleConfigCommand>,
+    config: Res<ConsoleConfiguration>,
+) {
+    while let Some(result) = console_config.take() {
+        let Ok(ConsoleConfigCommand {
+            action: ConfigAction::Dump,
+        }) = result
+        else {
+            continue;
+        };
+        match config.to_ron_string() {
+            Ok(ron) => {
+                for line in ron.lines() {
+                    console_config.reply(line.to_owned());
+                }
+            }
+            Err(err) => console_config.reply_failed(fill(
+                &config.localization.config_unwritable,
+                &[("error", &err)],
+            )),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::console::ConsoleState;
+use crate::{ConsoleCommand, ConsoleConfiguration};
+
+/// Copies the scrollback to the clipboard
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "copy")]
+pub(crate) struct CopyCommand {
+    /// Number of most recent lines to copy, all lines if omitted
+    lines: Option<usize>,
+}
+
+pub(crate) fn copy_command(
+    mut copy: ConsoleCommand<CopyCommand>,
+    state: Res<ConsoleState>,
+    config: Res<ConsoleConfiguration>,
+    mut egui_context: EguiContexts,
+) {
+    while let Some(result) = copy.take() {
+        let Ok(CopyCommand { lines }) = result else {
+            continue;
+        };
+        let count = lines
+            .unwrap_or(state.scrollback.len())
+            .min(state.scrollback.len());
+        let text = state.scrollback_text(count);
+        egui_context
+            .ctx_mut()
+            .output_mut(|output| output.copied_text = text);
+
+        copy.reply_ok(config.localization.copied_lines.fill(count, &[]));
+    }
+}
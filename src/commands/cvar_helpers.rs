@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::cvar::CvarError;
+use crate::{
+    reply_ok, ConsoleCommand, ConsoleConfiguration, ConsoleLocalization, ConsoleVariables,
+    CvarChanged,
+};
+
+/// Flips a bool console variable
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "toggle")]
+pub(crate) struct ToggleCommand {
+    /// Name of the console variable
+    name: String,
+}
+
+/// Increases a numeric console variable
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "inc")]
+pub(crate) struct IncCommand {
+    /// Name of the console variable
+    name: String,
+    /// Amount to add, 1 if left out
+    step: Option<f64>,
+}
+
+/// Decreases a numeric console variable
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "dec")]
+pub(crate) struct DecCommand {
+    /// Name of the console variable
+    name: String,
+    /// Amount to subtract, 1 if left out
+    step: Option<f64>,
+}
+
+/// Restores the default value of a console variable
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "reset")]
+pub(crate) struct ResetCommand {
+    /// Name of the console variable
+    name: String,
+}
+
+pub(crate) fn toggle_command(
+    mut toggle: ConsoleCommand<ToggleCommand>,
+    mut cvars: ResMut<ConsoleVariables>,
+    config: Res<ConsoleConfiguration>,
+    mut changed: EventWriter<CvarChanged>,
+) {
+    while let Some(result) = toggle.take() {
+        if let Ok(ToggleCommand { name }) = result {
+            let value = cvars.toggle(&name);
+            reply_change(&mut toggle, &mut changed, &config.localization, name, value);
+        }
+    }
+}
+
+pub(crate) fn inc_command(
+    mut inc: ConsoleCommand<IncCommand>,
+    mut cvars: ResMut<ConsoleVariables>,
+    config: Res<ConsoleConfiguration>,
+    mut changed: EventWriter<CvarChanged>,
+) {
+    while let Some(result) = inc.take() {
+        if let Ok(IncCommand { name, step }) = result {
+            let value = cvars.add(&name, step.unwrap_or(1.0));
+            reply_change(&mut inc, &mut changed, &config.localization, name, value);
+        }
+    }
+}
+
+pub(crate) fn dec_command(
+    mut dec: ConsoleCommand<DecCommand>,
+    mut cvars: ResMut<ConsoleVariables>,
+    config: Res<ConsoleConfiguration>,
+    mut changed: EventWriter<CvarChanged>,
+) {
+    while let Some(result) = dec.take() {
+        if let Ok(DecCommand { name, step }) = result {
+            let value = cvars.add(&name, -step.unwrap_or(1.0));
+            reply_change(&mut dec, &mut changed, &config.localization, name, value);
+        }
+    }
+}
+
+pub(crate) fn reset_command(
+    mut reset: ConsoleCommand<ResetCommand>,
+    mut cvars: ResMut<ConsoleVariables>,
+    config: Res<ConsoleConfiguration>,
+    mut changed: EventWriter<CvarChanged>,
+) {
+    while let Some(result) = reset.take() {
+        if let Ok(ResetCommand { name }) = result {
+            let value = cvars.reset(&name);
+            reply_change(&mut reset, &mut changed, &config.localization, name, value);
+        }
+    }
+}
+
+/// Replies with the new value of a variable and notifies about the change, like setting it
+/// directly does.
+fn reply_change<T>(
+    cmd: &mut ConsoleCommand<T>,
+    changed: &mut EventWriter<CvarChanged>,
+    localization: &ConsoleLocalization,
+    name: String,
+    value: Result<String, CvarError>,
+) {
+    match value {
+        Ok(value) => {
+            reply_ok!(cmd, "{name} = {value}");
+            changed.send(CvarChanged { name, value });
+        }
+        Err(err) => cmd.reply_failed(err.describe(localization)),
+    }
+}
@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::localization::fill;
+use crate::{reply, ConsoleCommand, ConsoleConfiguration, ConsoleVariables};
+
+/// Lists the console variables with their values
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "cvarlist")]
+pub(crate) struct CvarListCommand;
+
+pub(crate) fn cvarlist_command(
+    mut cvarlist: ConsoleCommand<CvarListCommand>,
+    cvars: Res<ConsoleVariables>,
+    config: Res<ConsoleConfiguration>,
+) {
+    while let Some(result) = cvarlist.take() {
+        if result.is_ok() {
+            for (name, value) in cvars.iter() {
+                reply!(cvarlist, "{name} = {value}");
+            }
+            for (prefix, type_name) in cvars.resource_prefixes() {
+                let fields = fill(
+                    &config.localization.help_resource_fields,
+                    &[("type", &type_name)],
+                );
+                reply!(cvarlist, "{prefix}.* = {fields}");
+            }
+            cvarlist.ok();
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use clap::builder::styling::AnsiColor;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::console::{colored, CommandOrigin, ConsoleState, ScrollbackLine};
+use crate::localization::fill;
+use crate::{ConsoleCommand, ConsoleConfiguration, ConsoleVariables};
+
+/// Writes the scrollback to a file, e.g. to attach to a bug report
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "dump")]
+pub(crate) struct DumpCommand {
+    /// Path of the file, replaced if it exists
+    path: PathBuf,
+    /// Start with the app version, the OS, the date and a summary of the configuration
+    #[arg(long)]
+    header: bool,
+    /// Prefix each line with the session time it was printed at and where its command came from
+    #[arg(long)]
+    origins: bool,
+}
+
+pub(crate) fn dump_command(
+    mut dump: ConsoleCommand<DumpCommand>,
+    config: Res<ConsoleConfiguration>,
+    cvars: Res<ConsoleVariables>,
+    state: Res<ConsoleState>,
+) {
+    while let Some(result) = dump.take() {
+        let Ok(DumpCommand {
+            path,
+            header,
+            origins,
+        }) = result
+        else {
+            continue;
+        };
+
+        let mut transcript = Vec::new();
+        if header {
+            transcript.extend(transcript_header(&config, &cvars, SystemTime::now()));
+        }
+        for line in &state.scrollback {
+            let text = line.plain_text();
+            transcript.push(if origins {
+                format!("{} {text}", line_prefix(line))
+            } else {
+                text
+            });
+        }
+        let mut text = transcript.join("\n");
+        text.push('\n');
+
+        let localization = &config.localization;
+        match fs::write(&path, text) {
+            Ok(()) => dump.reply_ok(
+                localization
+                    .dump_written
+                    .fill(state.scrollback.len(), &[("path", &path.display())]),
+            ),
+            Err(err) => {
+                let error = fill(
+                    &localization.dump_unwritable,
+                    &[("path", &path.display()), ("error", &err)],
+                );
+                dump.reply_failed(colored(AnsiColor::Red, &error));
+            }
+        }
+    }
+}
+
+/// Comment lines describing the app and the console the transcript comes from.
+fn transcript_header(
+    config: &ConsoleConfiguration,
+    cvars: &ConsoleVariables,
+    now: SystemTime,
+) -> Vec<String> {
+    let version = config
+        .app_version
+        .as_deref()
+        .unwrap_or(&config.localization.unknown_version);
+    vec![
+        format!("# version: {version}"),
+        format!("# os: {} {}", std::env::consts::OS, std::env::consts::ARCH),
+        format!("# date: {}", utc_date_time(now)),
+        format!(
+            "# console: {} commands, {} aliases, {} variables, scrollback of {} lines, \
+             layout {:?}",
+            config.commands.len(),
+            config.aliases.len(),
+            cvars.names().count(),
+            config.scrollback_size,
+            config.layout,
+        ),
+        String::new(),
+    ]
+}
+
+/// Session time a line was printed at and where its command came from, like
+/// `[00:01:02.345 remote 3]`.
+///
+/// Lines which don't echo or reply to a command, e.g. from the app or captured logs, are local.
+fn line_prefix(line: &ScrollbackLine) -> String {
+    let origin = match line.origin.unwrap_or_default() {
+        CommandOrigin::Local => "local".to_owned(),
+        CommandOrigin::Remote(id) => format!("remote {id}"),
+        CommandOrigin::Script => "exec".to_owned(),
+        CommandOrigin::Startup => "startup".to_owned(),
+    };
+    format!("[{} {origin}]", session_time(line.printed_at))
+}
+
+/// Time since startup, like `00:01:02.345`.
+fn session_time(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+/// Date and time in UTC, like `2024-03-09 14:03:27 UTC`.
+pub(crate) fn utc_date_time(time: SystemTime) -> String {
+    let since_epoch = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, seconds) = (since_epoch / 86_400, since_epoch % 86_400);
+
+    // Civil date from the days since 1970-01-01, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,21 @@
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::ConsoleCommand;
+
+/// Prints its arguments
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "echo")]
+pub(crate) struct EchoCommand {
+    /// Text to print, arguments are joined by spaces
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    text: Vec<String>,
+}
+
+pub(crate) fn echo_command(mut echo: ConsoleCommand<EchoCommand>) {
+    while let Some(result) = echo.take() {
+        if let Ok(EchoCommand { text }) = result {
+            echo.reply(text.join(" "));
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+use clap::builder::styling::AnsiColor;
+
+use crate::console::{colored, CommandOrigin, CommandQueue};
+use crate::script::{queue_script, ExecCommand};
+use crate::{ConsoleCommand, ConsoleConfiguration};
+
+pub(crate) fn exec_command(
+    mut exec: ConsoleCommand<ExecCommand>,
+    config: Res<ConsoleConfiguration>,
+    mut queue: ResMut<CommandQueue>,
+) {
+    while let Some(result) = exec.take() {
+        if let Ok(ExecCommand { path, strict }) = result {
+            let origin = exec.entered().map(|entered| entered.origin.script());
+            let origin = origin.unwrap_or(CommandOrigin::Script);
+            let localization = &config.localization;
+            if let Err(err) = queue_script(&path, strict, origin, localization, &mut queue) {
+                exec.reply_failed(colored(AnsiColor::Red, &err));
+            }
+        }
+    }
+}
@@ -1,21 +1,43 @@
+use std::time::Duration;
+
 use bevy::app::AppExit;
 use bevy::prelude::*;
 use clap::Parser;
 
 use crate as bevy_console;
-use crate::ConsoleCommand;
+use crate::localization::fill;
+use crate::{ConsoleCommand, ConsoleConfiguration};
+
+/// Time to confirm `exit` in by entering it again or pressing enter.
+const CONFIRMATION_WINDOW: Duration = Duration::from_secs(5);
 
 /// Exits the app
 #[derive(Parser, ConsoleCommand)]
-#[command(name = "exit")]
-pub(crate) struct ExitCommand;
+#[command(name = "exit", visible_alias = "quit")]
+pub(crate) struct ExitCommand {
+    /// Exit without asking for confirmation
+    #[arg(long)]
+    force: bool,
+}
 
 pub(crate) fn exit_command(
     mut exit: ConsoleCommand<ExitCommand>,
     mut exit_writer: EventWriter<AppExit>,
+    config: Res<ConsoleConfiguration>,
 ) {
-    if let Some(Ok(_)) = exit.take() {
-        exit_writer.send(AppExit);
-        exit.ok();
+    while let Some(result) = exit.take() {
+        let Ok(ExitCommand { force }) = result else {
+            continue;
+        };
+        let seconds = CONFIRMATION_WINDOW.as_secs();
+        let prompt = fill(&config.localization.exit_confirm, &[("seconds", &seconds)]);
+        if force
+            || exit
+                .confirm_repeated(prompt, CONFIRMATION_WINDOW)
+                .is_confirmed()
+        {
+            exit_writer.send(AppExit);
+            exit.ok();
+        }
     }
 }
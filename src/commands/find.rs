@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use clap::builder::styling::AnsiColor;
+use clap::Parser;
+use regex::{Regex, RegexBuilder};
+
+use crate as bevy_console;
+use crate::localization::fill;
+use crate::{reply, ConsoleCommand, ConsoleConfiguration, ConsoleVariables};
+
+/// Searches the names, aliases and descriptions of commands, and console variables
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "find", visible_alias = "apropos")]
+pub(crate) struct FindCommand {
+    /// Text to search for, ignoring case
+    pattern: String,
+    /// Search with a regular expression instead of plain text
+    #[arg(long)]
+    regex: bool,
+}
+
+pub(crate) fn find_command(
+    mut find: ConsoleCommand<FindCommand>,
+    config: Res<ConsoleConfiguration>,
+    cvars: Res<ConsoleVariables>,
+) {
+    while let Some(result) = find.take() {
+        let Ok(FindCommand { pattern, regex }) = result else {
+            continue;
+        };
+        let source = if regex {
+            pattern.clone()
+        } else {
+            regex::escape(&pattern)
+        };
+        let matcher = match RegexBuilder::new(&source).case_insensitive(true).build() {
+            Ok(matcher) => matcher,
+            Err(err) => {
+                let message = fill(
+                    &config.localization.find_invalid_pattern,
+                    &[("pattern", &pattern), ("error", &err)],
+                );
+                find.reply_failed(message);
+                continue;
+            }
+        };
+
+        let mut found = false;
+        for (name, registered) in &config.commands {
+            if registered.meta.hidden {
+                continue;
+            }
+            let aliases = config.aliases_of(name);
+            let about = registered
+                .command
+                .get_about()
+                .map(|about| about.to_string())
+                .unwrap_or_default();
+            let long_about = registered
+                .command
+                .get_long_about()
+                .map(|about| about.to_string())
+                .unwrap_or_default();
+            // Only the lines of the long description which match are shown
+            let long_matches = long_about
+                .lines()
+                .filter(|line| matcher.is_match(line))
+                .collect::<Vec<_>>();
+            let matched = matcher.is_match(name)
+                || aliases.iter().any(|alias| matcher.is_match(alias))
+                || matcher.is_match(&about)
+                || !long_matches.is_empty();
+            if !matched {
+                continue;
+            }
+
+            found = true;
+            let mut label = highlight(&matcher, name);
+            if !aliases.is_empty() {
+                let aliases = aliases
+                    .iter()
+                    .map(|alias| highlight(&matcher, alias))
+                    .collect::<Vec<_>>();
+                label.push_str(&format!(" ({})", aliases.join(", ")));
+            }
+            reply!(find, "  {label} - {}", highlight(&matcher, &about));
+            for line in long_matches {
+                reply!(find, "      {}", highlight(&matcher, line.trim()));
+            }
+        }
+        for (name, value) in cvars.iter() {
+            if matcher.is_match(name) {
+                found = true;
+                reply!(find, "  {} = {value}", highlight(&matcher, name));
+            }
+        }
+
+        if !found {
+            let message = fill(&config.localization.find_no_match, &[("pattern", &pattern)]);
+            find.reply_failed(message);
+        }
+    }
+}
+
+/// Highlights the parts of `text` matched by `matcher`.
+fn highlight(matcher: &Regex, text: &str) -> String {
+    let style = AnsiColor::Yellow.on_default().bold();
+    matcher
+        .replace_all(text, |captures: &regex::Captures| {
+            format!("{}{}{}", style.render(), &captures[0], style.render_reset())
+        })
+        .into_owned()
+}
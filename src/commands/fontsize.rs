@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::localization::fill;
+use crate::{ConsoleCommand, ConsoleConfiguration};
+
+/// Prints or changes the console font size
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "fontsize")]
+pub(crate) struct FontSizeCommand {
+    /// New font size in points
+    size: Option<f32>,
+}
+
+pub(crate) fn fontsize_command(
+    mut fontsize: ConsoleCommand<FontSizeCommand>,
+    mut config: ResMut<ConsoleConfiguration>,
+) {
+    while let Some(result) = fontsize.take() {
+        match result {
+            Ok(FontSizeCommand { size: Some(size) }) => {
+                if size.is_finite() && size > 0.0 {
+                    config.font_size = size;
+                    let message = fill(&config.localization.font_size_set, &[("size", &size)]);
+                    fontsize.reply_ok(message);
+                } else {
+                    fontsize.reply_failed(config.localization.font_size_invalid.clone());
+                }
+            }
+            Ok(FontSizeCommand { size: None }) => {
+                let size = config.font_size;
+                fontsize.reply(fill(&config.localization.font_size, &[("size", &size)]));
+            }
+            Err(_) => {}
+        }
+    }
+}
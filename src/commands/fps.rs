@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin,
+};
+use bevy::prelude::*;
+use clap::{Parser, Subcommand};
+
+use crate as bevy_console;
+use crate::localization::fill;
+use crate::{
+    ConsoleCommand, ConsoleConfiguration, ConsoleLocalization, NamedCommand, PrintConsoleLine,
+    Progress, ResetConsole,
+};
+
+/// Key of the line updated by `fps watch`
+const WATCH_KEY: &str = "watch";
+
+/// Prints the frame rate and frame time
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "fps")]
+pub(crate) struct FpsCommand {
+    #[command(subcommand)]
+    mode: Option<FpsMode>,
+}
+
+#[derive(Subcommand)]
+enum FpsMode {
+    /// Prints the frame rate every second until `fps stop`
+    Watch,
+    /// Stops `fps watch`
+    Stop,
+}
+
+/// When `fps watch` next updates its line, while it runs.
+#[derive(Default, Resource)]
+pub(crate) struct FpsWatch {
+    next: Option<Duration>,
+}
+
+pub(crate) fn fps_command(
+    mut fps: ConsoleCommand<FpsCommand>,
+    diagnostics: Option<Res<DiagnosticsStore>>,
+    mut watch: ResMut<FpsWatch>,
+    config: Res<ConsoleConfiguration>,
+    time: Res<Time<Real>>,
+) {
+    let localization = &config.localization;
+    while let Some(result) = fps.take() {
+        let Ok(FpsCommand { mode }) = result else {
+            continue;
+        };
+        let frame_times = diagnostics.as_deref().and_then(|diagnostics| {
+            diagnostics
+                .get(FrameTimeDiagnosticsPlugin::FPS)
+                .zip(diagnostics.get(FrameTimeDiagnosticsPlugin::FRAME_TIME))
+        });
+        let Some((fps_diagnostic, frame_time)) = frame_times else {
+            fps.reply_failed(localization.fps_unmeasured.clone());
+            continue;
+        };
+        match mode {
+            None => {
+                let rate = summary(fps_diagnostic, "", localization);
+                fps.reply(fill(&localization.fps_rate, &[("summary", &rate)]));
+                let frame_time = summary(frame_time, " ms", localization);
+                fps.reply(fill(
+                    &localization.fps_frame_time,
+                    &[("summary", &frame_time)],
+                ));
+                let entities = diagnostics
+                    .as_deref()
+                    .and_then(|diagnostics| {
+                        diagnostics.get(EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+                    })
+                    .and_then(Diagnostic::value);
+                if let Some(entities) = entities {
+                    fps.reply(fill(&localization.fps_entities, &[("count", &entities)]));
+                }
+            }
+            Some(FpsMode::Watch) => {
+                watch.next = Some(time.elapsed());
+            }
+            Some(FpsMode::Stop) => match watch.next.take() {
+                Some(_) => {
+                    let line = watch_line(diagnostics.as_deref(), localization).unwrap_or_default();
+                    fps.reply_progress_done(WATCH_KEY, line);
+                    fps.ok();
+                }
+                None => fps.reply(localization.fps_not_watching.clone()),
+            },
+        }
+    }
+}
+
+/// Updates the line of `fps watch` every second.
+pub(crate) fn fps_watch(
+    mut watch: ResMut<FpsWatch>,
+    diagnostics: Option<Res<DiagnosticsStore>>,
+    config: Res<ConsoleConfiguration>,
+    time: Res<Time<Real>>,
+    mut console_line: EventWriter<PrintConsoleLine>,
+) {
+    let Some(next) = watch.next else {
+        return;
+    };
+    if time.elapsed() < next {
+        return;
+    }
+    watch.next = Some(time.elapsed() + Duration::from_secs(1));
+    if let Some(line) = watch_line(diagnostics.as_deref(), &config.localization) {
+        let line = PrintConsoleLine::new(line.into())
+            .with_source(FpsCommand::name())
+            .with_progress(Progress::Update(WATCH_KEY.to_owned()));
+        console_line.send(line);
+    }
+}
+
+/// Stops `fps watch` when the console is reset.
+pub(crate) fn stop_fps_watch(mut resets: EventReader<ResetConsole>, mut watch: ResMut<FpsWatch>) {
+    if resets.read().count() > 0 {
+        watch.next = None;
+    }
+}
+
+/// Current value of a diagnostic followed by its average, minimum and maximum.
+fn summary(diagnostic: &Diagnostic, unit: &str, localization: &ConsoleLocalization) -> String {
+    let value =
+        |value: Option<f64>| value.map_or("-".to_owned(), |value| format!("{value:.2}{unit}"));
+    let min = diagnostic.values().copied().reduce(f64::min);
+    let max = diagnostic.values().copied().reduce(f64::max);
+    fill(
+        &localization.fps_summary,
+        &[
+            ("value", &value(diagnostic.value())),
+            ("average", &value(diagnostic.average())),
+            ("min", &value(min)),
+            ("max", &value(max)),
+        ],
+    )
+}
+
+/// Single line summary updated by `fps watch`.
+fn watch_line(
+    diagnostics: Option<&DiagnosticsStore>,
+    localization: &ConsoleLocalization,
+) -> Option<String> {
+    let diagnostics = diagnostics?;
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)?
+        .smoothed()?;
+    let frame_time = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FRAME_TIME)?
+        .smoothed()?;
+    let mut line = fill(
+        &localization.fps_watch,
+        &[
+            ("fps", &format!("{fps:.1}")),
+            ("millis", &format!("{frame_time:.2}")),
+        ],
+    );
+    let entities = diagnostics
+        .get(EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+        .and_then(Diagnostic::value);
+    if let Some(entities) = entities {
+        line.push_str(&localization.fps_watch_entities.fill(entities as usize, &[]));
+    }
+    Some(line)
+}
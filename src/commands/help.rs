@@ -2,51 +2,133 @@ use bevy::prelude::*;
 use clap::Parser;
 
 use crate as bevy_console;
-use crate::{reply, ConsoleCommand, ConsoleConfiguration};
+use crate::localization::fill;
+use crate::{reply, ConsoleCommand, ConsoleConfiguration, ConsoleVariables};
 
 /// Prints available arguments and usage
 #[derive(Parser, ConsoleCommand)]
 #[command(name = "help")]
 pub(crate) struct HelpCommand {
-    /// Help for a given command
-    command: Option<String>,
+    /// Help for a given command or subcommand, e.g. `help entity spawn`
+    command: Vec<String>,
 }
 
 pub(crate) fn help_command(
     mut help: ConsoleCommand<HelpCommand>,
-    mut config: ResMut<ConsoleConfiguration>,
+    config: Res<ConsoleConfiguration>,
+    cvars: Res<ConsoleVariables>,
 ) {
-    match help.take() {
-        Some(Ok(HelpCommand { command: Some(cmd) })) => match config.commands.get_mut(cmd.as_str())
-        {
-            Some(command_info) => {
-                help.reply(command_info.render_long_help().to_string());
+    let localization = &config.localization;
+    while let Some(result) = help.take() {
+        match result {
+            Ok(HelpCommand { command }) if !command.is_empty() => {
+                let cmd = &command[0];
+                let registered = config
+                    .resolve_command(cmd)
+                    .and_then(|name| config.commands.get(name));
+                match registered {
+                    Some(registered) => {
+                        // Subcommands only know their full name once their parent is built
+                        let mut clap_command = registered.command.clone();
+                        clap_command.build();
+                        let subcommand = command[1..]
+                            .iter()
+                            .try_fold(&mut clap_command, |clap_command, name| {
+                                clap_command.find_subcommand_mut(name)
+                            });
+                        match subcommand {
+                            Some(subcommand) => help.reply(subcommand.render_long_help()),
+                            None => help.reply(fill(
+                                &localization.help_no_such_subcommand,
+                                &[("name", &command[1..].join(" ")), ("command", cmd)],
+                            )),
+                        }
+                    }
+                    None => match cvars.display(cmd) {
+                        Some(value) => {
+                            help.reply(fill(
+                                &localization.help_console_variable,
+                                &[("value", &value)],
+                            ));
+                            help.reply(fill(&localization.help_variable_usage, &[("name", cmd)]));
+                        }
+                        None => {
+                            help.reply(fill(&localization.help_no_such_command, &[("name", cmd)]));
+                        }
+                    },
+                }
             }
-            None => {
-                reply!(help, "Command '{}' does not exist", cmd);
+            Ok(HelpCommand { .. }) => {
+                debug!("No command received in help");
+                help.reply(localization.help_commands.clone());
+                let commands = config
+                    .commands
+                    .iter()
+                    .filter(|(_, registered)| !registered.meta.hidden)
+                    .collect::<Vec<_>>();
+                let labels = commands
+                    .iter()
+                    .map(|(name, _)| {
+                        let aliases = config
+                            .aliases_of(name)
+                            .into_iter()
+                            .filter(|alias| !config.deprecated_aliases.contains(*alias))
+                            .collect::<Vec<_>>();
+                        match aliases.as_slice() {
+                            [] => name.to_string(),
+                            aliases => format!("{name} ({})", aliases.join(", ")),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let longest_label = labels.iter().map(String::len).max().unwrap_or(0);
+                for (label, (_, registered)) in labels.iter().zip(&commands) {
+                    let mut line = format!("  {label}{}", " ".repeat(longest_label - label.len()));
+                    line.push_str(&format!(
+                        " - {}",
+                        registered
+                            .command
+                            .get_about()
+                            .map(|about| about.to_string())
+                            .unwrap_or_default()
+                    ));
+                    help.reply(line);
+                    let subcommands = registered
+                        .command
+                        .get_subcommands()
+                        .filter(|subcommand| !subcommand.is_hide_set());
+                    for subcommand in subcommands {
+                        let about = subcommand
+                            .get_about()
+                            .map(|about| format!(" - {about}"))
+                            .unwrap_or_default();
+                        reply!(help, "      {}{about}", subcommand.get_name());
+                    }
+                }
+                let deprecated = config
+                    .deprecated_aliases
+                    .iter()
+                    .filter_map(|alias| Some((alias, config.deprecation(alias)?)))
+                    .collect::<Vec<_>>();
+                if !deprecated.is_empty() {
+                    help.reply(localization.help_deprecated_aliases.clone());
+                    for (alias, command) in deprecated {
+                        reply!(help, "  {alias} -> {command}");
+                    }
+                }
+                if cvars.names().next().is_some() || cvars.resource_prefixes().next().is_some() {
+                    help.reply(localization.help_console_variables.clone());
+                    for (name, value) in cvars.iter() {
+                        reply!(help, "  {name} = {value}");
+                    }
+                    for (prefix, type_name) in cvars.resource_prefixes() {
+                        let fields =
+                            fill(&localization.help_resource_fields, &[("type", &type_name)]);
+                        reply!(help, "  {prefix}.* - {fields}");
+                    }
+                }
+                help.reply("");
             }
-        },
-        Some(Ok(HelpCommand { command: None })) => {
-            debug!("No command received in help");
-            reply!(help, "Available commands:");
-            let longest_command_name = config
-                .commands
-                .keys()
-                .map(|name| name.len())
-                .max()
-                .unwrap_or(0);
-            for (name, cmd) in &config.commands {
-                let mut line = format!("  {name}{}", " ".repeat(longest_command_name - name.len()));
-                line.push_str(&format!(
-                    " - {}",
-                    cmd.get_about()
-                        .map(|about| about.to_string())
-                        .unwrap_or_default()
-                ));
-                help.reply(line);
-            }
-            help.reply("");
+            Err(_) => {}
         }
-        _ => {}
     }
 }
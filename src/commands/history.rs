@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::console::{CommandQueue, ConsoleState};
+use crate::localization::fill;
+use crate::{reply, ConsoleCommand, ConsoleConfiguration};
+
+/// Lists the history, most recent last, or clears it or runs one of its entries
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "history")]
+pub(crate) struct HistoryCommand {
+    /// `clear` to clear the history, or the number of an entry to run it
+    action: Option<String>,
+}
+
+pub(crate) fn history_command(
+    mut history: ConsoleCommand<HistoryCommand>,
+    mut state: ResMut<ConsoleState>,
+    mut queue: ResMut<CommandQueue>,
+    config: Res<ConsoleConfiguration>,
+) {
+    while let Some(result) = history.take() {
+        let Ok(HistoryCommand { action }) = result else {
+            continue;
+        };
+        match action.as_deref() {
+            None => {
+                for (n, entry) in state.numbered_history() {
+                    reply!(history, "{n:>4}  {entry}");
+                }
+            }
+            Some("clear") => {
+                state.clear_history();
+                history.ok();
+            }
+            Some(action) => {
+                let entry = action
+                    .parse()
+                    .ok()
+                    .and_then(|n| state.history_entry_before_input(n));
+                match entry {
+                    Some(entry) => queue.lines.push_back(entry.into()),
+                    None => history.reply_failed(fill(
+                        &config.localization.history_no_entry,
+                        &[("entry", &action)],
+                    )),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+use clap::{Parser, ValueEnum};
+
+use crate as bevy_console;
+use crate::{ConsoleCommand, ConsoleConfiguration, ConsoleLayout};
+
+/// Switches the console layout
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "console_layout")]
+pub(crate) struct ConsoleLayoutCommand {
+    /// Layout to switch to
+    #[arg(value_enum)]
+    layout: LayoutChoice,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LayoutChoice {
+    Window,
+    TopPanel,
+    BottomPanel,
+    Mini,
+}
+
+pub(crate) fn console_layout_command(
+    mut layout: ConsoleCommand<ConsoleLayoutCommand>,
+    mut config: ResMut<ConsoleConfiguration>,
+) {
+    while let Some(result) = layout.take() {
+        if let Ok(ConsoleLayoutCommand { layout: choice }) = result {
+            config.layout = match choice {
+                LayoutChoice::Window => ConsoleLayout::Window,
+                LayoutChoice::TopPanel => ConsoleLayout::TopPanel,
+                LayoutChoice::BottomPanel => ConsoleLayout::BottomPanel,
+                LayoutChoice::Mini => ConsoleLayout::Mini,
+            };
+            layout.ok();
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use bevy::utils::tracing::level_filters::LevelFilter;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+
+use crate as bevy_console;
+use crate::localization::fill;
+use crate::{reply, ConsoleCommand, ConsoleConfiguration, ConsoleLogFilter, NamedCommand};
+
+/// Prints or changes which captured log events are printed
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "loglevel")]
+pub(crate) struct LogLevelCommand {
+    /// Level like `warn`, a target followed by its level like `my_game::net debug`, or
+    /// directives like `warn,my_game::net=debug`
+    #[arg(value_parser = parse_directives)]
+    filter: Option<Directives>,
+    /// Level of the target
+    #[arg(value_parser = parse_level)]
+    level: Option<LevelFilter>,
+}
+
+/// Comma separated `level` and `target=level` directives, like with `RUST_LOG`.
+///
+/// A single target may leave out its level, which then follows as its own argument.
+#[derive(Clone, Debug)]
+struct Directives(Vec<(Option<String>, Option<LevelFilter>)>);
+
+fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .parse()
+        .map_err(|_| "expected one of off, error, warn, info, debug, trace".to_owned())
+}
+
+fn parse_directives(filter: &str) -> Result<Directives, String> {
+    let parts = filter.split(',').map(str::trim).collect::<Vec<_>>();
+    let single = parts.len() == 1;
+    let directives = parts
+        .into_iter()
+        .map(|part| match part.split_once('=') {
+            Some((target, level)) => Ok((Some(parse_target(target)?), Some(parse_level(level)?))),
+            None if single => match parse_level(part) {
+                Ok(level) => Ok((None, Some(level))),
+                Err(_) => Ok((Some(parse_target(part)?), None)),
+            },
+            None => Ok((None, Some(parse_level(part)?))),
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(Directives(directives))
+}
+
+fn parse_target(target: &str) -> Result<String, String> {
+    let valid = target
+        .split("::")
+        .all(|name| !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_'));
+    if valid {
+        Ok(target.to_owned())
+    } else {
+        Err(format!("'{target}' isn't a level or a module path"))
+    }
+}
+
+/// Builds an error like the ones of clap, for arguments which parsed but don't fit together.
+fn filter_error(message: String) -> clap::Error {
+    LogLevelCommand::command()
+        .name(LogLevelCommand::name())
+        .error(ErrorKind::ArgumentConflict, message)
+}
+
+pub(crate) fn loglevel_command(
+    mut loglevel: ConsoleCommand<LogLevelCommand>,
+    mut filter: ResMut<ConsoleLogFilter>,
+    config: Res<ConsoleConfiguration>,
+) {
+    let localization = &config.localization;
+    while let Some(result) = loglevel.take() {
+        match result {
+            Ok(LogLevelCommand {
+                filter: Some(Directives(directives)),
+                level,
+            }) => {
+                let directives = match (directives.as_slice(), level) {
+                    ([(Some(target), None)], Some(level)) => vec![(Some(target.clone()), level)],
+                    ([(Some(target), None)], None) => {
+                        let error = filter_error(fill(
+                            &localization.log_target_without_level,
+                            &[("target", target)],
+                        ));
+                        loglevel.reply(error.render());
+                        continue;
+                    }
+                    (_, Some(_)) => {
+                        let error = filter_error(localization.log_level_without_target.clone());
+                        loglevel.reply(error.render());
+                        continue;
+                    }
+                    (directives, None) => directives
+                        .iter()
+                        .filter_map(|(target, level)| Some((target.clone(), (*level)?)))
+                        .collect(),
+                };
+                for (target, level) in directives {
+                    match target {
+                        Some(target) => {
+                            loglevel.reply(fill(
+                                &localization.log_target_set,
+                                &[("target", &target), ("level", &level)],
+                            ));
+                            filter.modules.insert(target, level);
+                        }
+                        None => {
+                            loglevel.reply(fill(&localization.log_level_set, &[("level", &level)]));
+                            filter.level = level;
+                        }
+                    }
+                }
+                loglevel.ok();
+            }
+            Ok(LogLevelCommand { .. }) => {
+                loglevel.reply(fill(&localization.log_level, &[("level", &filter.level)]));
+                for (target, level) in &filter.modules {
+                    reply!(loglevel, "  {target} = {level}");
+                }
+            }
+            Err(_) => {}
+        }
+    }
+}
@@ -1,3 +1,145 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+
+use crate::commands::alias::{alias_command, unalias_command, AliasCommand, UnaliasCommand};
+use crate::commands::autoexec::{autoexec_command, AutoexecCommand};
+use crate::commands::bind::{
+    bind_command, bindlist_command, shortcuts_command, unbind_command, BindCommand,
+    BindListCommand, ShortcutsCommand, UnbindCommand,
+};
+use crate::commands::clear::{clear_command, ClearCommand};
+#[cfg(feature = "serde")]
+use crate::commands::config::{console_config_command, ConsoleConfigCommand};
+#[cfg(feature = "egui")]
+use crate::commands::copy::{copy_command, CopyCommand};
+use crate::commands::cvar_helpers::{
+    dec_command, inc_command, reset_command, toggle_command, DecCommand, IncCommand, ResetCommand,
+    ToggleCommand,
+};
+use crate::commands::cvarlist::{cvarlist_command, CvarListCommand};
+use crate::commands::dump::{dump_command, DumpCommand};
+use crate::commands::echo::{echo_command, EchoCommand};
+use crate::commands::exec::exec_command;
+use crate::commands::exit::{exit_command, ExitCommand};
+use crate::commands::find::{find_command, FindCommand};
+use crate::commands::fontsize::{fontsize_command, FontSizeCommand};
+use crate::commands::fps::{fps_command, fps_watch, stop_fps_watch, FpsCommand, FpsWatch};
+use crate::commands::help::{help_command, HelpCommand};
+use crate::commands::history::{history_command, HistoryCommand};
+use crate::commands::layout::{console_layout_command, ConsoleLayoutCommand};
+#[cfg(feature = "log_capture")]
+use crate::commands::loglevel::{loglevel_command, LogLevelCommand};
+use crate::commands::stats::{stats_command, StatsCommand};
+#[cfg(feature = "egui")]
+use crate::commands::theme::{theme_command, ThemeCommand};
+use crate::commands::timing::{timing_command, TimingCommand};
+use crate::commands::version::{version_command, VersionCommand};
+use crate::script::{run_autoexec, ExecCommand};
+use crate::{Command, ConsoleConfiguration, ConsoleSet};
+
+pub(crate) mod alias;
+pub(crate) mod autoexec;
+pub(crate) mod bind;
 pub(crate) mod clear;
+#[cfg(feature = "serde")]
+pub(crate) mod config;
+#[cfg(feature = "egui")]
+pub(crate) mod copy;
+pub(crate) mod cvar_helpers;
+pub(crate) mod cvarlist;
+pub(crate) mod dump;
+pub(crate) mod echo;
+pub(crate) mod exec;
 pub(crate) mod exit;
+pub(crate) mod find;
+pub(crate) mod fontsize;
+pub(crate) mod fps;
 pub(crate) mod help;
+pub(crate) mod history;
+pub(crate) mod layout;
+#[cfg(feature = "log_capture")]
+pub(crate) mod loglevel;
+pub(crate) mod stats;
+#[cfg(feature = "egui")]
+pub(crate) mod theme;
+pub(crate) mod timing;
+pub(crate) mod version;
+
+/// Names of the built-in commands which were registered, and whose systems handle them.
+#[derive(Default, Resource)]
+struct BuiltinCommands {
+    names: BTreeSet<&'static str>,
+}
+
+/// Registers the built-in console commands, enabled by the `default_commands` feature.
+pub(crate) fn add_default_commands(app: &mut App) {
+    app.init_resource::<BuiltinCommands>();
+    add_builtin_command::<AliasCommand, _>(app, alias_command);
+    add_builtin_command::<UnaliasCommand, _>(app, unalias_command);
+    add_builtin_command::<AutoexecCommand, _>(app, autoexec_command);
+    add_builtin_command::<BindCommand, _>(app, bind_command);
+    add_builtin_command::<BindListCommand, _>(app, bindlist_command);
+    add_builtin_command::<ClearCommand, _>(app, clear_command);
+    #[cfg(feature = "serde")]
+    add_builtin_command::<ConsoleConfigCommand, _>(app, console_config_command);
+    #[cfg(feature = "egui")]
+    add_builtin_command::<CopyCommand, _>(app, copy_command);
+    add_builtin_command::<CvarListCommand, _>(app, cvarlist_command);
+    add_builtin_command::<DecCommand, _>(app, dec_command);
+    add_builtin_command::<DumpCommand, _>(app, dump_command);
+    add_builtin_command::<EchoCommand, _>(app, echo_command);
+    add_builtin_command::<ExecCommand, _>(app, exec_command);
+    add_builtin_command::<ExitCommand, _>(app, exit_command);
+    add_builtin_command::<FindCommand, _>(app, find_command);
+    add_builtin_command::<FontSizeCommand, _>(app, fontsize_command);
+    add_builtin_command::<FpsCommand, _>(app, fps_command);
+    add_builtin_command::<HelpCommand, _>(app, help_command);
+    add_builtin_command::<HistoryCommand, _>(app, history_command);
+    add_builtin_command::<IncCommand, _>(app, inc_command);
+    add_builtin_command::<ConsoleLayoutCommand, _>(app, console_layout_command);
+    #[cfg(feature = "log_capture")]
+    add_builtin_command::<LogLevelCommand, _>(app, loglevel_command);
+    add_builtin_command::<ResetCommand, _>(app, reset_command);
+    add_builtin_command::<ShortcutsCommand, _>(app, shortcuts_command);
+    add_builtin_command::<StatsCommand, _>(app, stats_command);
+    #[cfg(feature = "egui")]
+    add_builtin_command::<ThemeCommand, _>(app, theme_command);
+    add_builtin_command::<TimingCommand, _>(app, timing_command);
+    add_builtin_command::<ToggleCommand, _>(app, toggle_command);
+    add_builtin_command::<UnbindCommand, _>(app, unbind_command);
+    add_builtin_command::<VersionCommand, _>(app, version_command);
+
+    // Updates the line of `fps watch` on frames without commands
+    app.init_resource::<FpsWatch>().add_systems(
+        Update,
+        (stop_fps_watch, fps_watch)
+            .chain()
+            .in_set(ConsoleSet::PostCommands),
+    );
+}
+
+/// Adds a built-in command like [`AddConsoleCommand::add_console_command`], unless it's disabled
+/// in [`ConsoleConfiguration::disabled_commands`] or the app registers a command of its own with
+/// the same name.
+///
+/// Built-ins are registered after the app's commands, so they can tell if they are overridden.
+///
+/// [`AddConsoleCommand::add_console_command`]: crate::AddConsoleCommand::add_console_command
+fn add_builtin_command<T: Command, Params>(app: &mut App, system: impl IntoSystemConfigs<Params>) {
+    let register = |mut config: ResMut<ConsoleConfiguration>,
+                    mut builtins: ResMut<BuiltinCommands>| {
+        let name = T::name();
+        if !config.is_builtin_disabled(name) && !config.commands.contains_key(name) {
+            config.register_console_command::<T>();
+            builtins.names.insert(name);
+        }
+    };
+    let registered = |builtins: Res<BuiltinCommands>| builtins.names.contains(T::name());
+
+    app.add_systems(PostStartup, register.before(run_autoexec))
+        .add_systems(
+            Update,
+            system.run_if(registered).in_set(ConsoleSet::Commands),
+        );
+}
@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::localization::fill;
+use crate::{ConsoleCommand, ConsoleCommandStats, ConsoleConfiguration};
+
+/// Lists how often each command was entered, most used first
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "stats")]
+pub(crate) struct StatsCommand;
+
+pub(crate) fn stats_command(
+    mut stats: ConsoleCommand<StatsCommand>,
+    usage: Res<ConsoleCommandStats>,
+    config: Res<ConsoleConfiguration>,
+    time: Res<Time<Real>>,
+) {
+    let localization = &config.localization;
+    while let Some(result) = stats.take() {
+        if result.is_err() {
+            continue;
+        }
+        let mut commands = usage.commands.iter().collect::<Vec<_>>();
+        commands.sort_by(|(a_name, a), (b_name, b)| {
+            (b.invocations + b.parse_failures)
+                .cmp(&(a.invocations + a.parse_failures))
+                .then_with(|| a_name.cmp(b_name))
+        });
+        if commands.is_empty() {
+            stats.reply(localization.stats_empty.clone());
+            continue;
+        }
+        let rows = commands.into_iter().map(|(name, usage)| {
+            let ago = time.elapsed().saturating_sub(usage.last_used);
+            [
+                name.clone(),
+                usage.invocations.to_string(),
+                usage.parse_failures.to_string(),
+                fill(
+                    &localization.stats_last_used,
+                    &[("seconds", &format!("{:.0}", ago.as_secs_f32()))],
+                ),
+            ]
+        });
+        stats.reply_table(localization.stats_headers.clone(), rows);
+    }
+}
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+use clap::{Parser, ValueEnum};
+
+use crate as bevy_console;
+use crate::{ConsoleCommand, ConsoleConfiguration, ConsoleTheme};
+
+/// Switches the console color theme
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "theme")]
+pub(crate) struct ThemeCommand {
+    /// Theme to switch to
+    #[arg(value_enum)]
+    theme: ThemePreset,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ThemePreset {
+    Dark,
+    Light,
+    HighContrast,
+}
+
+pub(crate) fn theme_command(
+    mut theme: ConsoleCommand<ThemeCommand>,
+    mut config: ResMut<ConsoleConfiguration>,
+) {
+    while let Some(result) = theme.take() {
+        if let Ok(ThemeCommand { theme: preset }) = result {
+            config.theme = match preset {
+                ThemePreset::Dark => ConsoleTheme::dark(),
+                ThemePreset::Light => ConsoleTheme::light(),
+                ThemePreset::HighContrast => ConsoleTheme::high_contrast(),
+            };
+            theme.ok();
+        }
+    }
+}
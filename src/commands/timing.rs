@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+use clap::{Parser, ValueEnum};
+
+use crate as bevy_console;
+use crate::{ConsoleCommand, ConsoleConfiguration};
+
+/// Prints how long commands take after running them
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "timing")]
+pub(crate) struct TimingCommand {
+    /// Turn timings on or off, toggles them if left out
+    #[arg(value_enum)]
+    state: Option<TimingState>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TimingState {
+    On,
+    Off,
+}
+
+pub(crate) fn timing_command(
+    mut timing: ConsoleCommand<TimingCommand>,
+    mut config: ResMut<ConsoleConfiguration>,
+) {
+    while let Some(result) = timing.take() {
+        if let Ok(TimingCommand { state }) = result {
+            config.show_timings = match state {
+                Some(TimingState::On) => true,
+                Some(TimingState::Off) => false,
+                None => !config.show_timings,
+            };
+            let localization = &config.localization;
+            let message = if config.show_timings {
+                &localization.timings_on
+            } else {
+                &localization.timings_off
+            };
+            timing.reply_ok(message.clone());
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::{ConsoleCommand, ConsoleConfiguration};
+
+/// Prints the name and version of the app
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "version")]
+pub(crate) struct VersionCommand;
+
+pub(crate) fn version_command(
+    mut version: ConsoleCommand<VersionCommand>,
+    config: Res<ConsoleConfiguration>,
+) {
+    while let Some(result) = version.take() {
+        if result.is_ok() {
+            let unknown = config.localization.unknown_version.as_str();
+            let app_version = config.app_version.as_deref().unwrap_or(unknown);
+            version.reply(app_version.to_owned());
+        }
+    }
+}
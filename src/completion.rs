@@ -0,0 +1,423 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::iter::Peekable;
+use std::marker::PhantomData;
+use std::vec;
+
+use bevy::ecs::component::Tick;
+use bevy::prelude::*;
+
+use crate::console::ConsoleConfiguration;
+use crate::instance::{ConsoleMarker, DefaultConsole};
+use crate::ConsoleVariables;
+
+/// Default of [`CompletionIndex::budget`]
+const DEFAULT_INDEX_BUDGET: usize = 2048;
+
+/// Names completed at the start of the input line of the console `M`, indexed ahead of time so
+/// completing a keystroke only searches the index.
+///
+/// Candidates come from sources registered with a version, see
+/// [`CompletionIndex::set_source`]. The console registers its commands as the source `commands`
+/// and the console variables as `variables`, apps can add others, e.g. the names of their
+/// entities. When a source changes, the index is rebuilt over the following frames, indexing
+/// [`CompletionIndex::budget`] candidates per frame, and completion keeps using the previous
+/// index until it's done.
+///
+/// Candidates are matched ignoring case, by prefix or else by the typed characters in order.
+///
+/// # Example
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_console::CompletionIndex;
+/// # #[derive(Component)]
+/// # struct Prop;
+/// fn index_props(mut index: ResMut<CompletionIndex>, props: Query<&Name, With<Prop>>) {
+///     let version = props.iter().len() as u64;
+///     index.set_source("props", version, || props.iter().map(|name| name.to_string()).collect());
+/// }
+/// ```
+#[derive(Resource)]
+pub struct CompletionIndex<M = DefaultConsole> {
+    /// Candidates indexed per frame while the index is rebuilt
+    pub budget: usize,
+    sources: BTreeMap<String, CompletionSource>,
+    /// Candidates of all sources as of the last finished rebuild
+    index: Candidates,
+    rebuild: Option<Rebuild>,
+    marker: PhantomData<M>,
+}
+
+impl<M> Default for CompletionIndex<M> {
+    fn default() -> Self {
+        Self {
+            budget: DEFAULT_INDEX_BUDGET,
+            sources: BTreeMap::new(),
+            index: Candidates::default(),
+            rebuild: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Candidates registered by a source, see [`CompletionIndex::set_source`].
+struct CompletionSource {
+    version: u64,
+    candidates: Vec<String>,
+}
+
+impl<M> CompletionIndex<M> {
+    /// Registers the candidates of the source `name`, replacing those of another `version`.
+    ///
+    /// `candidates` is only called when the version differs from the registered one, so a source
+    /// can be registered every frame with e.g. the change tick of the resource it comes from.
+    /// The index is rebuilt when the candidates differ.
+    pub fn set_source(
+        &mut self,
+        name: &str,
+        version: u64,
+        candidates: impl FnOnce() -> Vec<String>,
+    ) {
+        if let Some(source) = self.sources.get_mut(name) {
+            if source.version == version {
+                return;
+            }
+            source.version = version;
+            let candidates = candidates();
+            if source.candidates == candidates {
+                return;
+            }
+            source.candidates = candidates;
+        } else {
+            let candidates = candidates();
+            let source = CompletionSource {
+                version,
+                candidates,
+            };
+            self.sources.insert(name.to_owned(), source);
+        }
+        self.rebuild = Some(Rebuild::default());
+    }
+
+    /// Removes the candidates of the source `name`.
+    pub fn remove_source(&mut self, name: &str) {
+        if self.sources.remove(name).is_some() {
+            self.rebuild = Some(Rebuild::default());
+        }
+    }
+
+    /// Whether the index has the candidates of every source, rather than being rebuilt.
+    pub fn is_ready(&self) -> bool {
+        self.rebuild.is_none()
+    }
+
+    /// Indexes up to [`CompletionIndex::budget`] more candidates while the index is rebuilt,
+    /// done by the console every frame.
+    pub fn index_step(&mut self) {
+        let Some(rebuild) = &mut self.rebuild else {
+            return;
+        };
+        if let Some(index) = rebuild.step(&self.sources, self.budget.max(1)) {
+            self.index = index;
+            self.rebuild = None;
+        }
+    }
+
+    /// Finishes rebuilding the index right away.
+    pub fn index_all(&mut self) {
+        while !self.is_ready() {
+            self.index_step();
+        }
+    }
+
+    /// Up to `max` candidates starting with `typed`, or containing its characters in order if
+    /// none do, ignoring case.
+    pub fn suggestions(&self, typed: &str, max: usize) -> Vec<&str> {
+        self.index.suggestions(typed, max)
+    }
+
+    /// The indexed candidates.
+    pub(crate) fn candidates(&self) -> &Candidates {
+        &self.index
+    }
+
+    /// Registers the visible commands of the console and the console variables, `changed` being
+    /// when each last changed.
+    pub(crate) fn set_console_sources(
+        &mut self,
+        config: &ConsoleConfiguration<M>,
+        cvars: &ConsoleVariables,
+        changed: [Tick; 2],
+    ) where
+        M: ConsoleMarker,
+    {
+        self.set_source("commands", changed[0].get().into(), || {
+            config.visible_commands().map(str::to_owned).collect()
+        });
+        self.set_source("variables", changed[1].get().into(), || {
+            cvars.names().map(str::to_owned).collect()
+        });
+    }
+}
+
+/// Keeps the completion index of the console `M` up to date with its commands and the console
+/// variables, and indexes the next candidates while it's rebuilt.
+pub(crate) fn update_completion_index<M: ConsoleMarker>(
+    mut index: ResMut<CompletionIndex<M>>,
+    config: Res<ConsoleConfiguration<M>>,
+    cvars: Res<ConsoleVariables>,
+) {
+    // Registering unchanged sources would mark the index changed every frame
+    let index = index.bypass_change_detection();
+    let changed = [config.last_changed(), cvars.last_changed()];
+    index.set_console_sources(&config, &cvars, changed);
+    index.index_step();
+}
+
+/// Sorted completion candidates, searched by their lowercase form.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Candidates {
+    entries: Vec<Candidate>,
+}
+
+/// Ordered by the lowercase form first, so the candidates sharing a prefix ignoring case are
+/// next to each other.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Candidate {
+    folded: String,
+    name: String,
+    /// Characters of the lowercase form, see [`char_mask`]
+    chars: u64,
+}
+
+impl Candidate {
+    fn new(name: String) -> Self {
+        let folded = name.to_lowercase();
+        Self {
+            chars: char_mask(&folded),
+            folded,
+            name,
+        }
+    }
+
+    /// Whether the candidate contains the characters of `typed` in order, `mask` being those of
+    /// `typed`.
+    fn fuzzy_matches(&self, typed: &str, mask: u64) -> bool {
+        if self.chars & mask != mask {
+            return false;
+        }
+        // ASCII bytes are never part of other characters, so they're compared as bytes
+        if typed.is_ascii() {
+            let mut bytes = self.folded.bytes();
+            return typed.bytes().all(|b| bytes.any(|folded_b| folded_b == b));
+        }
+        let mut chars = self.folded.chars();
+        typed.chars().all(|c| chars.any(|folded_c| folded_c == c))
+    }
+}
+
+/// Bit set of the characters in `text`, folded into 64 bits, so a candidate missing a bit of
+/// the typed text is skipped without looking at its characters.
+fn char_mask(text: &str) -> u64 {
+    text.chars()
+        .fold(0, |mask, c| mask | 1 << (u32::from(c) % 64))
+}
+
+impl Candidates {
+    pub(crate) fn new(names: impl IntoIterator<Item = String>) -> Self {
+        let mut entries = names.into_iter().map(Candidate::new).collect::<Vec<_>>();
+        entries.sort_unstable();
+        entries.dedup();
+        Self { entries }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|candidate| candidate.name.as_str())
+    }
+
+    /// Candidates starting with `typed` ignoring case, found with a binary search.
+    pub(crate) fn starting_with<'a>(&'a self, typed: &str) -> impl Iterator<Item = &'a str> {
+        let typed = typed.to_lowercase();
+        let start = self
+            .entries
+            .partition_point(|candidate| candidate.folded < typed);
+        self.entries[start..]
+            .iter()
+            .take_while(move |candidate| candidate.folded.starts_with(&typed))
+            .map(|candidate| candidate.name.as_str())
+    }
+
+    /// The candidate `name` if it's indexed.
+    pub(crate) fn get(&self, name: &str) -> Option<&str> {
+        self.starting_with(name)
+            .find(|candidate| *candidate == name)
+    }
+
+    /// Up to `max` candidates starting with `typed`, or containing its characters in order if
+    /// none do, ignoring case.
+    pub(crate) fn suggestions(&self, typed: &str, max: usize) -> Vec<&str> {
+        if typed.is_empty() {
+            return Vec::new();
+        }
+        let prefixed = self.starting_with(typed).take(max).collect::<Vec<_>>();
+        if !prefixed.is_empty() {
+            return prefixed;
+        }
+        let typed = typed.to_lowercase();
+        let mask = char_mask(&typed);
+        self.entries
+            .iter()
+            .filter(|candidate| candidate.fuzzy_matches(&typed, mask))
+            .map(|candidate| candidate.name.as_str())
+            .take(max)
+            .collect()
+    }
+}
+
+/// Index being built from the candidates of all sources, sorting them a chunk at a time and then
+/// merging the sorted chunks.
+#[derive(Default)]
+struct Rebuild {
+    /// Position of the source and of its candidate to index next
+    next: (usize, usize),
+    /// Sorted chunks waiting to be merged
+    runs: VecDeque<Vec<Candidate>>,
+    /// Merge of two chunks in progress
+    merge: Option<Merge>,
+}
+
+impl Rebuild {
+    /// Indexes up to `budget` candidates, returning the index once it's complete.
+    fn step(
+        &mut self,
+        sources: &BTreeMap<String, CompletionSource>,
+        budget: usize,
+    ) -> Option<Candidates> {
+        if self.next.0 < sources.len() {
+            let mut chunk = Vec::with_capacity(budget);
+            for source in sources.values().skip(self.next.0) {
+                let rest = &source.candidates[self.next.1..];
+                let count = rest.len().min(budget - chunk.len());
+                chunk.extend(rest[..count].iter().cloned().map(Candidate::new));
+                self.next.1 += count;
+                if self.next.1 < source.candidates.len() {
+                    break;
+                }
+                self.next = (self.next.0 + 1, 0);
+            }
+            chunk.sort_unstable();
+            chunk.dedup();
+            self.runs.push_back(chunk);
+            return None;
+        }
+
+        let mut budget = budget;
+        while budget > 0 {
+            let merge = match &mut self.merge {
+                Some(merge) => merge,
+                None if self.runs.len() < 2 => {
+                    let entries = self.runs.pop_front().unwrap_or_default();
+                    return Some(Candidates { entries });
+                }
+                None => {
+                    let left = self.runs.pop_front().unwrap_or_default();
+                    let right = self.runs.pop_front().unwrap_or_default();
+                    self.merge.insert(Merge::new(left, right))
+                }
+            };
+            budget -= merge.step(budget);
+            if merge.is_done() {
+                let merged = self.merge.take().map(|merge| merge.merged);
+                self.runs.extend(merged);
+            }
+        }
+        None
+    }
+}
+
+/// Two sorted chunks merged into one, without duplicates.
+struct Merge {
+    left: Peekable<vec::IntoIter<Candidate>>,
+    right: Peekable<vec::IntoIter<Candidate>>,
+    merged: Vec<Candidate>,
+}
+
+impl Merge {
+    fn new(left: Vec<Candidate>, right: Vec<Candidate>) -> Self {
+        Self {
+            merged: Vec::with_capacity(left.len() + right.len()),
+            left: left.into_iter().peekable(),
+            right: right.into_iter().peekable(),
+        }
+    }
+
+    /// Merges up to `budget` candidates, returning how many were.
+    fn step(&mut self, budget: usize) -> usize {
+        for count in 0..budget {
+            let next = match (self.left.peek(), self.right.peek()) {
+                (Some(left), Some(right)) if left <= right => self.left.next(),
+                (_, Some(_)) => self.right.next(),
+                (Some(_), None) => self.left.next(),
+                (None, None) => return count,
+            };
+            if let Some(candidate) = next.filter(|next| self.merged.last() != Some(next)) {
+                self.merged.push(candidate);
+            }
+        }
+        budget
+    }
+
+    fn is_done(&mut self) -> bool {
+        self.left.peek().is_none() && self.right.peek().is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(count: usize) -> Vec<String> {
+        (0..count).rev().map(|i| format!("Entity_{i:03}")).collect()
+    }
+
+    #[test]
+    fn test_index_rebuilds_within_budget() {
+        let mut index = CompletionIndex::<DefaultConsole> {
+            budget: 16,
+            ..default()
+        };
+        index.set_source("entities", 1, || names(100));
+        index.set_source("commands", 1, || {
+            vec!["help".to_owned(), "entity".to_owned()]
+        });
+
+        let mut frames = 0;
+        while !index.is_ready() {
+            assert!(index.suggestions("ent", 5).is_empty());
+            index.index_step();
+            frames += 1;
+        }
+        // 7 chunks are sorted, then merged 16 candidates at a time
+        assert!(frames > 7, "indexed in {frames} frames");
+        let expected = ["entity", "Entity_000", "Entity_001", "Entity_002"];
+        assert_eq!(index.suggestions("ENT", 4), expected);
+        assert_eq!(index.candidates().iter().count(), 102);
+        assert_eq!(index.suggestions("ey9", 2), ["Entity_009", "Entity_019"]);
+
+        // Unchanged sources and versions keep the index
+        index.set_source("entities", 2, || names(100));
+        index.set_source("commands", 1, || unreachable!("the version is the same"));
+        assert!(index.is_ready());
+
+        index.set_source("entities", 3, || vec!["help".to_owned()]);
+        index.index_all();
+        assert_eq!(
+            index.candidates().iter().collect::<Vec<_>>(),
+            ["entity", "help"]
+        );
+        index.remove_source("commands");
+        index.index_all();
+        assert_eq!(index.candidates().iter().collect::<Vec<_>>(), ["help"]);
+    }
+}
@@ -1,20 +1,59 @@
 use bevy::ecs::{
     component::Tick,
+    event::ManualEventReader,
     schedule::IntoSystemConfigs,
-    system::{Resource, SystemMeta, SystemParam},
+    system::{Resource, SystemMeta, SystemParam, SystemState},
     world::unsafe_world_cell::UnsafeWorldCell,
 };
 use bevy::prelude::*;
-use clap::{builder::StyledStr, CommandFactory, FromArgMatches};
-use std::collections::{BTreeMap, VecDeque};
+use bevy::utils::Instant;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
+#[cfg(feature = "egui")]
+use bevy_egui::egui;
+use clap::{
+    builder::{styling::AnsiColor, StyledStr},
+    error::ErrorKind,
+    CommandFactory, FromArgMatches,
+};
+use shlex::Shlex;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Display;
 use std::marker::PhantomData;
-use std::mem;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::dispatch::{
+    answer_confirmation, parse_command_line, run_command_line, split_expanded_commands,
+    CommandLineError, CommandLookup, InputValidation, LookupGeneration,
+};
+use crate::instance::{ConsoleMarker, DefaultConsole};
+use crate::localization::{fill, Plural};
+use crate::script::ScriptLine;
+#[cfg(feature = "egui")]
+use crate::ConsoleTheme;
+use crate::{ConsoleLocalization, ConsoleMirror, ConsoleSet, ConsoleVariables, CvarChanged};
+
+/// Default maximum width of a [`ConsoleCommand::reply_table`] cell, in characters
+pub const DEFAULT_TABLE_CELL_WIDTH: usize = 40;
+
+type ConsoleCommandEnteredReaderSystemParam<M> =
+    EventReader<'static, 'static, ConsoleCommandEntered<M>>;
+
+type PrintConsoleLineWriterSystemParam<M> = EventWriter<'static, PrintConsoleLine<M>>;
+
+type RequestConfirmationWriterSystemParam<M> = EventWriter<'static, RequestConfirmation<M>>;
 
-use crate::ConsoleSet;
+type CommandParseFailedWriterSystemParam<M> = EventWriter<'static, CommandParseFailed<M>>;
 
-type ConsoleCommandEnteredReaderSystemParam = EventReader<'static, 'static, ConsoleCommandEntered>;
+type CommandFailedWriterSystemParam<M> = EventWriter<'static, CommandFailed<M>>;
 
-type PrintConsoleLineWriterSystemParam = EventWriter<'static, PrintConsoleLine>;
+type CommandFinishedWriterSystemParam<M> = EventWriter<'static, CommandFinished<M>>;
+
+type StatusLabelsSystemParam = Option<Res<'static, StatusLabels>>;
 
 /// A super-trait for command like structures
 pub trait Command: NamedCommand + CommandFactory + FromArgMatches + Sized + Resource {}
@@ -47,48 +86,158 @@ pub trait NamedCommand {
 /// }
 ///
 /// fn log_command(mut log: ConsoleCommand<LogCommand>) {
-///     if let Some(Ok(LogCommand { msg, num })) = log.take() {
-///         log.ok();
+///     // The command may have been entered several times since the system last ran
+///     while let Some(result) = log.take() {
+///         if let Ok(LogCommand { msg, num }) = result {
+///             log.ok();
+///         }
 ///     }
 /// }
 /// ```
-pub struct ConsoleCommand<'w, T> {
-    command: Option<Result<T, clap::Error>>,
-    console_line: EventWriter<'w, PrintConsoleLine>,
+///
+/// Commands of other consoles than the default one name their marker type, like
+/// `ConsoleCommand<LogCommand, GameConsole>`, see [`instance`](crate::instance).
+pub struct ConsoleCommand<'w, T, M: ConsoleMarker = DefaultConsole> {
+    commands: VecDeque<(Result<T, clap::Error>, ConsoleCommandEntered<M>)>,
+    /// The invocation last returned by [`ConsoleCommand::take`]
+    current: Option<ConsoleCommandEntered<M>>,
+    console_line: EventWriter<'w, PrintConsoleLine<M>>,
+    confirmations: EventWriter<'w, RequestConfirmation<M>>,
+    failures: EventWriter<'w, CommandFailed<M>>,
+    finished: EventWriter<'w, CommandFinished<M>>,
+    labels: StatusLabels,
+    source: &'static str,
 }
 
-impl<'w, T> ConsoleCommand<'w, T> {
-    /// Returns Some(T) if the command was executed and arguments were valid.
+impl<'w, T, M: ConsoleMarker> ConsoleCommand<'w, T, M> {
+    /// Returns the next invocation of the command, `Ok(T)` if its arguments were valid.
     ///
-    /// This method should only be called once.
-    /// Consecutive calls will return None regardless if the command occurred.
+    /// Every invocation entered since the system last ran is queued, call this in a loop to
+    /// handle all of them. Returns `None` once the queue is empty.
     pub fn take(&mut self) -> Option<Result<T, clap::Error>> {
-        mem::take(&mut self.command)
+        let (result, entered) = self.commands.pop_front()?;
+        self.current = Some(entered);
+        Some(result)
+    }
+
+    /// The invocation last returned by [`ConsoleCommand::take`], e.g. to send
+    /// [`CommandFinished`] for it once it's done in a later frame.
+    pub fn entered(&self) -> Option<&ConsoleCommandEntered<M>> {
+        self.current.as_ref()
+    }
+
+    /// Returns all queued invocations of the command, oldest first.
+    pub fn take_all(&mut self) -> Vec<Result<T, clap::Error>> {
+        self.current = None;
+        self.commands.drain(..).map(|(result, _)| result).collect()
+    }
+
+    /// Asks the user to confirm the invocation last returned by [`ConsoleCommand::take`].
+    ///
+    /// The `prompt` is printed and the next line entered in the console answers it: `y` or `yes`
+    /// enters the command again, marked as [`ConsoleCommandEntered::confirmed`], anything else
+    /// cancels it. Asking again before the answer cancels the earlier confirmation, and it's
+    /// cancelled after [`ConsoleConfiguration::confirmation_timeout`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// while let Some(Ok(WipeCommand)) = wipe.take() {
+    ///     if wipe.confirm("Wipe the save?").is_confirmed() {
+    ///         wipe_save();
+    ///         wipe.ok();
+    ///     }
+    /// }
+    /// ```
+    pub fn confirm(&mut self, prompt: impl Into<StyledStr>) -> Confirmation {
+        self.request_confirmation(prompt.into(), None)
+    }
+
+    /// Asks the user to confirm the invocation last returned by [`ConsoleCommand::take`] by
+    /// entering the command again or an empty line within `window`.
+    ///
+    /// Unlike [`ConsoleCommand::confirm`], the `prompt` is printed without the answers and any
+    /// other line cancels the confirmation and runs as usual.
+    pub fn confirm_repeated(
+        &mut self,
+        prompt: impl Into<StyledStr>,
+        window: Duration,
+    ) -> Confirmation {
+        self.request_confirmation(prompt.into(), Some(window))
+    }
+
+    fn request_confirmation(
+        &mut self,
+        prompt: StyledStr,
+        repeat_within: Option<Duration>,
+    ) -> Confirmation {
+        let Some(current) = &self.current else {
+            return Confirmation::Pending;
+        };
+        if current.confirmed {
+            return Confirmation::Confirmed;
+        }
+        self.confirmations.send(RequestConfirmation {
+            prompt,
+            command: current.clone(),
+            repeat_within,
+        });
+        Confirmation::Pending
     }
 
-    /// Print `[ok]` in the console.
+    /// A reply to the invocation last returned by [`ConsoleCommand::take`].
+    fn reply_line(&self, line: StyledStr) -> PrintConsoleLine<M> {
+        let line = PrintConsoleLine::new(line).with_source(self.source);
+        match &self.current {
+            Some(current) => line.with_origin(current.origin),
+            None => line,
+        }
+    }
+
+    fn send(&mut self, line: StyledStr) {
+        let line = self.reply_line(line);
+        self.console_line.send(line);
+    }
+
+    /// Restores the console variables set for the invocation last returned by
+    /// [`ConsoleCommand::take`], if any.
+    fn finish(&mut self) {
+        if let Some(current) = self.current.as_ref() {
+            if !current.overrides.is_empty() {
+                self.finished.send(CommandFinished::new(current.clone()));
+            }
+        }
+    }
+
+    /// Print a green `[ok]` in the console.
     pub fn ok(&mut self) {
-        self.console_line.send(PrintConsoleLine::new("[ok]".into()));
+        self.finish();
+        self.send(colored(AnsiColor::Green, &self.labels.ok));
     }
 
-    /// Print `[failed]` in the console.
+    /// Print a red `[failed]` in the console.
     pub fn failed(&mut self) {
-        self.console_line
-            .send(PrintConsoleLine::new("[failed]".into()));
+        self.finish();
+        self.failures.send(CommandFailed {
+            marker: PhantomData,
+        });
+        self.send(colored(AnsiColor::Red, &self.labels.failed));
     }
 
     /// Print a reply in the console.
     ///
+    /// ANSI styling in `msg`, e.g. from [`clap::builder::styling`], is rendered as colored text.
+    ///
     /// See [`reply!`](crate::reply) for usage with the [`format!`] syntax.
     pub fn reply(&mut self, msg: impl Into<StyledStr>) {
-        self.console_line.send(PrintConsoleLine::new(msg.into()));
+        self.send(msg.into());
     }
 
     /// Print a reply in the console followed by `[ok]`.
     ///
     /// See [`reply_ok!`](crate::reply_ok) for usage with the [`format!`] syntax.
     pub fn reply_ok(&mut self, msg: impl Into<StyledStr>) {
-        self.console_line.send(PrintConsoleLine::new(msg.into()));
+        self.send(msg.into());
         self.ok();
     }
 
@@ -96,28 +245,150 @@ impl<'w, T> ConsoleCommand<'w, T> {
     ///
     /// See [`reply_failed!`](crate::reply_failed) for usage with the [`format!`] syntax.
     pub fn reply_failed(&mut self, msg: impl Into<StyledStr>) {
-        self.console_line.send(PrintConsoleLine::new(msg.into()));
+        self.send(msg.into());
         self.failed();
     }
+
+    /// Print the value of `result` followed by `[ok]`, or its error followed by `[failed]`.
+    ///
+    /// Values spanning several lines are printed as one console line per line.
+    ///
+    /// See [`reply_result!`](crate::reply_result) for formatting the value with the [`format!`]
+    /// syntax.
+    pub fn reply_result<V: Display, E: Display>(&mut self, result: Result<V, E>) {
+        match result {
+            Ok(value) => {
+                self.reply_lines(value.to_string().lines().map(str::to_owned));
+                self.ok();
+            }
+            Err(err) => {
+                self.reply_lines(err.to_string().lines().map(str::to_owned));
+                self.failed();
+            }
+        }
+    }
+
+    /// Print each item as its own line in the console.
+    pub fn reply_lines(&mut self, lines: impl IntoIterator<Item = impl Into<StyledStr>>) {
+        for line in lines {
+            self.send(line.into());
+        }
+    }
+
+    /// Print `rows` as aligned columns under a bold `headers` row.
+    ///
+    /// Cells longer than [`DEFAULT_TABLE_CELL_WIDTH`] characters are truncated, see
+    /// [`ConsoleCommand::reply_table_with_max_width`] to change the limit.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// cmd.reply_table(["id", "name"], [["1", "camera"], ["2", "player"]]);
+    /// ```
+    pub fn reply_table(
+        &mut self,
+        headers: impl IntoIterator<Item = impl ToString>,
+        rows: impl IntoIterator<Item = impl IntoIterator<Item = impl ToString>>,
+    ) {
+        self.reply_table_with_max_width(headers, rows, DEFAULT_TABLE_CELL_WIDTH);
+    }
+
+    /// Print `rows` as aligned columns under a bold `headers` row, truncating cells longer
+    /// than `max_width` characters.
+    pub fn reply_table_with_max_width(
+        &mut self,
+        headers: impl IntoIterator<Item = impl ToString>,
+        rows: impl IntoIterator<Item = impl IntoIterator<Item = impl ToString>>,
+        max_width: usize,
+    ) {
+        let headers = headers
+            .into_iter()
+            .map(|header| header.to_string())
+            .collect::<Vec<_>>();
+        let rows = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(|cell| cell.to_string()).collect())
+            .collect::<Vec<_>>();
+
+        self.reply_lines(crate::table::render_table(&headers, &rows, max_width));
+    }
+
+    /// Print a progress line in the console, replacing the line last printed with the same `key`.
+    ///
+    /// The line keeps its position in the scrollback while it is updated.
+    /// Use [`ConsoleCommand::reply_progress_done`] to print its final state.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// cmd.reply_progress("import", format!("importing… {done}/{total}"));
+    /// ```
+    pub fn reply_progress(&mut self, key: impl Into<String>, msg: impl Into<StyledStr>) {
+        let line = self
+            .reply_line(msg.into())
+            .with_progress(Progress::Update(key.into()));
+        self.console_line.send(line);
+    }
+
+    /// Print the final state of a progress line, after which it no longer updates.
+    pub fn reply_progress_done(&mut self, key: impl Into<String>, msg: impl Into<StyledStr>) {
+        let line = self
+            .reply_line(msg.into())
+            .with_progress(Progress::Done(key.into()));
+        self.console_line.send(line);
+    }
+
+    /// Print a line-based diff between `old` and `new` in the console.
+    ///
+    /// Removed lines are prefixed with `-` and colored red, added lines with `+` and colored green.
+    /// Long runs of unchanged lines are collapsed into a single `… N unchanged lines` line.
+    pub fn reply_diff(&mut self, old: &str, new: &str) {
+        for line in crate::diff::render_diff(old, new, &self.labels.unchanged_lines) {
+            self.send(line);
+        }
+    }
 }
 
-pub struct ConsoleCommandState<T> {
+pub struct ConsoleCommandState<T, M: ConsoleMarker> {
     #[allow(clippy::type_complexity)]
-    event_reader: <ConsoleCommandEnteredReaderSystemParam as SystemParam>::State,
-    console_line: <PrintConsoleLineWriterSystemParam as SystemParam>::State,
+    event_reader: <ConsoleCommandEnteredReaderSystemParam<M> as SystemParam>::State,
+    console_line: <PrintConsoleLineWriterSystemParam<M> as SystemParam>::State,
+    confirmations: <RequestConfirmationWriterSystemParam<M> as SystemParam>::State,
+    parse_failures: <CommandParseFailedWriterSystemParam<M> as SystemParam>::State,
+    failures: <CommandFailedWriterSystemParam<M> as SystemParam>::State,
+    finished: <CommandFinishedWriterSystemParam<M> as SystemParam>::State,
+    labels: <StatusLabelsSystemParam as SystemParam>::State,
     marker: PhantomData<T>,
 }
 
-unsafe impl<T: Command> SystemParam for ConsoleCommand<'_, T> {
-    type State = ConsoleCommandState<T>;
-    type Item<'w, 's> = ConsoleCommand<'w, T>;
+unsafe impl<T: Command, M: ConsoleMarker> SystemParam for ConsoleCommand<'_, T, M> {
+    type State = ConsoleCommandState<T, M>;
+    type Item<'w, 's> = ConsoleCommand<'w, T, M>;
 
     fn init_state(world: &mut World, system_meta: &mut SystemMeta) -> Self::State {
-        let event_reader = ConsoleCommandEnteredReaderSystemParam::init_state(world, system_meta);
-        let console_line = PrintConsoleLineWriterSystemParam::init_state(world, system_meta);
+        let event_reader =
+            ConsoleCommandEnteredReaderSystemParam::<M>::init_state(world, system_meta);
+        let console_line = PrintConsoleLineWriterSystemParam::<M>::init_state(world, system_meta);
+        // Commands can be handled without the console plugin, e.g. in tests
+        world.init_resource::<Events<RequestConfirmation<M>>>();
+        world.init_resource::<Events<CommandParseFailed<M>>>();
+        world.init_resource::<Events<CommandFailed<M>>>();
+        world.init_resource::<Events<CommandFinished<M>>>();
+        let confirmations =
+            RequestConfirmationWriterSystemParam::<M>::init_state(world, system_meta);
+        let parse_failures =
+            CommandParseFailedWriterSystemParam::<M>::init_state(world, system_meta);
+        let failures = CommandFailedWriterSystemParam::<M>::init_state(world, system_meta);
+        let finished = CommandFinishedWriterSystemParam::<M>::init_state(world, system_meta);
+        let labels = StatusLabelsSystemParam::init_state(world, system_meta);
         ConsoleCommandState {
             event_reader,
             console_line,
+            confirmations,
+            parse_failures,
+            failures,
+            finished,
+            labels,
             marker: PhantomData,
         }
     }
@@ -129,210 +400,3941 @@ unsafe impl<T: Command> SystemParam for ConsoleCommand<'_, T> {
         world: UnsafeWorldCell<'w>,
         change_tick: Tick,
     ) -> Self::Item<'w, 's> {
-        let mut event_reader = ConsoleCommandEnteredReaderSystemParam::get_param(
+        let mut event_reader = ConsoleCommandEnteredReaderSystemParam::<M>::get_param(
             &mut state.event_reader,
             system_meta,
             world,
             change_tick,
         );
-        let mut console_line = PrintConsoleLineWriterSystemParam::get_param(
+        let mut console_line = PrintConsoleLineWriterSystemParam::<M>::get_param(
             &mut state.console_line,
             system_meta,
             world,
             change_tick,
         );
+        let confirmations = RequestConfirmationWriterSystemParam::<M>::get_param(
+            &mut state.confirmations,
+            system_meta,
+            world,
+            change_tick,
+        );
+        let mut parse_failures = CommandParseFailedWriterSystemParam::<M>::get_param(
+            &mut state.parse_failures,
+            system_meta,
+            world,
+            change_tick,
+        );
+        let failures = CommandFailedWriterSystemParam::<M>::get_param(
+            &mut state.failures,
+            system_meta,
+            world,
+            change_tick,
+        );
+        let finished = CommandFinishedWriterSystemParam::<M>::get_param(
+            &mut state.finished,
+            system_meta,
+            world,
+            change_tick,
+        );
+        // Commands can be handled without the console plugin, which keeps the labels up to date
+        let labels =
+            StatusLabelsSystemParam::get_param(&mut state.labels, system_meta, world, change_tick)
+                .map(|labels| labels.clone())
+                .unwrap_or_default();
 
-        let command = event_reader.read().find_map(|command| {
-            if T::name() == command.command_name {
-                let clap_command = T::command().no_binary_name(true);
-                // .color(clap::ColorChoice::Always);
-                let arg_matches = clap_command.try_get_matches_from(command.args.iter());
+        let commands = event_reader
+            .read()
+            .filter_map(|command| {
+                if T::name() == command.command_name {
+                    let clap_command = console_clap_command(T::command().name(T::name()));
+                    let arg_matches = clap_command.try_get_matches_from(command.args.iter());
 
-                debug!(
-                    "Trying to parse as `{}`. Result: {arg_matches:?}",
-                    command.command_name
-                );
+                    debug!(
+                        "Trying to parse as `{}`. Result: {arg_matches:?}",
+                        command.command_name
+                    );
 
-                match arg_matches {
-                    Ok(matches) => {
-                        return Some(T::from_arg_matches(&matches));
-                    }
-                    Err(err) => {
-                        console_line.send(PrintConsoleLine::new(err.render()));
-                        return Some(Err(err));
+                    match arg_matches {
+                        Ok(matches) => {
+                            return Some((T::from_arg_matches(&matches), command.clone()));
+                        }
+                        Err(err) => {
+                            // Asking for the help or version isn't a mistake
+                            if !matches!(
+                                err.kind(),
+                                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
+                            ) {
+                                parse_failures.send(CommandParseFailed {
+                                    command_name: T::name(),
+                                    marker: PhantomData,
+                                });
+                            }
+                            console_line.send(
+                                PrintConsoleLine::new(err.render())
+                                    .with_source(T::name())
+                                    .with_origin(command.origin),
+                            );
+                            return Some((Err(err), command.clone()));
+                        }
                     }
                 }
-            }
-            None
-        });
+                None
+            })
+            .collect();
 
         ConsoleCommand {
-            command,
+            commands,
+            current: None,
             console_line,
+            confirmations,
+            failures,
+            finished,
+            labels,
+            source: T::name(),
         }
     }
 }
 
 /// Parsed raw console command into `command` and `args`.
+///
+/// Created with [`ConsoleCommandEntered::new`] outside this crate, so fields can be added.
 #[derive(Clone, Debug, Event)]
-pub struct ConsoleCommandEntered {
+#[non_exhaustive]
+pub struct ConsoleCommandEntered<M = DefaultConsole> {
     /// the command definition
     pub command_name: String,
     /// Raw parsed arguments
     pub args: Vec<String>,
+    /// The input exactly as typed, trimmed and without the prompt symbol
+    pub raw: String,
+    /// The command was entered again after the user confirmed it, see
+    /// [`ConsoleCommand::confirm`]
+    pub confirmed: bool,
+    /// Where the command was entered
+    pub origin: CommandOrigin,
+    /// Names and values of the console variables set for the duration of the command, entered
+    /// before it like `gravity=0 spawn_level`
+    pub(crate) overrides: CvarOverrides,
+    /// Console the command was entered in
+    #[doc(hidden)]
+    pub marker: PhantomData<M>,
 }
 
-/// Events to print to the console.
-#[derive(Clone, Debug, Eq, Event, PartialEq)]
-pub struct PrintConsoleLine {
-    /// Console line
-    pub line: StyledStr,
+/// Names and values of console variables set for the duration of a command.
+pub(crate) type CvarOverrides = Vec<(String, String)>;
+
+/// Where a command was entered, which its replies are tagged with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CommandOrigin {
+    /// Typed in the console, or run by the app
+    #[default]
+    Local,
+    /// Sent by a client of the remote console, with the id of its connection
+    Remote(u64),
+    /// Run by a script, with `exec` or `autoexec`
+    Script,
+    /// Run by the [`ConsoleConfiguration::autoexec`] script at startup
+    Startup,
 }
 
-impl PrintConsoleLine {
-    /// Creates a new console line to print.
-    pub const fn new(line: StyledStr) -> Self {
-        Self { line }
+impl CommandOrigin {
+    #[cfg(feature = "default_commands")]
+    /// Origin of the lines of a script run by a command entered at this origin.
+    ///
+    /// Scripts run from the remote console reply to its client.
+    pub(crate) fn script(self) -> Self {
+        match self {
+            CommandOrigin::Local => CommandOrigin::Script,
+            origin => origin,
+        }
+    }
+
+    /// Id of the remote console connection, `None` for commands entered in the app, whose
+    /// confirmations are answered in the console.
+    pub(crate) fn connection(self) -> Option<u64> {
+        match self {
+            CommandOrigin::Remote(id) => Some(id),
+            _ => None,
+        }
     }
 }
 
-/// Key for toggling the console.
-#[derive(Copy, Clone)]
-pub enum ToggleConsoleKey {
-    /// Keycode supported by bevy_input
-    KeyCode(KeyCode),
-    /// Raw scan code
-    ScanCode(u32),
+impl<M> ConsoleCommandEntered<M> {
+    /// Creates a command entered with these arguments, as if typed with single spaces between
+    /// them.
+    pub fn new(command_name: impl Into<String>, args: Vec<String>) -> Self {
+        let command_name = command_name.into();
+        let raw = std::iter::once(&command_name)
+            .chain(&args)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            command_name,
+            args,
+            raw,
+            confirmed: false,
+            origin: CommandOrigin::Local,
+            overrides: Vec::new(),
+            marker: PhantomData,
+        }
+    }
 }
 
-/// Console configuration
-#[derive(Clone, Resource)]
-pub struct ConsoleConfiguration {
-    /// Registered keys for toggling the console
-    pub keys: Vec<ToggleConsoleKey>,
-    /// Left position
-    pub left_pos: f32,
-    /// Top position
-    pub top_pos: f32,
-    /// Console height
-    pub height: f32,
-    /// Console width
-    pub width: f32,
-    /// Console resizable
-    pub resizable: bool,
-    /// Console title bar
-    pub title_bar: bool,
-    /// Console collapsible
-    pub collapsible: bool,
-    /// Registered console commands
-    pub commands: BTreeMap<&'static str, clap::Command>,
-    /// Number of commands to store in history
-    pub history_size: usize,
-    ///Line prefix symbol
-    pub symbol: String,
+/// Answer to [`ConsoleCommand::confirm`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Confirmation {
+    /// The user confirmed the command, it can run
+    Confirmed,
+    /// The user was asked to confirm the command, it's entered again if they do
+    Pending,
 }
 
-impl Default for ConsoleConfiguration {
-    fn default() -> Self {
-        Self {
-            keys: vec![ToggleConsoleKey::KeyCode(KeyCode::Grave)],
-            left_pos: 0.0,
-            top_pos: 0.0,
-            height: 400.0,
-            width: 800.0,
-            resizable: false,
-            title_bar: false,
-            collapsible: false,
-            commands: BTreeMap::new(),
-            history_size: 50,
-            symbol: "> ".to_owned(),
-        }
+impl Confirmation {
+    /// Whether the command can run.
+    pub fn is_confirmed(self) -> bool {
+        self == Confirmation::Confirmed
     }
 }
 
-/// Add a console commands to Bevy app.
-pub trait AddConsoleCommand {
-    /// Add a console command with a given system.
-    ///
-    /// This registers the console command so it will print with the built-in `help` console command.
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// # use bevy::prelude::*;
-    /// # use bevy_console::{AddConsoleCommand, ConsoleCommand};
-    /// # use clap::Parser;
-    /// App::new()
-    ///     .add_console_command::<LogCommand, _>(log_command);
-    /// #
-    /// # /// Prints given arguments to the console.
-    /// # #[derive(Parser, ConsoleCommand)]
-    /// # #[command(name = "log")]
-    /// # struct LogCommand;
-    /// #
-    /// # fn log_command(mut log: ConsoleCommand<LogCommand>) {}
-    /// ```
-    fn add_console_command<T: Command, Params>(
-        &mut self,
-        system: impl IntoSystemConfigs<Params>,
-    ) -> &mut Self;
+/// Asks the user to confirm a command before it's entered again, see [`ConsoleCommand::confirm`].
+#[derive(Clone, Debug, Event)]
+pub(crate) struct RequestConfirmation<M = DefaultConsole> {
+    pub(crate) prompt: StyledStr,
+    pub(crate) command: ConsoleCommandEntered<M>,
+    /// Window to confirm by repeating the command in, see [`ConsoleCommand::confirm_repeated`]
+    pub(crate) repeat_within: Option<Duration>,
 }
 
-impl AddConsoleCommand for App {
-    fn add_console_command<T: Command, Params>(
-        &mut self,
-        system: impl IntoSystemConfigs<Params>,
-    ) -> &mut Self {
-        let sys = move |mut config: ResMut<ConsoleConfiguration>| {
-            let command = T::command().no_binary_name(true);
-            // .color(clap::ColorChoice::Always);
-            let name = T::name();
-            if config.commands.contains_key(name) {
-                warn!(
-                    "console command '{}' already registered and was overwritten",
-                    name
-                );
-            }
-            config.commands.insert(name, command);
-        };
+/// Sent by [`ConsoleCommand`] for each invocation whose arguments it couldn't parse, counted by
+/// [`record_command_stats`].
+#[derive(Clone, Debug, Event)]
+pub(crate) struct CommandParseFailed<M = DefaultConsole> {
+    pub(crate) command_name: &'static str,
+    marker: PhantomData<M>,
+}
 
-        self.add_systems(Startup, sys)
-            .add_systems(Update, system.in_set(ConsoleSet::Commands))
+/// Sent by [`ConsoleCommand::failed`], stopping the script whose line entered the command if it's
+/// strict.
+#[derive(Clone, Debug, Event)]
+pub(crate) struct CommandFailed<M = DefaultConsole> {
+    marker: PhantomData<M>,
+}
+
+/// Sent when a command is done, restoring the console variables set for it, like `gravity=0` in
+/// `gravity=0 load_level`.
+///
+/// [`ConsoleCommand::ok`] and [`ConsoleCommand::failed`] send it for the invocation they reply
+/// to. Commands registered with [`CommandMeta::finishes_later`] which reply in a later frame
+/// send it themselves, with the invocation from [`ConsoleCommand::entered`].
+#[derive(Clone, Debug, Event)]
+#[non_exhaustive]
+pub struct CommandFinished<M = DefaultConsole> {
+    /// The invocation which finished
+    pub command: ConsoleCommandEntered<M>,
+}
+
+impl<M> CommandFinished<M> {
+    /// Creates the event for an invocation which finished.
+    pub fn new(command: ConsoleCommandEntered<M>) -> Self {
+        Self { command }
     }
 }
 
-/// Console open state
-#[derive(Default, Resource)]
-pub struct ConsoleOpen {
-    /// Console open
-    pub open: bool,
+/// A command waiting for the user to confirm it.
+#[derive(Clone, Debug)]
+pub(crate) struct PendingConfirmation<M = DefaultConsole> {
+    pub(crate) command: ConsoleCommandEntered<M>,
+    /// When the confirmation is cancelled, as the [`Time<Real>`] elapsed
+    pub(crate) deadline: Option<Duration>,
+    /// Confirmed by entering the command again or an empty line instead of an answer
+    pub(crate) repeat: bool,
 }
 
-#[derive(Resource)]
-pub(crate) struct ConsoleState {
-    pub(crate) buf: String,
-    pub(crate) scrollback: Vec<StyledStr>,
-    pub(crate) history: VecDeque<StyledStr>,
-    pub(crate) history_index: usize,
+impl<M> PendingConfirmation<M> {
+    /// Whether the confirmation can no longer be answered at `now`.
+    pub(crate) fn expired(&self, now: Duration) -> bool {
+        self.deadline.is_some_and(|deadline| now > deadline)
+    }
 }
 
-impl Default for ConsoleState {
-    fn default() -> Self {
-        ConsoleState {
-            buf: String::default(),
-            scrollback: Vec::new(),
-            history: VecDeque::from([StyledStr::new()]),
-            history_index: 0,
+/// Line asking to confirm a command, followed by the expected answers.
+pub(crate) fn confirmation_prompt(
+    prompt: &StyledStr,
+    localization: &ConsoleLocalization,
+) -> StyledStr {
+    let mut line = prompt.clone();
+    line.push_str(&format!(" {}", localization.confirm_answers));
+    line
+}
+
+/// Line telling a command waiting for confirmation won't run, from one of the
+/// [`ConsoleLocalization`] messages about it.
+pub(crate) fn confirmation_cancelled<M>(
+    command: &ConsoleCommandEntered<M>,
+    message: &str,
+) -> StyledStr {
+    colored(
+        AnsiColor::Yellow,
+        &fill(message, &[("command", &command.raw)]),
+    )
+}
+
+/// Prints the confirmations asked for by commands and cancels those which timed out.
+pub(crate) fn request_confirmations<M: ConsoleMarker>(
+    mut requests: EventReader<RequestConfirmation<M>>,
+    mut state: ResMut<ConsoleState<M>>,
+    config: Res<ConsoleConfiguration<M>>,
+    time: Res<Time<Real>>,
+    mut console_line: EventWriter<PrintConsoleLine<M>>,
+    mut finished: EventWriter<CommandFinished<M>>,
+) {
+    let now = time.elapsed();
+    let timed_out = state
+        .pending_confirmation
+        .as_ref()
+        .is_some_and(|pending| pending.expired(now));
+    if timed_out {
+        if let Some(pending) = state.pending_confirmation.take() {
+            let message = &config.localization.confirmation_timed_out;
+            let line = confirmation_cancelled(&pending.command, message);
+            console_line.send(PrintConsoleLine::new(line).with_origin(pending.command.origin));
+        }
+    }
+
+    for request in requests.read() {
+        // The invocation asking is over, the confirmed one sets the variables again
+        if !request.command.overrides.is_empty() {
+            finished.send(CommandFinished::new(request.command.clone()));
         }
+        let timeout = request.repeat_within.or(config.confirmation_timeout);
+        let deadline = timeout.map(|timeout| now + timeout);
+        let repeat = request.repeat_within.is_some();
+        if let Some(replaced) = state.await_confirmation(request.command.clone(), deadline, repeat)
+        {
+            let message = &config.localization.confirmation_cancelled;
+            let line = confirmation_cancelled(&replaced, message);
+            console_line.send(PrintConsoleLine::new(line).with_origin(replaced.origin));
+        }
+        let prompt = if repeat {
+            request.prompt.clone()
+        } else {
+            confirmation_prompt(&request.prompt, &config.localization)
+        };
+        console_line.send(PrintConsoleLine::new(prompt).with_origin(request.command.origin));
     }
 }
 
-pub(crate) fn receive_console_line(
-    mut console_state: ResMut<ConsoleState>,
-    mut events: EventReader<PrintConsoleLine>,
-) {
-    for event in events.read() {
-        let event: &PrintConsoleLine = event;
-        console_state.scrollback.push(event.line.clone());
+/// Sent when an entered command isn't registered, so the app can handle it instead.
+///
+/// Turn off [`ConsoleConfiguration::print_unknown_command_error`] to handle these without the
+/// console printing an error, e.g. when forwarding them to a scripting language.
+#[derive(Clone, Debug, Event, PartialEq, Eq)]
+pub struct UnknownConsoleCommand<M = DefaultConsole> {
+    /// Name of the command as entered
+    pub name: String,
+    /// Raw parsed arguments
+    pub args: Vec<String>,
+    /// The input exactly as typed, trimmed and without the prompt symbol
+    pub raw: String,
+    /// Console the command was entered in
+    #[doc(hidden)]
+    pub marker: PhantomData<M>,
+}
+
+/// Events to print to the console.
+///
+/// Created with [`PrintConsoleLine::new`] outside this crate, so fields can be added.
+#[derive(Clone, Debug, Eq, Event, PartialEq)]
+#[non_exhaustive]
+pub struct PrintConsoleLine<M = DefaultConsole> {
+    /// Console line
+    pub line: StyledStr,
+    /// What printed the line, shown with [`ConsoleConfiguration::show_sources`].
+    ///
+    /// Replies sent through [`ConsoleCommand`] are tagged with the command name.
+    pub source: Option<String>,
+    /// Updates a previously printed progress line in place instead of appending a new line
+    pub progress: Option<Progress>,
+    /// Where the command the line replies to was entered.
+    ///
+    /// Replies sent through [`ConsoleCommand`] are tagged with the origin of the invocation.
+    pub origin: Option<CommandOrigin>,
+    /// Console the line is printed in
+    #[doc(hidden)]
+    pub marker: PhantomData<M>,
+}
+
+/// Updates a progress line printed with [`PrintConsoleLine::progress`].
+///
+/// Progress lines are identified by their key together with their [`PrintConsoleLine::source`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Progress {
+    /// Replaces the progress line with this key, printing a new line if there is none
+    Update(String),
+    /// Replaces the progress line with this key one last time, after which it no longer updates
+    Done(String),
+}
+
+impl<M> PrintConsoleLine<M> {
+    /// Creates a new console line to print.
+    pub const fn new(line: StyledStr) -> Self {
+        Self {
+            line,
+            source: None,
+            progress: None,
+            origin: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Tags the line with what printed it.
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Makes the line update the progress line it refers to.
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Tags the line as a reply to a command entered at `origin`.
+    pub fn with_origin(mut self, origin: CommandOrigin) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+}
+
+/// Prints to the console from any system.
+///
+/// Text written with [`std::fmt::Write`] is printed line by line, a line is printed once it
+/// ends with a newline or when the system returns.
+///
+/// # Example
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_console::Console;
+/// use std::fmt::Write;
+///
+/// fn report_spawns(mut console: Console, spawned: Query<Entity, Added<Transform>>) {
+///     let count = spawned.iter().count();
+///     if count > 0 {
+///         writeln!(console, "spawned {count} entities").unwrap();
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct Console<'w, 's> {
+    console_line: EventWriter<'w, PrintConsoleLine>,
+    /// Text written since the last newline
+    partial_line: Local<'s, String>,
+}
+
+impl Console<'_, '_> {
+    /// Print a line in the console.
+    pub fn write_line(&mut self, line: impl Into<StyledStr>) {
+        self.console_line.send(PrintConsoleLine::new(line.into()));
+    }
+
+    /// Print a line in the console in yellow.
+    pub fn warn(&mut self, line: &str) {
+        self.write_line(colored(AnsiColor::Yellow, line));
+    }
+
+    /// Print a line in the console in red.
+    pub fn error(&mut self, line: &str) {
+        self.write_line(colored(AnsiColor::Red, line));
+    }
+}
+
+impl std::fmt::Write for Console<'_, '_> {
+    fn write_str(&mut self, text: &str) -> std::fmt::Result {
+        self.partial_line.push_str(text);
+        while let Some(end) = self.partial_line.find('\n') {
+            let line = self.partial_line[..end].to_owned();
+            self.partial_line.replace_range(..=end, "");
+            self.write_line(line);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Console<'_, '_> {
+    fn drop(&mut self) {
+        if !self.partial_line.is_empty() {
+            let line = std::mem::take(&mut *self.partial_line);
+            self.write_line(line);
+        }
+    }
+}
+
+/// Runs console commands from any system, e.g. for cutscenes or admin messages from a server,
+/// whether or not the console is open.
+///
+/// Lines are run like entered ones and sent as [`ConsoleCommandEntered`], so their command
+/// systems run this frame if they are scheduled after the caller.
+///
+/// # Example
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_console::ConsoleRunner;
+/// fn start_cutscene(mut console: ConsoleRunner) {
+///     if let Err(err) = console.run("spawn cube 3") {
+///         error!("cutscene failed to start: {err}");
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct ConsoleRunner<'w> {
+    config: Res<'w, ConsoleConfiguration>,
+    aliases: Res<'w, ConsoleAliases>,
+    cvars: Res<'w, ConsoleVariables>,
+    gates: Res<'w, ConsoleGates>,
+    state: ResMut<'w, ConsoleState>,
+    queue: ResMut<'w, CommandQueue>,
+    time: Res<'w, Time<Real>>,
+    command_entered: EventWriter<'w, ConsoleCommandEntered>,
+    unknown_command: EventWriter<'w, UnknownConsoleCommand>,
+}
+
+impl ConsoleRunner<'_> {
+    /// Runs a command line as if it was entered in the console.
+    ///
+    /// Commands chained with `;` are all run, or none of them if one can't be parsed. The
+    /// error is returned instead of printed, an unknown command still sends an
+    /// [`UnknownConsoleCommand`]. Like entered lines, `wait` delays the commands after it and
+    /// commands registered with [`CommandMeta::confirm`] ask for confirmation first. The line
+    /// runs after the commands still waiting in the console, and is echoed into the scrollback
+    /// if [`ConsoleConfiguration::echo_run_commands`] is set.
+    pub fn run(&mut self, line: &str) -> Result<(), String> {
+        self.run_from(line, CommandOrigin::Local)
+    }
+
+    /// Runs a command line entered at `origin`, see [`ConsoleRunner::run`].
+    ///
+    /// Lines from elsewhere than the console answer the confirmations asked for the commands
+    /// they entered, like lines typed after a prompt.
+    pub(crate) fn run_from(&mut self, line: &str, origin: CommandOrigin) -> Result<(), String> {
+        let now = self.time.elapsed();
+        let echo = self.config.echo_run_commands;
+        if origin != CommandOrigin::Local
+            && answer_confirmation(
+                line,
+                origin,
+                echo,
+                &mut self.state,
+                &self.config,
+                &mut self.command_entered,
+                now,
+            )
+        {
+            return Ok(());
+        }
+
+        let lookup = CommandLookup {
+            config: &self.config,
+            aliases: &self.aliases,
+            cvars: &self.cvars,
+            gates: &self.gates,
+        };
+        let commands = split_expanded_commands(line, &lookup);
+        if commands.is_empty() {
+            return Err("error: no command to run".to_owned());
+        }
+        for command in &commands {
+            let parsed = match parse_wait(command, now, &self.config.localization) {
+                Some(wait) => wait.map(drop),
+                None => match parse_command_line(command, &lookup) {
+                    Ok(_) => Ok(()),
+                    Err(CommandLineError::Unknown(unknown, error)) => {
+                        self.unknown_command.send(unknown);
+                        Err(error)
+                    }
+                    Err(CommandLineError::Invalid(error)) => Err(error),
+                },
+            };
+            parsed?;
+        }
+
+        let queued = QueuedLine {
+            line: line.trim().to_owned(),
+            script: None,
+            chained: !echo,
+            origin,
+        };
+        if self.queue.wait.is_some() || !self.queue.lines.is_empty() {
+            self.queue.lines.push_back(queued);
+            return Ok(());
+        }
+        run_command_line(
+            &queued,
+            &mut self.state,
+            &lookup,
+            &mut self.queue,
+            &mut self.command_entered,
+            &mut self.unknown_command,
+            now,
+        );
+        Ok(())
+    }
+}
+
+/// Markers printed by [`ConsoleCommand::ok`] and [`ConsoleCommand::failed`] and the collapsed
+/// lines of [`ConsoleCommand::reply_diff`], copied from the [`ConsoleLocalization`] so command
+/// systems can still change the configuration.
+#[derive(Clone, Resource)]
+pub(crate) struct StatusLabels {
+    ok: String,
+    failed: String,
+    unchanged_lines: Plural,
+}
+
+impl StatusLabels {
+    fn new(localization: &ConsoleLocalization) -> Self {
+        Self {
+            ok: localization.ok.clone(),
+            failed: localization.failed.clone(),
+            unchanged_lines: localization.unchanged_lines.clone(),
+        }
+    }
+}
+
+impl Default for StatusLabels {
+    fn default() -> Self {
+        Self::new(&ConsoleLocalization::default())
+    }
+}
+
+pub(crate) fn update_status_labels(
+    config: Res<ConsoleConfiguration>,
+    mut labels: ResMut<StatusLabels>,
+) {
+    if config.is_changed() {
+        *labels = StatusLabels::new(&config.localization);
+    }
+}
+
+/// Command lines waiting to be run as if they were entered, e.g. from scripts run with `exec`.
+///
+/// A line entering commands runs alone in its frame, so it's resolved once the commands before
+/// it ran and sees their effects, like an alias they defined.
+#[derive(Default, Resource)]
+pub(crate) struct CommandQueue<M = DefaultConsole> {
+    pub(crate) lines: VecDeque<QueuedLine>,
+    /// Delay from a `wait` command, the lines run once it's over
+    pub(crate) wait: Option<Wait>,
+    /// Script line whose commands were entered last, which nested scripts run within and whose
+    /// failures stop a strict script
+    pub(crate) script: Option<ScriptLine>,
+    marker: PhantomData<M>,
+}
+
+/// Command line waiting in the [`CommandQueue`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct QueuedLine {
+    pub(crate) line: String,
+    /// Script the line was read from
+    pub(crate) script: Option<ScriptLine>,
+    /// Rest of a line which was already echoed, after a `;` or a `wait`
+    pub(crate) chained: bool,
+    /// Where the line was entered
+    pub(crate) origin: CommandOrigin,
+}
+
+impl From<String> for QueuedLine {
+    fn from(line: String) -> Self {
+        Self { line, ..default() }
+    }
+}
+
+impl From<&str> for QueuedLine {
+    fn from(line: &str) -> Self {
+        line.to_owned().into()
+    }
+}
+
+impl PartialEq<&str> for QueuedLine {
+    fn eq(&self, other: &&str) -> bool {
+        self.line == *other
+    }
+}
+
+/// Delay of the queued commands after a `wait` command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Wait {
+    /// Number of frames left to wait
+    Frames(u32),
+    /// Real time since startup at which the wait is over
+    Until(Duration),
+}
+
+/// Parses a `wait` command, returning `None` for other commands.
+///
+/// `wait 60` waits 60 frames, `wait 2s` or `wait 500ms` wait for real time, and a plain `wait`
+/// waits a single frame.
+pub(crate) fn parse_wait(
+    command: &str,
+    now: Duration,
+    localization: &ConsoleLocalization,
+) -> Option<Result<Wait, String>> {
+    let mut args = Shlex::new(command);
+    if args.next()? != "wait" {
+        return None;
+    }
+    let usage = || localization.wait_usage.clone();
+    let arg = args.next();
+    if args.next().is_some() {
+        return Some(Err(usage()));
+    }
+    let Some(arg) = arg else {
+        return Some(Ok(Wait::Frames(1)));
+    };
+
+    let seconds = if let Some(millis) = arg.strip_suffix("ms") {
+        millis.parse::<f64>().map(|millis| millis / 1000.0)
+    } else if let Some(seconds) = arg.strip_suffix('s') {
+        seconds.parse::<f64>()
+    } else {
+        return Some(arg.parse().map(Wait::Frames).map_err(|_| usage()));
+    };
+    Some(
+        seconds
+            .ok()
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .map(|delay| Wait::Until(now + delay))
+            .ok_or_else(usage),
+    )
+}
+
+/// Key for toggling the console.
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ToggleConsoleKey {
+    /// Keycode supported by bevy_input
+    KeyCode(KeyCode),
+    /// Raw scan code
+    ScanCode(u32),
+}
+
+/// Font family used to render the console.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FontChoice {
+    /// egui's built-in monospace family
+    #[default]
+    Monospace,
+    /// egui's built-in proportional family
+    Proportional,
+    /// A font family registered with egui under the given name.
+    ///
+    /// Falls back to [`FontChoice::Monospace`] if no such family is registered.
+    Named(String),
+}
+
+/// Container the console is shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConsoleLayout {
+    /// Floating window
+    #[default]
+    Window,
+    /// Panel docked to the top of the screen, pushing other egui panels out of the way
+    TopPanel,
+    /// Panel docked to the bottom of the screen, pushing other egui panels out of the way
+    BottomPanel,
+    /// Input line docked to the bottom of the screen with only the most recent line above it
+    Mini,
+}
+
+/// Window the console is shown in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConsoleWindowTarget {
+    /// The primary window
+    #[default]
+    Primary,
+    /// The window with the given entity
+    Entity(Entity),
+    /// The window with the given [`Name`] component, or without one the given title
+    Named(String),
+}
+
+/// Screen edge or corner the console is attached to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConsoleAnchor {
+    /// Top left corner
+    TopLeft,
+    /// Top right corner
+    TopRight,
+    /// Bottom left corner
+    BottomLeft,
+    /// Bottom right corner
+    BottomRight,
+    /// Center of the screen
+    Center,
+    /// Top edge, spanning the whole screen width
+    TopStretch,
+    /// Bottom edge, spanning the whole screen width
+    BottomStretch,
+}
+
+/// Base direction of the console text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TextDirection {
+    /// Lines start on the left, with the prompt on the left of the input
+    #[default]
+    LeftToRight,
+    /// Lines start on the right, with the prompt on the right of the input
+    RightToLeft,
+}
+
+/// What to do with an entered line, decided by [`ConsoleConfiguration::input_filter`].
+#[derive(Clone, Debug)]
+pub enum InputAction {
+    /// Run this line instead, it's added to the history in place of the entered one
+    Execute(String),
+    /// Print this text after the entered line without running anything
+    Print(StyledStr),
+    /// Drop the entered line without echoing it
+    Ignore,
+}
+
+/// Callback seeing entered lines, see [`ConsoleConfiguration::input_filter`].
+pub type InputFilter = Arc<dyn Fn(&str) -> InputAction + Send + Sync>;
+
+/// Callback changing the egui style of the console, see [`ConsoleConfiguration::ui_style`].
+#[cfg(feature = "egui")]
+pub type UiStyle = Arc<dyn Fn(&mut egui::Style) + Send + Sync>;
+
+/// Console configuration
+///
+/// With the `serde` feature it can be loaded from a settings file, see
+/// [`ConsoleConfiguration::from_ron_str`]. Registered commands and callbacks aren't serialized,
+/// and fields left out of the file keep their default value.
+#[derive(Clone, Resource)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(default, bound = "M: ConsoleMarker")
+)]
+pub struct ConsoleConfiguration<M = DefaultConsole> {
+    /// Registered keys for toggling the console
+    pub keys: Vec<ToggleConsoleKey>,
+    /// Container the console is shown in, panels only use `height` of the geometry
+    pub layout: ConsoleLayout,
+    /// Window the console is shown in, the primary window is used if it doesn't exist
+    pub window: ConsoleWindowTarget,
+    /// Left position
+    pub left_pos: f32,
+    /// Top position
+    pub top_pos: f32,
+    /// Console height
+    pub height: f32,
+    /// Console width
+    pub width: f32,
+    /// Attaches the console to a screen edge or corner, taking precedence over `left_pos` and
+    /// `top_pos`
+    pub anchor: Option<ConsoleAnchor>,
+    /// Console width in percent of the screen width, taking precedence over `width`
+    pub width_percent: Option<f32>,
+    /// Console height in percent of the screen height, taking precedence over `height`
+    pub height_percent: Option<f32>,
+    /// Console resizable, resizing updates `width` and `height`
+    pub resizable: bool,
+    /// Console can be dragged around, moving it updates `left_pos` and `top_pos`
+    pub movable: bool,
+    /// Seconds the console takes to slide down from the top of the screen when opened and back
+    /// up when closed, `0.0` shows and hides it instantly
+    pub slide_duration: f32,
+    /// Console title bar
+    pub title_bar: bool,
+    /// Text of the title bar, setting it shows the title bar. Defaults to "Console"
+    pub title: Option<String>,
+    /// Console collapsible
+    pub collapsible: bool,
+    /// Show a close button in the title bar, clicking it closes the console
+    pub show_close_button: bool,
+    /// Keep the console window above other egui windows, like inspector windows, while it's open
+    pub always_on_top: bool,
+    /// Corner rounding of the console window
+    pub rounding: f32,
+    /// Width of the console window border, colored with [`ConsoleTheme::border`]
+    pub border_width: f32,
+    /// Space between the console window border and its contents
+    pub inner_margin: f32,
+    /// Registered console commands
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub commands: BTreeMap<String, RegisteredCommand>,
+    /// Alternative names of commands, mapped to the command name.
+    ///
+    /// Aliases set with clap's `#[command(alias = "...")]` are used as well. An alias never shadows
+    /// a command with the same name.
+    pub aliases: BTreeMap<String, String>,
+    /// Aliases which still run their command but print a warning naming it, e.g. `tp` after
+    /// renaming it to `teleport`. `help` lists them apart from the other aliases.
+    ///
+    /// They can be set in [`ConsoleConfiguration::aliases`] or declared with clap.
+    pub deprecated_aliases: BTreeSet<String>,
+    /// Match entered command names and aliases ignoring ASCII case, e.g. `HELP` runs `help`.
+    ///
+    /// Commands whose names only differ by case from a registered command aren't registered.
+    pub case_insensitive_commands: bool,
+    /// Run the only command starting with an entered name which isn't a command, e.g. `hel`
+    /// runs `help`. If several commands start with it, the entered line is rejected.
+    pub allow_prefix_matching: bool,
+    /// Print an error for entered commands which aren't registered. An
+    /// [`UnknownConsoleCommand`] is sent either way.
+    pub print_unknown_command_error: bool,
+    /// Echo the lines run with [`ConsoleRunner::run`] into the scrollback, like entered lines
+    pub echo_run_commands: bool,
+    /// Panic at startup if a command name was added more than once, instead of printing the
+    /// conflicts in the scrollback. Only the command added last runs either way.
+    pub panic_on_duplicate_commands: bool,
+    /// Print how long the command systems took after each frame commands were entered in, e.g.
+    /// `(took 12.3 ms)`. Toggled with the `timing` command
+    pub show_timings: bool,
+    /// Built-in commands which aren't added, e.g. `exit` in a shipped game.
+    ///
+    /// Built-ins are also left out if the app registers a command with the same name.
+    pub disabled_commands: BTreeSet<String>,
+    /// Add the built-in `exit` command, turn it off to keep players from quitting through the
+    /// console in a shipped game
+    pub allow_exit_command: bool,
+    /// Time after which a command waiting for confirmation is cancelled, see
+    /// [`ConsoleCommand::confirm`]. `None` waits for the next entered line however long it takes
+    pub confirmation_timeout: Option<Duration>,
+    /// Name and version printed by the `version` command, usually set with [`app_version!`]
+    ///
+    /// [`app_version!`]: crate::app_version
+    pub app_version: Option<String>,
+    /// Number of commands to store in history
+    pub history_size: usize,
+    /// Buttons shown under the input line as `(label, command)`, clicking one runs its command
+    /// as if it was typed
+    pub quick_commands: Vec<(String, String)>,
+    /// Number of commands suggested in a popup while typing a command name, 0 disables the popup
+    pub suggestion_count: usize,
+    /// Number of lines to keep in the scrollback, the oldest lines are dropped first
+    pub scrollback_size: usize,
+    ///Line prefix symbol
+    pub symbol: String,
+    /// Font size of the scrollback and the input line
+    pub font_size: f32,
+    /// Multiplier of the console size, margins and font size, e.g. for small text on high DPI
+    /// screens.
+    ///
+    /// egui already follows the window scale factor, this scales the console on top of it and
+    /// is picked up on the next frame when changed, resizing the console to the scaled size.
+    pub ui_scale: f32,
+    /// Font family of the scrollback and the input line
+    pub font: FontChoice,
+    /// Wrap scrollback lines wider than the console, indenting the continuation rows.
+    ///
+    /// When disabled, long lines can be scrolled horizontally and only the visible part of the
+    /// scrollback is laid out each frame.
+    pub wrap_lines: bool,
+    /// Collapse consecutive identical lines into one line with a repeat counter
+    pub collapse_duplicates: bool,
+    /// Multiplier of the mouse wheel scroll distance in the scrollback
+    pub scroll_speed: f32,
+    /// Animate jumps within the scrollback, e.g. to the bottom or by a page
+    pub smooth_scrolling: bool,
+    /// Prefix lines with the [`PrintConsoleLine::source`] that printed them
+    pub show_sources: bool,
+    /// Number of characters after which a scrollback line is cut off until it is clicked.
+    ///
+    /// Laying out very long lines is slow, the full text is kept for copying.
+    pub max_line_length: usize,
+    /// Console colors
+    #[cfg(feature = "egui")]
+    pub theme: ConsoleTheme,
+    /// Messages printed by the console and its built-in commands
+    pub localization: ConsoleLocalization,
+    /// Base direction of the scrollback and the input line, for right-to-left UIs
+    pub text_direction: TextDirection,
+    /// Jump to the bottom of the scrollback whenever the console is opened.
+    ///
+    /// When disabled the console reopens at the scroll position it was closed at, unless it was
+    /// following the bottom of the scrollback.
+    pub scroll_to_bottom_on_open: bool,
+    /// Ungrab and show the cursor of the primary window while the console is open, restoring
+    /// its grab mode and visibility when it closes
+    pub release_cursor: bool,
+    /// Pause [`Time<Virtual>`] while the console is open, unless it was already paused.
+    ///
+    /// Other things like game states can be paused on [`ConsoleOpened`] and [`ConsoleClosed`].
+    pub pause_time: bool,
+    /// Print panics in the console, in addition to the previous panic hook. Set before
+    /// `Startup`, when the hook is installed
+    pub capture_panics: bool,
+    /// Open the console when a panic is printed with [`ConsoleConfiguration::capture_panics`]
+    pub open_on_panic: bool,
+    /// Run lines read from the standard input as if they were entered, and write the lines added
+    /// to the scrollback to the standard output, e.g. on servers without a window. Set before
+    /// `Startup`, when the input starts being read
+    pub read_stdin: bool,
+    /// Where lines added to the scrollback are also written, without styling
+    pub mirror: Vec<ConsoleMirror>,
+    /// Start mirrored lines with the UTC time of day they were added at
+    pub mirror_timestamps: bool,
+    /// Append to mirror files which already exist rather than truncating them
+    pub mirror_append: bool,
+    /// Size past which a mirror file is moved aside to `<path>.1` and started over
+    pub mirror_max_bytes: Option<u64>,
+    /// Color the input line by whether it would run while it's typed, red with the reason after
+    /// it if it can't and dimmed if the command is unknown. Entering the line isn't prevented
+    pub validate_input: bool,
+    /// Show a status bar under the input line with the number of commands, the scrollback size,
+    /// the history position and the FPS if [`FrameTimeDiagnosticsPlugin`] is added.
+    ///
+    /// [`FrameTimeDiagnosticsPlugin`]: bevy::diagnostic::FrameTimeDiagnosticsPlugin
+    pub show_status_bar: bool,
+    /// Changes the egui style of the console contents before they are laid out, for settings
+    /// not covered by the configuration like spacing or the scroll bar width.
+    ///
+    /// Runs every frame the console is shown, so it should be cheap.
+    #[cfg(feature = "egui")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub ui_style: Option<UiStyle>,
+    /// Script run once at startup, after the commands added in `Startup` are registered.
+    ///
+    /// Its lines are run like with `exec`, so their output is in the scrollback once the console
+    /// is opened. Without the `default_commands` feature, `exec` lines in it are unknown commands.
+    pub autoexec: Option<PathBuf>,
+    /// Sees each non-blank line entered in the console before it's parsed, e.g. to require a
+    /// leading `/` for commands and treat other lines as chat.
+    ///
+    /// The prompt line always shows the line as typed. Lines run by scripts, bindings or
+    /// `history` aren't filtered.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub input_filter: Option<InputFilter>,
+    /// Console this configures
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub marker: PhantomData<M>,
+}
+
+impl<M: ConsoleMarker> Default for ConsoleConfiguration<M> {
+    fn default() -> Self {
+        Self {
+            keys: vec![ToggleConsoleKey::KeyCode(KeyCode::Grave)],
+            layout: ConsoleLayout::Window,
+            window: ConsoleWindowTarget::Primary,
+            left_pos: 0.0,
+            top_pos: 0.0,
+            height: 400.0,
+            width: 800.0,
+            anchor: None,
+            width_percent: None,
+            height_percent: None,
+            resizable: false,
+            movable: false,
+            slide_duration: 0.0,
+            title_bar: false,
+            title: None,
+            collapsible: false,
+            show_close_button: false,
+            always_on_top: false,
+            rounding: 4.0,
+            border_width: 1.0,
+            inner_margin: 4.0,
+            commands: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            deprecated_aliases: BTreeSet::new(),
+            case_insensitive_commands: false,
+            allow_prefix_matching: false,
+            print_unknown_command_error: true,
+            echo_run_commands: false,
+            panic_on_duplicate_commands: false,
+            show_timings: false,
+            disabled_commands: BTreeSet::new(),
+            allow_exit_command: true,
+            confirmation_timeout: Some(Duration::from_secs(30)),
+            app_version: None,
+            history_size: 50,
+            scrollback_size: 10_000,
+            suggestion_count: 5,
+            quick_commands: Vec::new(),
+            symbol: "> ".to_owned(),
+            font_size: 14.0,
+            ui_scale: 1.0,
+            font: FontChoice::Monospace,
+            wrap_lines: true,
+            collapse_duplicates: false,
+            scroll_speed: 1.0,
+            smooth_scrolling: true,
+            show_sources: false,
+            max_line_length: 4096,
+            #[cfg(feature = "egui")]
+            theme: ConsoleTheme::dark(),
+            localization: ConsoleLocalization::default(),
+            text_direction: TextDirection::LeftToRight,
+            scroll_to_bottom_on_open: true,
+            release_cursor: false,
+            pause_time: false,
+            capture_panics: false,
+            open_on_panic: false,
+            read_stdin: false,
+            mirror: Vec::new(),
+            mirror_timestamps: false,
+            mirror_append: true,
+            mirror_max_bytes: None,
+            show_status_bar: false,
+            validate_input: true,
+            #[cfg(feature = "egui")]
+            ui_style: None,
+            autoexec: None,
+            input_filter: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<M: ConsoleMarker> ConsoleConfiguration<M> {
+    /// Registers a console command right away, e.g. from a plugin loaded after startup.
+    ///
+    /// The command is only handled if a system reading its [`ConsoleCommand`] is scheduled, like
+    /// the one added by [`AddConsoleCommand::add_console_command`].
+    pub fn register_console_command<T: Command>(&mut self) {
+        self.register_console_command_with::<T>(CommandMeta::default());
+    }
+
+    /// Registers a console command right away with options like hiding it from `help`.
+    pub fn register_console_command_with<T: Command>(&mut self, meta: CommandMeta) {
+        // Commands derived without a `name` are named after their struct instead of the crate
+        let command = T::command().name(T::name());
+        self.register_raw_console_command_with(T::name(), command, meta);
+    }
+
+    /// Registers a console command by name, handled by reading [`ConsoleCommandEntered`] events.
+    ///
+    /// Unlike [`ConsoleConfiguration::register_console_command`] the name doesn't have to be
+    /// known at compile time.
+    pub fn register_raw_console_command(
+        &mut self,
+        name: impl Into<String>,
+        command: clap::Command,
+    ) {
+        self.register_raw_console_command_with(name, command, CommandMeta::default());
+    }
+
+    /// Registers a console command by name with options like hiding it from `help`.
+    pub fn register_raw_console_command_with(
+        &mut self,
+        name: impl Into<String>,
+        command: clap::Command,
+        meta: CommandMeta,
+    ) {
+        let name = name.into();
+        let command = console_clap_command(command);
+        if self.case_insensitive_commands {
+            let existing = self
+                .commands
+                .keys()
+                .find(|existing| **existing != name && existing.eq_ignore_ascii_case(&name));
+            if let Some(existing) = existing {
+                warn!(
+                    "console command '{name}' differs from '{existing}' only by case and wasn't registered"
+                );
+                return;
+            }
+        }
+        if self.commands.contains_key(&name) {
+            warn!(
+                "console command '{}' already registered and was overwritten",
+                name
+            );
+        }
+        self.commands
+            .insert(name, RegisteredCommand { command, meta });
+    }
+
+    /// Name of the command `name` refers to, either directly or as an alias.
+    pub(crate) fn resolve_command(&self, name: &str) -> Option<&str> {
+        if let Some((name, _)) = self.commands.get_key_value(name) {
+            return Some(name);
+        }
+        let matches = |other: &str| {
+            other == name || (self.case_insensitive_commands && other.eq_ignore_ascii_case(name))
+        };
+        if let Some(command) = self.commands.keys().find(|command| matches(command)) {
+            return Some(command);
+        }
+
+        let configured = self
+            .aliases
+            .iter()
+            .find(|(alias, _)| matches(alias))
+            .and_then(|(_, target)| self.commands.get_key_value(target.as_str()));
+        let declared = || {
+            self.commands
+                .iter()
+                .find(|(_, registered)| registered.command.get_all_aliases().any(&matches))
+        };
+        configured.or_else(declared).map(|(name, _)| name.as_str())
+    }
+
+    /// Command to use instead of `name` if it's a deprecated alias.
+    pub(crate) fn deprecation(&self, name: &str) -> Option<&str> {
+        let deprecated = self.deprecated_aliases.iter().any(|alias| {
+            alias == name || (self.case_insensitive_commands && alias.eq_ignore_ascii_case(name))
+        });
+        self.resolve_command(name)
+            .filter(|command| deprecated && !command.eq_ignore_ascii_case(name))
+    }
+
+    /// Names of the commands starting with `prefix` which aren't hidden, sorted.
+    pub(crate) fn commands_with_prefix(&self, prefix: &str) -> Vec<&str> {
+        self.visible_commands()
+            .filter(|command| {
+                command.starts_with(prefix)
+                    || (self.case_insensitive_commands
+                        && command
+                            .get(..prefix.len())
+                            .is_some_and(|start| start.eq_ignore_ascii_case(prefix)))
+            })
+            .collect()
+    }
+
+    /// Names of the commands which aren't hidden, sorted.
+    pub(crate) fn visible_commands(&self) -> impl Iterator<Item = &str> {
+        self.commands
+            .iter()
+            .filter(|(_, registered)| !registered.meta.hidden)
+            .map(|(name, _)| name.as_str())
+    }
+
+    #[cfg(feature = "default_commands")]
+    /// Whether the built-in command `name` is turned off by the configuration.
+    pub(crate) fn is_builtin_disabled(&self, name: &str) -> bool {
+        self.disabled_commands.contains(name) || (name == "exit" && !self.allow_exit_command)
+    }
+
+    /// Whether the command `name` takes its arguments untouched, see [`CommandMeta::raw_args`].
+    pub(crate) fn has_raw_args(&self, name: &str) -> bool {
+        self.resolve_command(name)
+            .and_then(|name| self.commands.get(name))
+            .is_some_and(|registered| registered.meta.raw_args)
+    }
+
+    #[cfg(feature = "default_commands")]
+    /// Aliases of the command `name`, sorted.
+    pub(crate) fn aliases_of(&self, name: &str) -> Vec<&str> {
+        let configured = self
+            .aliases
+            .iter()
+            .filter(|(_, target)| *target == name)
+            .map(|(alias, _)| alias.as_str());
+        let declared = self
+            .commands
+            .get(name)
+            .into_iter()
+            .flat_map(|registered| registered.command.get_all_aliases());
+
+        let mut aliases = configured
+            .chain(declared)
+            .filter(|alias| !self.commands.contains_key(*alias))
+            .collect::<Vec<_>>();
+        aliases.sort_unstable();
+        aliases.dedup();
+        aliases
+    }
+
+    /// Removes a console command, returning it if it was registered.
+    ///
+    /// Its system stays scheduled, but the console stops sending it the command and reports it as
+    /// invalid instead.
+    pub fn unregister_console_command(&mut self, name: &str) -> Option<clap::Command> {
+        self.commands
+            .remove(name)
+            .map(|registered| registered.command)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<M: ConsoleMarker> ConsoleConfiguration<M> {
+    /// Reads a configuration from RON, e.g. the console section of a settings file.
+    ///
+    /// Fields left out keep their default value. Commands are registered into the configuration
+    /// at startup, so it should be inserted before the app runs.
+    pub fn from_ron_str(ron: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(ron)
+    }
+
+    /// Writes the configuration as RON, without the registered commands and callbacks.
+    pub fn to_ron_string(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+}
+
+/// Add a console commands to Bevy app.
+pub trait AddConsoleCommand {
+    /// Add a console command with a given system.
+    ///
+    /// This registers the console command so it will print with the built-in `help` console command.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_console::{AddConsoleCommand, ConsoleCommand};
+    /// # use clap::Parser;
+    /// App::new()
+    ///     .add_console_command::<LogCommand, _>(log_command);
+    /// #
+    /// # /// Prints given arguments to the console.
+    /// # #[derive(Parser, ConsoleCommand)]
+    /// # #[command(name = "log")]
+    /// # struct LogCommand;
+    /// #
+    /// # fn log_command(mut log: ConsoleCommand<LogCommand>) {}
+    /// ```
+    fn add_console_command<T: Command, Params>(
+        &mut self,
+        system: impl IntoSystemConfigs<Params>,
+    ) -> &mut Self;
+
+    /// Add a console command with a given system and options like hiding it from `help`.
+    ///
+    /// Adding a command with the same name again replaces the earlier system, which no longer
+    /// runs. The conflict is printed in the console at startup, see
+    /// [`ConsoleConfiguration::panic_on_duplicate_commands`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_console::{AddConsoleCommand, CommandMeta, ConsoleCommand};
+    /// # use clap::Parser;
+    /// App::new().add_console_command_with::<NoclipCommand, _>(
+    ///     noclip_command,
+    ///     CommandMeta {
+    ///         hidden: true,
+    ///         requires: Some("cheats".to_owned()),
+    ///         ..default()
+    ///     },
+    /// );
+    /// #
+    /// # /// Flies through walls.
+    /// # #[derive(Parser, ConsoleCommand)]
+    /// # #[command(name = "noclip")]
+    /// # struct NoclipCommand;
+    /// #
+    /// # fn noclip_command(mut noclip: ConsoleCommand<NoclipCommand>) {}
+    /// ```
+    fn add_console_command_with<T: Command, Params>(
+        &mut self,
+        system: impl IntoSystemConfigs<Params>,
+        meta: CommandMeta,
+    ) -> &mut Self;
+
+    /// Add a console command handled by a system which is run once for every valid invocation.
+    ///
+    /// The system gets the parsed command as its [`In`] input, followed by any other system
+    /// params. Returning `Ok(())` prints `[ok]`, returning an error prints it followed by
+    /// `[failed]`. Invalid arguments are reported before the system would run.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_console::{AddConsoleCommand, ConsoleCommand};
+    /// # use clap::Parser;
+    /// App::new().add_fallible_console_command::<SpawnCommand, _>(spawn_command);
+    ///
+    /// /// Spawns entities
+    /// #[derive(Parser, ConsoleCommand)]
+    /// #[command(name = "spawn")]
+    /// struct SpawnCommand {
+    ///     count: usize,
+    /// }
+    ///
+    /// fn spawn_command(In(spawn): In<SpawnCommand>, mut commands: Commands) -> Result<(), String> {
+    ///     if spawn.count > 100 {
+    ///         return Err(format!("can't spawn {} entities at once", spawn.count));
+    ///     }
+    ///     for _ in 0..spawn.count {
+    ///         commands.spawn(SpatialBundle::default());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    fn add_fallible_console_command<T: Command, Params>(
+        &mut self,
+        system: impl IntoSystem<T, Result<(), String>, Params>,
+    ) -> &mut Self;
+
+    /// Add a console command to the console with the marker `M`, see [`instance`](crate::instance).
+    ///
+    /// The command is only run from that console, by a system reading its
+    /// `ConsoleCommand<T, M>`. Other consoles can have commands of the same name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_console::{AddConsoleCommand, ConsoleCommand};
+    /// # use clap::Parser;
+    /// #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    /// struct AssetConsole;
+    ///
+    /// App::new().add_console_command_for::<AssetConsole, ReloadCommand, _>(reload_command);
+    /// #
+    /// # /// Reloads the assets
+    /// # #[derive(Parser, ConsoleCommand)]
+    /// # #[command(name = "reload")]
+    /// # struct ReloadCommand;
+    /// #
+    /// # fn reload_command(mut reload: ConsoleCommand<ReloadCommand, AssetConsole>) {}
+    /// ```
+    fn add_console_command_for<M: ConsoleMarker, T: Command, Params>(
+        &mut self,
+        system: impl IntoSystemConfigs<Params>,
+    ) -> &mut Self;
+
+    /// Add a console command to the console with the marker `M` with options like hiding it
+    /// from `help`.
+    fn add_console_command_for_with<M: ConsoleMarker, T: Command, Params>(
+        &mut self,
+        system: impl IntoSystemConfigs<Params>,
+        meta: CommandMeta,
+    ) -> &mut Self;
+}
+
+impl AddConsoleCommand for App {
+    fn add_console_command<T: Command, Params>(
+        &mut self,
+        system: impl IntoSystemConfigs<Params>,
+    ) -> &mut Self {
+        self.add_console_command_with::<T, Params>(system, CommandMeta::default())
+    }
+
+    fn add_console_command_with<T: Command, Params>(
+        &mut self,
+        system: impl IntoSystemConfigs<Params>,
+        meta: CommandMeta,
+    ) -> &mut Self {
+        self.add_console_command_for_with::<DefaultConsole, T, Params>(system, meta)
+    }
+
+    fn add_fallible_console_command<T: Command, Params>(
+        &mut self,
+        system: impl IntoSystem<T, Result<(), String>, Params>,
+    ) -> &mut Self {
+        let mut system = IntoSystem::into_system(system);
+        let mut command_state = None::<SystemState<ConsoleCommand<T>>>;
+        let run = move |world: &mut World| {
+            let command_state = command_state.get_or_insert_with(|| {
+                system.initialize(world);
+                SystemState::new(world)
+            });
+            // Parse errors are printed while taking the invocations
+            let invocations = command_state
+                .get_mut(world)
+                .take_all()
+                .into_iter()
+                .filter_map(Result::ok)
+                .collect::<Vec<_>>();
+
+            let labels = world
+                .get_resource::<StatusLabels>()
+                .cloned()
+                .unwrap_or_default();
+            for command in invocations {
+                let result = system.run(command, world);
+                system.apply_deferred(world);
+                let mut reply = |line: StyledStr| {
+                    let line = PrintConsoleLine::<DefaultConsole>::new(line);
+                    world.send_event(line.with_source(T::name()));
+                };
+                match result {
+                    Ok(()) => reply(colored(AnsiColor::Green, &labels.ok)),
+                    Err(err) => {
+                        reply(err.into());
+                        reply(colored(AnsiColor::Red, &labels.failed));
+                    }
+                }
+            }
+        };
+
+        self.add_console_command::<T, _>(run)
+    }
+
+    fn add_console_command_for<M: ConsoleMarker, T: Command, Params>(
+        &mut self,
+        system: impl IntoSystemConfigs<Params>,
+    ) -> &mut Self {
+        self.add_console_command_for_with::<M, T, Params>(system, CommandMeta::default())
+    }
+
+    fn add_console_command_for_with<M: ConsoleMarker, T: Command, Params>(
+        &mut self,
+        system: impl IntoSystemConfigs<Params>,
+        meta: CommandMeta,
+    ) -> &mut Self {
+        let id = self
+            .world
+            .get_resource_or_insert_with(CommandOwners::<M>::default)
+            .claim(T::name());
+        let sys = move |mut config: ResMut<ConsoleConfiguration<M>>,
+                        owners: Res<CommandOwners<M>>| {
+            if owners.owns(T::name(), id) {
+                config.register_console_command_with::<T>(meta.clone());
+            }
+        };
+        // Earlier systems added for the same name would respond to its invocations as well
+        let owned = move |owners: Res<CommandOwners<M>>| owners.owns(T::name(), id);
+
+        self.add_systems(Startup, sys)
+            .add_systems(Update, system.run_if(owned).in_set(ConsoleSet::Commands))
+    }
+}
+
+/// Which [`AddConsoleCommand`] call handles each command name of a console, only the last one
+/// added runs.
+#[derive(Default, Resource)]
+pub(crate) struct CommandOwners<M = DefaultConsole> {
+    registrations: usize,
+    owners: BTreeMap<&'static str, usize>,
+    /// Number of times each command name was added
+    counts: BTreeMap<&'static str, usize>,
+    marker: PhantomData<M>,
+}
+
+impl<M> CommandOwners<M> {
+    /// Makes a new registration the owner of `name`, returning its id.
+    fn claim(&mut self, name: &'static str) -> usize {
+        self.registrations += 1;
+        let count = self.counts.entry(name).or_default();
+        *count += 1;
+        if *count > 1 {
+            warn!("console command '{name}' was added {count} times, only the last one runs");
+        }
+        self.owners.insert(name, self.registrations);
+        self.registrations
+    }
+
+    fn owns(&self, name: &str, id: usize) -> bool {
+        self.owners.get(name) == Some(&id)
+    }
+
+    /// Command names added more than once, with the number of times they were added.
+    fn duplicates(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        self.counts
+            .iter()
+            .filter(|(_, count)| **count > 1)
+            .map(|(name, count)| (*name, *count))
+    }
+}
+
+/// Lists the command names added more than once, or panics if
+/// [`ConsoleConfiguration::panic_on_duplicate_commands`] is set.
+pub(crate) fn report_duplicate_commands<M: ConsoleMarker>(
+    owners: Res<CommandOwners<M>>,
+    config: Res<ConsoleConfiguration<M>>,
+    mut console_line: EventWriter<PrintConsoleLine<M>>,
+) {
+    let localization = &config.localization;
+    let duplicates = owners
+        .duplicates()
+        .map(|(name, count)| {
+            fill(
+                &localization.duplicate_command,
+                &[("name", &name), ("count", &count)],
+            )
+        })
+        .collect::<Vec<_>>();
+    if duplicates.is_empty() {
+        return;
+    }
+    let message = fill(
+        &localization.duplicate_commands,
+        &[("names", &duplicates.join(", "))],
+    );
+    if config.panic_on_duplicate_commands {
+        panic!("{message}");
+    }
+    console_line.send(PrintConsoleLine::new(colored(AnsiColor::Yellow, &message)));
+}
+
+/// A console command registered in [`ConsoleConfiguration::commands`].
+#[derive(Clone, Debug)]
+pub struct RegisteredCommand {
+    /// Definition of the command, used to parse its arguments and print its help
+    pub command: clap::Command,
+    /// Options of the command
+    pub meta: CommandMeta,
+}
+
+/// Options of a console command, see [`AddConsoleCommand::add_console_command_with`].
+#[derive(Clone, Debug, Default)]
+pub struct CommandMeta {
+    /// Leaves the command out of `help` and completion, it can still be run
+    pub hidden: bool,
+    /// Name of the gate which has to be open to run the command, see [`ConsoleGates`]
+    pub requires: Option<String>,
+    /// Pass everything after the command name as a single argument, untouched by quoting or
+    /// `;`, e.g. for a `lua <code>` command with one `String` argument.
+    ///
+    /// The argument is left out if nothing follows the command name.
+    pub raw_args: bool,
+    /// Ask the user to confirm the command before it runs, like [`ConsoleCommand::confirm`]
+    pub confirm: bool,
+    /// The command is done in a later frame than it's entered in, e.g. once a level loaded, and
+    /// sends [`CommandFinished`] then.
+    ///
+    /// Console variables set for the command, like `gravity=0 load_level`, keep their value
+    /// until then, rather than until the end of the frame.
+    pub finishes_later: bool,
+}
+
+/// Gates enabling commands which require them, e.g. cheats.
+///
+/// A gate is open if it's set to `true` here, or if a `bool` console variable of the same name
+/// is `true`. Running a command whose gate is closed fails with an error.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct ConsoleGates {
+    /// Gate names mapped to whether they're open
+    pub gates: BTreeMap<String, bool>,
+}
+
+impl ConsoleGates {
+    /// Whether the gate `name` is open.
+    pub fn is_open(&self, name: &str, cvars: &ConsoleVariables) -> bool {
+        self.gates.get(name).copied().unwrap_or(false) || cvars.get::<bool>(name) == Some(true)
+    }
+}
+
+/// Aliases defined at runtime with the built-in `alias` command.
+///
+/// When the first word of an entered line is an alias, it's replaced with the alias text before
+/// the line is parsed, keeping the rest of the line as additional arguments. Aliases never
+/// shadow registered commands.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct ConsoleAliases {
+    /// Alias names mapped to the text they expand to
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl ConsoleAliases {
+    /// Expands aliases at the start of `input`, each alias at most once so aliases referring to
+    /// each other can't loop forever.
+    pub(crate) fn expand<M: ConsoleMarker>(
+        &self,
+        config: &ConsoleConfiguration<M>,
+        input: &str,
+    ) -> String {
+        let mut line = input.trim().to_owned();
+        let mut expanded = Vec::new();
+        loop {
+            let (name, rest) = line
+                .split_once(char::is_whitespace)
+                .unwrap_or((line.as_str(), ""));
+            if config.resolve_command(name).is_some() || expanded.iter().any(|done| done == name) {
+                break;
+            }
+            let Some(expansion) = self.aliases.get(name) else {
+                break;
+            };
+
+            let next = if rest.is_empty() {
+                expansion.clone()
+            } else {
+                format!("{expansion} {rest}")
+            };
+            expanded.push(name.to_owned());
+            line = next;
+        }
+        line
+    }
+}
+
+/// Console open state
+#[derive(Default, Resource)]
+pub struct ConsoleOpen<M = DefaultConsole> {
+    /// Console open
+    pub open: bool,
+    /// Console this state belongs to
+    #[doc(hidden)]
+    pub marker: PhantomData<M>,
+}
+
+/// Whether the console's input line has keyboard focus, updated by the console every frame it's
+/// shown.
+///
+/// Read by the [`not_typing_in_console`](crate::not_typing_in_console) run condition.
+#[derive(Default, Resource)]
+pub struct ConsoleInputFocus<M = DefaultConsole> {
+    /// Input line focused
+    pub focused: bool,
+    /// Console this state belongs to
+    #[doc(hidden)]
+    pub marker: PhantomData<M>,
+}
+
+/// What opened or closed the console.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConsoleToggleSource {
+    /// One of the [`ConsoleConfiguration::keys`]
+    Key,
+    /// A write to [`ConsoleOpen`] from a system
+    Code,
+    /// The close button of the console window
+    CloseButton,
+}
+
+/// Event sent when the console opens.
+///
+/// Sent after [`ConsoleOpen::open`] changes, whatever changed it, so it can be read from
+/// [`ConsoleSet::PostCommands`](crate::ConsoleSet::PostCommands) on.
+#[derive(Clone, Copy, Debug, Event, PartialEq, Eq)]
+pub struct ConsoleOpened<M = DefaultConsole> {
+    /// What opened the console
+    pub source: ConsoleToggleSource,
+    /// Console opened
+    #[doc(hidden)]
+    pub marker: PhantomData<M>,
+}
+
+/// Event sent when the console closes.
+///
+/// Sent after [`ConsoleOpen::open`] changes, whatever changed it, so it can be read from
+/// [`ConsoleSet::PostCommands`](crate::ConsoleSet::PostCommands) on.
+#[derive(Clone, Copy, Debug, Event, PartialEq, Eq)]
+pub struct ConsoleClosed<M = DefaultConsole> {
+    /// What closed the console
+    pub source: ConsoleToggleSource,
+    /// Console closed
+    #[doc(hidden)]
+    pub marker: PhantomData<M>,
+}
+
+/// Read-only overlay showing the console scrollback.
+///
+/// The overlay has no input line and never takes keyboard focus, so it can stay up while playing.
+/// It is independent of [`ConsoleOpen`] and can be shown together with the console.
+#[derive(Clone, Resource)]
+pub struct ConsoleOverlay {
+    /// Overlay shown
+    pub open: bool,
+    /// Left position
+    pub left_pos: f32,
+    /// Top position
+    pub top_pos: f32,
+    /// Overlay width
+    pub width: f32,
+    /// Maximum overlay height, older lines are cut off at the top
+    pub height: f32,
+    /// Seconds after which lines start fading out, `None` keeps them visible
+    pub fade_after: Option<f32>,
+    /// Seconds it takes a line to fade out
+    pub fade_duration: f32,
+}
+
+impl Default for ConsoleOverlay {
+    fn default() -> Self {
+        Self {
+            open: false,
+            left_pos: 10.0,
+            top_pos: 10.0,
+            width: 500.0,
+            height: 200.0,
+            fade_after: Some(5.0),
+            fade_duration: 1.0,
+        }
+    }
+}
+
+/// Event to clear the console scrollback.
+///
+/// Unlike [`ResetConsole`] the input line and the command history are kept. Only the default
+/// console is cleared.
+#[derive(Clone, Copy, Debug, Default, Event)]
+pub struct ClearConsole;
+
+/// Event to reset the console to a pristine state.
+///
+/// Clears the scrollback, the input line, the command history and the queued commands, cancels
+/// the commands waiting for confirmation and `fps watch`, and restores the log filter set with
+/// `loglevel`. Lines printed before the reset which weren't received yet are dropped, so progress
+/// lines updated afterwards start a new line. Registered commands and the
+/// [`ConsoleConfiguration`] are left untouched. Only the default console is reset.
+#[derive(Clone, Copy, Debug, Default, Event)]
+pub struct ResetConsole {
+    /// Also restore every console variable to the value it was registered with, sending
+    /// [`CvarChanged`](crate::CvarChanged) for those which change
+    pub cvars: bool,
+}
+
+/// Sets up a clap command to parse the arguments of console commands.
+pub(crate) fn console_clap_command(command: clap::Command) -> clap::Command {
+    let command = command
+        .no_binary_name(true)
+        .color(clap::ColorChoice::Always)
+        // `help entity spawn` prints the help of subcommands
+        .disable_help_subcommand(true);
+    // Entering just the command prints its help listing the subcommands, instead of an error
+    if command.is_subcommand_required_set() {
+        command.arg_required_else_help(true)
+    } else {
+        command
+    }
+}
+
+/// Wraps `text` in the ANSI escape sequences for the given color.
+pub(crate) fn colored(color: AnsiColor, text: &str) -> StyledStr {
+    let style = color.on_default();
+    format!("{}{text}{}", style.render(), style.render_reset()).into()
+}
+
+/// What produced a scrollback line.
+pub(crate) enum LineKind {
+    /// Printed with a [`PrintConsoleLine`] or by the console itself
+    Output,
+    /// Echo of an entered command, with the prompt symbol laid out as its own run
+    Echo { prompt: String },
+}
+
+/// A single line of the console scrollback.
+pub(crate) struct ScrollbackLine {
+    pub(crate) text: StyledStr,
+    /// Number of text rows in `text` when not wrapped
+    pub(crate) rows: usize,
+    /// Number of consecutive times this line was printed
+    pub(crate) count: usize,
+    /// What printed this line
+    pub(crate) source: Option<String>,
+    /// Key of a progress line which is still being updated
+    pub(crate) progress_key: Option<String>,
+    pub(crate) kind: LineKind,
+    /// Real time since startup at which this line was last printed
+    pub(crate) printed_at: Duration,
+    /// Show all of `text` even if it is longer than the maximum line length
+    pub(crate) expanded: bool,
+    /// Whether the cached layout cuts `text` off
+    pub(crate) truncated: bool,
+    /// Where the command this line echoes or replies to was entered
+    pub(crate) origin: Option<CommandOrigin>,
+    /// Cached text layout of `text`, built lazily by the UI
+    #[cfg(feature = "egui")]
+    pub(crate) layout: Option<crate::ui::LineLayout>,
+}
+
+impl ScrollbackLine {
+    pub(crate) fn new(text: impl Into<StyledStr>) -> Self {
+        let text = text.into();
+        let rows = text.ansi().to_string().matches('\n').count() + 1;
+        Self {
+            text,
+            rows,
+            count: 1,
+            source: None,
+            progress_key: None,
+            kind: LineKind::Output,
+            printed_at: Duration::ZERO,
+            expanded: false,
+            truncated: false,
+            origin: None,
+            #[cfg(feature = "egui")]
+            layout: None,
+        }
+    }
+
+    pub(crate) fn echo(prompt: &str, text: impl Into<StyledStr>) -> Self {
+        Self {
+            kind: LineKind::Echo {
+                prompt: prompt.to_owned(),
+            },
+            ..Self::new(text)
+        }
+    }
+
+    /// Sets the real time since startup at which the line was printed.
+    pub(crate) fn at(mut self, printed_at: Duration) -> Self {
+        self.printed_at = printed_at;
+        self
+    }
+
+    /// Tags the line with where the command it echoes or replies to was entered.
+    pub(crate) fn with_origin(mut self, origin: CommandOrigin) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// The line as shown in the console, with styling stripped.
+    pub(crate) fn plain_text(&self) -> String {
+        let prompt = match &self.kind {
+            LineKind::Echo { prompt } => prompt.as_str(),
+            LineKind::Output => "",
+        };
+        if self.count > 1 {
+            format!("{prompt}{} (x{})", self.text, self.count)
+        } else {
+            format!("{prompt}{}", self.text)
+        }
+    }
+}
+
+#[derive(Resource)]
+pub(crate) struct ConsoleState<M = DefaultConsole> {
+    pub(crate) buf: String,
+    pub(crate) scrollback: Vec<ScrollbackLine>,
+    pub(crate) history: VecDeque<StyledStr>,
+    pub(crate) history_index: usize,
+    /// The input submitted this frame was added to the history, shifting the entry numbers
+    pub(crate) input_recorded: bool,
+    /// The user scrolled away from the bottom of the scrollback
+    pub(crate) scrolled_up: bool,
+    /// Scrollback length when the user scrolled away from the bottom
+    pub(crate) seen_lines: usize,
+    /// Scroll to the bottom of the scrollback on the next frame
+    pub(crate) scroll_to_bottom: bool,
+    /// Vertical scroll offset of the scrollback when it was last shown
+    pub(crate) scroll_offset: f32,
+    /// Largest vertical scroll offset of the scrollback when it was last shown
+    pub(crate) max_scroll_offset: f32,
+    /// Offset the scrollback is being scrolled to, infinite for the bottom
+    pub(crate) scroll_target: Option<f32>,
+    /// The pointer was over the scrollback when it was last shown
+    pub(crate) scrollback_hovered: bool,
+    /// Highlighted entry of the command suggestion popup
+    pub(crate) suggestion_index: usize,
+    /// The suggestion popup was closed with escape since the input last changed
+    pub(crate) suggestions_dismissed: bool,
+    /// Command which the next entered line confirms or cancels
+    pub(crate) pending_confirmation: Option<PendingConfirmation<M>>,
+    /// Input line last validated and the resources it was resolved with, with the result
+    pub(crate) validation: Option<(String, LookupGeneration, Option<InputValidation>)>,
+    /// What toggled [`ConsoleOpen`] this frame, when the console did it itself
+    pub(crate) toggle_source: Option<ConsoleToggleSource>,
+    /// Lines added to the scrollback since they were last mirrored
+    pub(crate) mirror_lines: Vec<String>,
+    /// Replies to remote clients added to the scrollback since they were last sent, with the id
+    /// of the connection they go to
+    #[cfg(feature = "remote")]
+    pub(crate) remote_lines: Vec<(u64, String)>,
+}
+
+impl<M> Default for ConsoleState<M> {
+    fn default() -> Self {
+        ConsoleState {
+            buf: String::default(),
+            scrollback: Vec::new(),
+            history: VecDeque::from([StyledStr::new()]),
+            history_index: 0,
+            input_recorded: false,
+            scrolled_up: false,
+            seen_lines: 0,
+            scroll_to_bottom: false,
+            scroll_offset: 0.0,
+            max_scroll_offset: 0.0,
+            scroll_target: None,
+            scrollback_hovered: false,
+            suggestion_index: 0,
+            suggestions_dismissed: false,
+            pending_confirmation: None,
+            validation: None,
+            toggle_source: None,
+            mirror_lines: Vec::new(),
+            #[cfg(feature = "remote")]
+            remote_lines: Vec::new(),
+        }
+    }
+}
+
+impl<M> ConsoleState<M> {
+    /// Makes the next entered line confirm or cancel `command`, returning the command which was
+    /// waiting for confirmation before.
+    ///
+    /// With `repeat`, entering the command again or an empty line confirms it.
+    pub(crate) fn await_confirmation(
+        &mut self,
+        command: ConsoleCommandEntered<M>,
+        deadline: Option<Duration>,
+        repeat: bool,
+    ) -> Option<ConsoleCommandEntered<M>> {
+        let pending = PendingConfirmation {
+            command,
+            deadline,
+            repeat,
+        };
+        self.pending_confirmation
+            .replace(pending)
+            .map(|pending| pending.command)
+    }
+
+    /// Adds a line to the scrollback, to be mirrored.
+    pub(crate) fn push_line(&mut self, line: ScrollbackLine) {
+        self.record_line(&line);
+        self.scrollback.push(line);
+    }
+
+    /// Keeps a line added to the scrollback to be mirrored, and sent to the remote client it
+    /// replies to.
+    fn record_line(&mut self, line: &ScrollbackLine) {
+        let text = line.plain_text();
+        #[cfg(feature = "remote")]
+        if let (Some(CommandOrigin::Remote(id)), LineKind::Output) = (line.origin, &line.kind) {
+            self.remote_lines.push((id, text.clone()));
+        }
+        self.mirror_lines.push(text);
+    }
+
+    /// Returns the console to its initial state.
+    pub(crate) fn reset(&mut self) {
+        *self = ConsoleState::default();
+    }
+
+    /// Removes all lines from the scrollback, along with the scroll position tracking them.
+    pub(crate) fn clear_scrollback(&mut self) {
+        self.scrollback.clear();
+        self.scrolled_up = false;
+        self.seen_lines = 0;
+        self.scroll_to_bottom = true;
+        self.scroll_offset = 0.0;
+        self.max_scroll_offset = 0.0;
+    }
+
+    /// Drops the oldest scrollback lines beyond `size`.
+    pub(crate) fn trim_scrollback(&mut self, size: usize) {
+        let excess = self.scrollback.len().saturating_sub(size);
+        if excess > 0 {
+            self.scrollback.drain(..excess);
+            self.seen_lines = self.seen_lines.saturating_sub(excess);
+        }
+    }
+
+    /// Replaces the input with the previous history entry, returning whether there was one.
+    pub(crate) fn history_previous(&mut self) -> bool {
+        if self.history_index + 1 >= self.history.len() {
+            return false;
+        }
+
+        if self.history_index == 0 && !self.buf.trim().is_empty() {
+            self.history[0] = self.buf.clone().into();
+        }
+
+        self.history_index += 1;
+        self.buf = self.history[self.history_index].to_string();
+        true
+    }
+
+    /// Replaces the input with the next history entry, returning whether there was one.
+    pub(crate) fn history_next(&mut self) -> bool {
+        if self.history_index == 0 {
+            return false;
+        }
+
+        self.history_index -= 1;
+        self.buf = self.history[self.history_index].to_string();
+        true
+    }
+
+    /// The `n`-th most recent history entry, `1` being the last line entered.
+    pub(crate) fn history_entry(&self, n: usize) -> Option<String> {
+        if n == 0 {
+            return None;
+        }
+        self.history.get(n).map(ToString::to_string)
+    }
+
+    #[cfg(feature = "default_commands")]
+    /// The history entries with their numbers, oldest first.
+    pub(crate) fn numbered_history(&self) -> impl Iterator<Item = (usize, String)> + '_ {
+        (1..self.history.len())
+            .rev()
+            .filter_map(|n| self.history_entry(n).map(|entry| (n, entry)))
+    }
+
+    #[cfg(feature = "default_commands")]
+    /// The history entry numbered `n` when the current input was submitted.
+    ///
+    /// Commands referring to entries by number run after their own line was added to the history,
+    /// which would otherwise shift the numbers by one.
+    pub(crate) fn history_entry_before_input(&self, n: usize) -> Option<String> {
+        self.history_entry(n + usize::from(self.input_recorded))
+    }
+
+    #[cfg(feature = "default_commands")]
+    /// Removes all history entries.
+    pub(crate) fn clear_history(&mut self) {
+        self.history = VecDeque::from([StyledStr::new()]);
+        self.history_index = 0;
+    }
+
+    /// Expands a history reference at the start of the input, keeping the rest of the input.
+    ///
+    /// `!!` is the last line entered, `!n` the `n`-th most recent line and `!prefix` the most
+    /// recent line starting with `prefix`.
+    pub(crate) fn expand_history(&self, input: &str) -> Result<String, String> {
+        let Some(reference) = input.trim_start().strip_prefix('!') else {
+            return Ok(input.to_owned());
+        };
+        let end = reference
+            .find(char::is_whitespace)
+            .unwrap_or(reference.len());
+        let (reference, rest) = reference.split_at(end);
+
+        let entry = if reference.is_empty() {
+            return Ok(input.to_owned());
+        } else if reference == "!" {
+            self.history_entry(1)
+        } else if let Ok(n) = reference.parse() {
+            self.history_entry(n)
+        } else {
+            self.history
+                .iter()
+                .skip(1)
+                .map(ToString::to_string)
+                .find(|entry| entry.starts_with(reference))
+        };
+        entry
+            .map(|entry| format!("{entry}{rest}"))
+            .ok_or_else(|| format!("!{reference}: event not found"))
+    }
+
+    /// Plain text of the last `count` scrollback lines, joined with newlines.
+    pub(crate) fn scrollback_text(&self, count: usize) -> String {
+        let start = self.scrollback.len().saturating_sub(count);
+        self.scrollback[start..]
+            .iter()
+            .map(ScrollbackLine::plain_text)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub(crate) fn clear_console(
+    mut events: EventReader<ClearConsole>,
+    mut console_state: ResMut<ConsoleState>,
+) {
+    if events.read().count() > 0 {
+        console_state.clear_scrollback();
+    }
+}
+
+/// Sends [`ConsoleOpened`] and [`ConsoleClosed`] when [`ConsoleOpen::open`] changed since the
+/// last frame.
+pub(crate) fn send_console_toggles<M: ConsoleMarker>(
+    console_open: Res<ConsoleOpen<M>>,
+    mut state: ResMut<ConsoleState<M>>,
+    mut was_open: Local<bool>,
+    mut opened: EventWriter<ConsoleOpened<M>>,
+    mut closed: EventWriter<ConsoleClosed<M>>,
+) {
+    // Toggles written back in the same frame cancel out and aren't reported
+    let source = state.toggle_source.take();
+    if console_open.open == *was_open {
+        return;
+    }
+    *was_open = console_open.open;
+    let source = source.unwrap_or(ConsoleToggleSource::Code);
+    let marker = PhantomData;
+    if console_open.open {
+        opened.send(ConsoleOpened { source, marker });
+    } else {
+        closed.send(ConsoleClosed { source, marker });
+    }
+}
+
+/// Releases the cursor while the console is open, for [`ConsoleConfiguration::release_cursor`].
+pub(crate) fn release_cursor(
+    config: Res<ConsoleConfiguration>,
+    mut opened: EventReader<ConsoleOpened>,
+    mut closed: EventReader<ConsoleClosed>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut grabbed: Local<Option<(CursorGrabMode, bool)>>,
+) {
+    let opened = opened.read().count() > 0;
+    let closed = closed.read().count() > 0;
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+    if opened && config.release_cursor && grabbed.is_none() {
+        *grabbed = Some((window.cursor.grab_mode, window.cursor.visible));
+        window.cursor.grab_mode = CursorGrabMode::None;
+        window.cursor.visible = true;
+    } else if closed {
+        // Restored even if the option was turned off while open
+        if let Some((grab_mode, visible)) = grabbed.take() {
+            window.cursor.grab_mode = grab_mode;
+            window.cursor.visible = visible;
+        }
+    }
+}
+
+/// Pauses the virtual time while the console is open, for [`ConsoleConfiguration::pause_time`].
+pub(crate) fn pause_time(
+    config: Res<ConsoleConfiguration>,
+    mut opened: EventReader<ConsoleOpened>,
+    mut closed: EventReader<ConsoleClosed>,
+    mut time: ResMut<Time<Virtual>>,
+    mut paused: Local<bool>,
+) {
+    let opened = opened.read().count() > 0;
+    let closed = closed.read().count() > 0;
+    if opened && config.pause_time && !time.is_paused() {
+        time.pause();
+        *paused = true;
+    } else if closed && *paused {
+        time.unpause();
+        *paused = false;
+    }
+}
+
+pub(crate) fn reset_console(
+    mut events: EventReader<ResetConsole>,
+    mut console_state: ResMut<ConsoleState>,
+    mut queue: ResMut<CommandQueue>,
+    mut reader: ResMut<ConsoleLineReader>,
+    lines: Res<Events<PrintConsoleLine>>,
+    mut cvars: ResMut<ConsoleVariables>,
+    mut changed: EventWriter<CvarChanged>,
+) {
+    let (mut reset, mut reset_cvars) = (false, false);
+    for event in events.read() {
+        reset = true;
+        reset_cvars |= event.cvars;
+    }
+    if !reset {
+        return;
+    }
+    console_state.reset();
+    reader.0.clear(&lines);
+    queue.lines.clear();
+    queue.wait = None;
+    queue.script = None;
+    for (name, value) in cvars.end_overrides() {
+        changed.send(CvarChanged { name, value });
+    }
+    if reset_cvars {
+        for (name, value) in cvars.reset_all() {
+            changed.send(CvarChanged { name, value });
+        }
+    }
+}
+
+/// When the commands entered this frame were sent, for [`ConsoleConfiguration::show_timings`].
+#[derive(Default, Resource)]
+pub(crate) struct CommandTimer {
+    started: Option<Instant>,
+    commands: usize,
+}
+
+/// Starts timing the commands entered this frame, once the console has sent all of them.
+pub(crate) fn start_command_timer(
+    mut timer: ResMut<CommandTimer>,
+    config: Res<ConsoleConfiguration>,
+    mut entered: EventReader<ConsoleCommandEntered>,
+) {
+    let commands = entered.read().count();
+    if config.show_timings && commands > 0 {
+        timer.started = Some(Instant::now());
+        timer.commands = commands;
+    }
+}
+
+/// Prints how long the command systems took, whether or not the commands replied.
+pub(crate) fn print_command_timings(
+    mut timer: ResMut<CommandTimer>,
+    config: Res<ConsoleConfiguration>,
+    mut console_line: EventWriter<PrintConsoleLine>,
+) {
+    let Some(started) = timer.started.take() else {
+        return;
+    };
+    if !config.show_timings {
+        return;
+    }
+    let millis = format!("{:.1}", started.elapsed().as_secs_f64() * 1000.0);
+    let localization = &config.localization;
+    let text = match timer.commands {
+        1 => fill(&localization.command_timing, &[("millis", &millis)]),
+        count => fill(
+            &localization.commands_timing,
+            &[("count", &count), ("millis", &millis)],
+        ),
+    };
+    console_line.send(PrintConsoleLine::new(colored(
+        AnsiColor::BrightBlack,
+        &text,
+    )));
+}
+
+/// How often each console command was entered, printed by the `stats` command.
+///
+/// Every [`ConsoleCommandEntered`] is counted, whether it was typed, run by a script or a key
+/// binding, or sent by the app.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct ConsoleCommandStats {
+    /// Usage of each entered command, by name
+    pub commands: BTreeMap<String, CommandUsage>,
+}
+
+/// Usage of a console command, see [`ConsoleCommandStats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandUsage {
+    /// Number of times the command was entered with valid arguments
+    pub invocations: u64,
+    /// Number of times the command was entered with arguments it couldn't parse
+    pub parse_failures: u64,
+    /// When the command was last entered, as the [`Time<Real>`] elapsed since startup
+    pub last_used: Duration,
+}
+
+pub(crate) fn record_command_stats(
+    mut stats: ResMut<ConsoleCommandStats>,
+    time: Res<Time<Real>>,
+    mut entered: EventReader<ConsoleCommandEntered>,
+    mut parse_failures: EventReader<CommandParseFailed>,
+) {
+    for command in entered.read() {
+        let usage = stats
+            .commands
+            .entry(command.command_name.clone())
+            .or_default();
+        usage.invocations += 1;
+        usage.last_used = time.elapsed();
+    }
+    // Console variables and raw commands without a definition always parse
+    for failure in parse_failures.read() {
+        let usage = stats
+            .commands
+            .entry(failure.command_name.to_owned())
+            .or_default();
+        usage.invocations = usage.invocations.saturating_sub(1);
+        usage.parse_failures += 1;
+    }
+}
+
+/// Reads the [`PrintConsoleLine`] events for [`receive_console_line`], which runs both before the
+/// input is handled and after commands ran, and mustn't receive a line twice.
+#[derive(Default, Resource)]
+pub(crate) struct ConsoleLineReader<M: ConsoleMarker = DefaultConsole>(
+    ManualEventReader<PrintConsoleLine<M>>,
+);
+
+pub(crate) fn receive_console_line<M: ConsoleMarker>(
+    mut console_state: ResMut<ConsoleState<M>>,
+    config: Res<ConsoleConfiguration<M>>,
+    time: Res<Time<Real>>,
+    mut reader: ResMut<ConsoleLineReader<M>>,
+    events: Res<Events<PrintConsoleLine<M>>>,
+) {
+    for event in reader.0.read(&events) {
+        let event: &PrintConsoleLine<M> = event;
+
+        let mut line = ScrollbackLine::new(event.line.clone()).at(time.elapsed());
+        line.source = event.source.clone();
+        line.origin = event.origin;
+        // Updated progress lines and collapsed duplicates are mirrored as they come
+        console_state.record_line(&line);
+
+        if let Some(progress) = &event.progress {
+            let (key, done) = match progress {
+                Progress::Update(key) => (key, false),
+                Progress::Done(key) => (key, true),
+            };
+            if !done {
+                line.progress_key = Some(key.clone());
+            }
+
+            let live_line = console_state.scrollback.iter_mut().rev().find(|live| {
+                live.progress_key.as_ref() == Some(key) && live.source == event.source
+            });
+            if let Some(live_line) = live_line {
+                *live_line = line;
+            } else {
+                console_state.scrollback.push(line);
+            }
+            continue;
+        }
+
+        if config.collapse_duplicates {
+            if let Some(last) = console_state.scrollback.last_mut() {
+                if last.text == event.line
+                    && last.source == event.source
+                    && last.origin == event.origin
+                    && last.progress_key.is_none()
+                    && event.line != StyledStr::new()
+                {
+                    last.count += 1;
+                    last.printed_at = time.elapsed();
+                    continue;
+                }
+            }
+        }
+
+        console_state.scrollback.push(line);
+    }
+
+    console_state.trim_scrollback(config.scrollback_size);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as bevy_console;
+    use crate::cvar::{apply_cvar_overrides, restore_cvar_overrides};
+    use crate::dispatch::run_queued_commands;
+    use crate::instance;
+    use crate::{
+        reply, reply_result, ConsoleClosed, ConsoleCommand, ConsoleCommandEntered,
+        ConsoleConfiguration, ConsoleOpen, ConsoleOpened, PrintConsoleLine, UnknownConsoleCommand,
+    };
+    use clap::Parser;
+
+    /// Counts invocations
+    #[derive(Parser, ConsoleCommand)]
+    #[command(name = "count")]
+    struct CountCommand {
+        amount: u32,
+    }
+
+    #[derive(Default, Resource)]
+    struct Counted(Vec<u32>);
+
+    fn count_command(mut count: ConsoleCommand<CountCommand>, mut counted: ResMut<Counted>) {
+        while let Some(result) = count.take() {
+            if let Ok(CountCommand { amount }) = result {
+                counted.0.push(amount);
+            }
+        }
+    }
+
+    /// Enters each command line in the default console, then updates the app once and returns
+    /// the lines printed in the console.
+    fn run_commands(app: &mut App, lines: &[&str]) -> Vec<String> {
+        for line in lines {
+            let mut words = Shlex::new(line);
+            app.world.send_event(ConsoleCommandEntered {
+                command_name: words.next().unwrap_or_default(),
+                args: words.collect(),
+                raw: line.to_string(),
+                confirmed: false,
+                origin: CommandOrigin::Local,
+                overrides: Vec::new(),
+                marker: PhantomData,
+            });
+        }
+        app.update();
+
+        let events = app.world.resource::<Events<PrintConsoleLine>>();
+        events
+            .get_reader()
+            .read(events)
+            .map(|line| line.line.to_string())
+            .collect()
+    }
+
+    /// Spawns an enemy
+    #[derive(Parser, ConsoleCommand)]
+    struct SpawnEnemyCommand;
+
+    #[test]
+    fn test_derived_name_defaults_to_struct_name() {
+        assert_eq!(SpawnEnemyCommand::name(), "spawn_enemy");
+
+        let mut config = ConsoleConfiguration::default();
+        config.register_console_command::<SpawnEnemyCommand>();
+        let registered = &config.commands["spawn_enemy"];
+        assert_eq!(registered.command.get_name(), "spawn_enemy");
+    }
+
+    /// Spawns and despawns entities
+    #[derive(Parser, ConsoleCommand)]
+    #[command(name = "entity")]
+    struct EntityCommand {
+        #[command(subcommand)]
+        action: EntityAction,
+    }
+
+    #[derive(clap::Subcommand, Debug, PartialEq)]
+    enum EntityAction {
+        /// Spawns an entity
+        Spawn { shape: String },
+        /// Despawns an entity
+        Despawn { index: u32 },
+    }
+
+    #[derive(Default, Resource)]
+    struct EntityActions(Vec<EntityAction>);
+
+    fn entity_command(
+        mut entity: ConsoleCommand<EntityCommand>,
+        mut actions: ResMut<EntityActions>,
+    ) {
+        while let Some(result) = entity.take() {
+            if let Ok(EntityCommand { action }) = result {
+                actions.0.push(action);
+            }
+        }
+    }
+
+    #[test]
+    fn test_subcommands() {
+        let mut app = App::new();
+        app.init_resource::<EntityActions>()
+            .init_resource::<ConsoleConfiguration>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, entity_command);
+        app.world
+            .resource_mut::<ConsoleConfiguration>()
+            .register_console_command::<EntityCommand>();
+
+        let lines = run_commands(
+            &mut app,
+            &["entity spawn cube", "entity despawn 42", "entity"],
+        );
+
+        assert_eq!(
+            app.world.resource::<EntityActions>().0,
+            [
+                EntityAction::Spawn {
+                    shape: "cube".to_owned()
+                },
+                EntityAction::Despawn { index: 42 },
+            ]
+        );
+        // The command without a subcommand prints its help instead of an error
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("Usage: entity <COMMAND>"));
+        assert!(lines[0].contains("despawn"));
+
+        let entity = &app.world.resource::<ConsoleConfiguration>().commands["entity"];
+        assert!(entity.command.find_subcommand("spawn").is_some());
+        assert!(entity.command.find_subcommand("help").is_none());
+    }
+
+    #[test]
+    fn test_register_and_unregister_at_runtime() {
+        let mut config = ConsoleConfiguration::default();
+        config.register_console_command::<CountCommand>();
+        config.register_raw_console_command(format!("mod_{}", 1), clap::Command::new("mod_1"));
+
+        assert!(config.commands.contains_key("count"));
+        assert!(config.commands.contains_key("mod_1"));
+
+        assert!(config.unregister_console_command("mod_1").is_some());
+        assert!(config.unregister_console_command("mod_1").is_none());
+        assert!(!config.commands.contains_key("mod_1"));
+    }
+
+    #[test]
+    fn test_aliases_resolve_to_commands() {
+        let mut config = ConsoleConfiguration::default();
+        config.register_raw_console_command("teleport", clap::Command::new("teleport").alias("tp"));
+        config.register_raw_console_command("warp", clap::Command::new("warp"));
+        config
+            .aliases
+            .insert("go".to_owned(), "teleport".to_owned());
+        config
+            .aliases
+            .insert("warp".to_owned(), "teleport".to_owned());
+        config
+            .aliases
+            .insert("gone".to_owned(), "missing".to_owned());
+
+        assert_eq!(config.resolve_command("teleport"), Some("teleport"));
+        assert_eq!(config.resolve_command("tp"), Some("teleport"));
+        assert_eq!(config.resolve_command("go"), Some("teleport"));
+        assert_eq!(config.resolve_command("warp"), Some("warp"));
+        assert_eq!(config.resolve_command("gone"), None);
+        #[cfg(feature = "default_commands")]
+        assert_eq!(config.aliases_of("teleport"), ["go", "tp"]);
+    }
+
+    #[test]
+    fn test_deprecated_aliases() {
+        let mut config = ConsoleConfiguration::default();
+        config.register_raw_console_command("teleport", clap::Command::new("teleport").alias("tp"));
+        config.register_raw_console_command("warp", clap::Command::new("warp"));
+        config
+            .aliases
+            .insert("go".to_owned(), "teleport".to_owned());
+        for alias in ["tp", "warp", "missing"] {
+            config.deprecated_aliases.insert(alias.to_owned());
+        }
+
+        assert_eq!(config.deprecation("tp"), Some("teleport"));
+        assert_eq!(config.deprecation("go"), None);
+        assert_eq!(config.deprecation("teleport"), None);
+        // Commands shadow aliases, and aliases of nothing have nothing to point to
+        assert_eq!(config.deprecation("warp"), None);
+        assert_eq!(config.deprecation("missing"), None);
+    }
+
+    #[test]
+    fn test_history_expansion() {
+        let mut state = ConsoleState::<DefaultConsole>::default();
+        for line in ["spawn cube", "god", "spawn sphere"] {
+            state.history.insert(1, line.into());
+        }
+
+        assert_eq!(state.expand_history("!!"), Ok("spawn sphere".to_owned()));
+        assert_eq!(state.expand_history("!3"), Ok("spawn cube".to_owned()));
+        assert_eq!(
+            state.expand_history("!g --force"),
+            Ok("god --force".to_owned())
+        );
+        assert_eq!(
+            state.expand_history("!spawn"),
+            Ok("spawn sphere".to_owned())
+        );
+        assert_eq!(state.expand_history("say !!"), Ok("say !!".to_owned()));
+        assert_eq!(state.expand_history("! hi"), Ok("! hi".to_owned()));
+        assert_eq!(
+            state.expand_history("!spwan"),
+            Err("!spwan: event not found".to_owned())
+        );
+        assert_eq!(
+            state.expand_history("!4"),
+            Err("!4: event not found".to_owned())
+        );
+    }
+
+    #[cfg(feature = "default_commands")]
+    #[test]
+    fn test_numbered_history() {
+        let mut state = ConsoleState::<DefaultConsole>::default();
+        for line in ["spawn cube", "god", "spawn sphere"] {
+            state.history.insert(1, line.into());
+        }
+
+        assert_eq!(
+            state.numbered_history().collect::<Vec<_>>(),
+            [
+                (3, "spawn cube".to_owned()),
+                (2, "god".to_owned()),
+                (1, "spawn sphere".to_owned())
+            ]
+        );
+
+        // `history 2` run after its own line was added refers to the same entry as `!2`
+        state.history.insert(1, "history 2".into());
+        state.input_recorded = true;
+        assert_eq!(state.history_entry_before_input(2), Some("god".to_owned()));
+    }
+
+    #[test]
+    fn test_parse_wait() {
+        let now = Duration::from_secs(10);
+        let localization = ConsoleLocalization::default();
+        let parse_wait = |command| parse_wait(command, now, &localization);
+        assert_eq!(parse_wait("wait"), Some(Ok(Wait::Frames(1))));
+        assert_eq!(parse_wait("wait 60"), Some(Ok(Wait::Frames(60))));
+        assert_eq!(
+            parse_wait("wait 2s"),
+            Some(Ok(Wait::Until(Duration::from_secs(12))))
+        );
+        assert_eq!(
+            parse_wait("wait 500ms"),
+            Some(Ok(Wait::Until(Duration::from_millis(10_500))))
+        );
+        assert!(matches!(parse_wait("wait -1s"), Some(Err(_))));
+        assert!(matches!(parse_wait("wait 1 2"), Some(Err(_))));
+        assert_eq!(parse_wait("waiter 1"), None);
+    }
+
+    #[test]
+    fn test_case_insensitive_commands() {
+        let mut config = ConsoleConfiguration::default();
+        config.register_raw_console_command("help", clap::Command::new("help"));
+        config.register_raw_console_command("teleport", clap::Command::new("teleport").alias("tp"));
+        assert_eq!(config.resolve_command("HELP"), None);
+
+        config.case_insensitive_commands = true;
+        assert_eq!(config.resolve_command("HELP"), Some("help"));
+        assert_eq!(config.resolve_command("Tp"), Some("teleport"));
+        assert_eq!(config.commands_with_prefix("TE"), ["teleport"]);
+
+        config.register_raw_console_command("Help", clap::Command::new("Help"));
+        assert!(!config.commands.contains_key("Help"));
+    }
+
+    #[test]
+    fn test_alias_expansion() {
+        let mut config = ConsoleConfiguration::default();
+        config.register_raw_console_command("save", clap::Command::new("save"));
+        let mut aliases = ConsoleAliases::default();
+        for (name, expansion) in [
+            ("qs", "save quick"),
+            ("q", "qs"),
+            ("a", "a"),
+            ("save", "exit"),
+        ] {
+            aliases
+                .aliases
+                .insert(name.to_owned(), expansion.to_owned());
+        }
+
+        assert_eq!(aliases.expand(&config, "qs"), "save quick");
+        assert_eq!(
+            aliases.expand(&config, " q --slot 2"),
+            "save quick --slot 2"
+        );
+        assert_eq!(aliases.expand(&config, "a b"), "a b");
+        assert_eq!(aliases.expand(&config, "save x"), "save x");
+        assert_eq!(aliases.expand(&config, "help qs"), "help qs");
+    }
+
+    #[test]
+    fn test_command_entered_new() {
+        let entered = ConsoleCommandEntered::new("count", vec!["3".to_owned()]);
+
+        assert_eq!(entered.command_name, "count");
+        assert_eq!(entered.raw, "count 3");
+        assert!(!entered.confirmed);
+    }
+
+    #[test]
+    fn test_every_invocation_in_a_frame_is_handled() {
+        let mut app = App::new();
+        app.init_resource::<Counted>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, count_command);
+
+        let errors = run_commands(&mut app, &["count 1", "count oops", "count 3"]);
+
+        assert_eq!(app.world.resource::<Counted>().0, vec![1, 3]);
+        assert_eq!(errors.len(), 1);
+    }
+
+    fn checked_count_command(
+        In(CountCommand { amount }): In<CountCommand>,
+        mut counted: ResMut<Counted>,
+    ) -> Result<(), String> {
+        if amount == 0 {
+            return Err("nothing to count".to_owned());
+        }
+        counted.0.push(amount);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fallible_commands_print_their_result() {
+        let mut app = App::new();
+        app.init_resource::<Counted>()
+            .init_resource::<ConsoleConfiguration>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_fallible_console_command::<CountCommand, _>(checked_count_command);
+
+        let lines = run_commands(&mut app, &["count 2", "count 0", "count oops"]);
+
+        assert_eq!(app.world.resource::<Counted>().0, vec![2]);
+        // The parse error of `oops` is printed before the invocations run
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].contains("[ok]"));
+        assert_eq!(lines[2], "nothing to count");
+        assert!(lines[3].contains("[failed]"));
+    }
+
+    #[test]
+    fn test_duplicate_commands_only_run_the_last_one() {
+        fn doubled_count_command(
+            mut count: ConsoleCommand<CountCommand>,
+            mut counted: ResMut<Counted>,
+        ) {
+            while let Some(Ok(CountCommand { amount })) = count.take() {
+                counted.0.push(amount * 2);
+            }
+        }
+
+        let mut app = App::new();
+        app.init_resource::<Counted>()
+            .init_resource::<ConsoleConfiguration>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_console_command::<CountCommand, _>(count_command)
+            .add_console_command::<CountCommand, _>(doubled_count_command)
+            .add_systems(Startup, report_duplicate_commands::<DefaultConsole>);
+
+        let lines = run_commands(&mut app, &["count 3"]);
+
+        assert_eq!(app.world.resource::<Counted>().0, vec![6]);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("'count' (2 times)"));
+    }
+
+    #[test]
+    fn test_commands_only_run_in_their_console() {
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        struct OtherConsole;
+
+        fn other_count_command(
+            mut count: ConsoleCommand<CountCommand, OtherConsole>,
+            mut counted: ResMut<Counted>,
+        ) {
+            while let Some(Ok(CountCommand { amount })) = count.take() {
+                counted.0.push(amount * 10);
+            }
+        }
+
+        let mut app = App::new();
+        app.init_resource::<Counted>()
+            .init_resource::<ConsoleConfiguration>()
+            .init_resource::<instance::ConsoleConfiguration<OtherConsole>>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<instance::ConsoleCommandEntered<OtherConsole>>()
+            .add_event::<PrintConsoleLine>()
+            .add_event::<instance::PrintConsoleLine<OtherConsole>>()
+            .add_console_command::<CountCommand, _>(count_command)
+            .add_console_command_for::<OtherConsole, CountCommand, _>(other_count_command);
+
+        run_commands(&mut app, &["count 3"]);
+        assert_eq!(app.world.resource::<Counted>().0, vec![3]);
+
+        app.world
+            .send_event(instance::ConsoleCommandEntered::<OtherConsole>::new(
+                "count",
+                vec!["5".to_owned()],
+            ));
+        app.update();
+        assert_eq!(app.world.resource::<Counted>().0, vec![3, 50]);
+        // Each console registers the command in its own configuration
+        assert!(app
+            .world
+            .resource::<instance::ConsoleConfiguration<OtherConsole>>()
+            .commands
+            .contains_key("count"));
+    }
+
+    #[cfg(all(feature = "default_commands", feature = "log_capture"))]
+    #[test]
+    fn test_loglevel_command() {
+        use crate::commands::loglevel::loglevel_command;
+        use crate::ConsoleLogFilter;
+        use bevy::utils::tracing::level_filters::LevelFilter;
+
+        let mut app = App::new();
+        app.init_resource::<ConsoleLogFilter>()
+            .init_resource::<ConsoleConfiguration>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, loglevel_command);
+
+        let lines = run_commands(
+            &mut app,
+            &[
+                "loglevel warn",
+                "loglevel my_game::net debug",
+                "loglevel wgpu=error,naga=off",
+                "loglevel my_game::ai",
+            ],
+        );
+        let filter = app.world.resource::<ConsoleLogFilter>();
+        assert_eq!(filter.level, LevelFilter::WARN);
+        assert_eq!(
+            filter.modules.iter().collect::<Vec<_>>(),
+            [
+                (&"my_game::net".to_owned(), &LevelFilter::DEBUG),
+                (&"naga".to_owned(), &LevelFilter::OFF),
+                (&"wgpu".to_owned(), &LevelFilter::ERROR),
+            ]
+        );
+        assert_eq!(lines[0], "Log level set to warn");
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("missing the level of 'my_game::ai'")));
+
+        let lines = run_commands(&mut app, &["loglevel"]);
+        assert_eq!(
+            lines[lines.len() - 4..],
+            [
+                "Log level is warn",
+                "  my_game::net = debug",
+                "  naga = off",
+                "  wgpu = error"
+            ]
+        );
+    }
+
+    #[cfg(feature = "default_commands")]
+    #[test]
+    fn test_exit_command_asks_for_confirmation() {
+        use crate::commands::exit::exit_command;
+        use bevy::app::AppExit;
+
+        let mut app = App::new();
+        app.init_resource::<ConsoleConfiguration>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_event::<AppExit>()
+            .add_systems(Update, exit_command);
+
+        run_commands(&mut app, &["exit"]);
+        assert!(app.world.resource::<Events<AppExit>>().is_empty());
+        let requests = app.world.resource::<Events<RequestConfirmation>>();
+        let requested = ManualEventReader::<RequestConfirmation>::default()
+            .read(requests)
+            .map(|request| request.command.raw.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(requested, ["exit"]);
+
+        // Answering the prompt enters the command again, confirmed
+        let mut confirmed = ConsoleCommandEntered::new("exit", Vec::new());
+        confirmed.confirmed = true;
+        app.world.send_event(confirmed);
+        app.update();
+        assert!(!app.world.resource::<Events<AppExit>>().is_empty());
+
+        app.world.resource_mut::<Events<AppExit>>().clear();
+        run_commands(&mut app, &["exit --force"]);
+        assert!(!app.world.resource::<Events<AppExit>>().is_empty());
+    }
+
+    #[cfg(feature = "default_commands")]
+    #[test]
+    fn test_exit_confirmed_by_repeating_within_window() {
+        use crate::commands::exit::{exit_command, ExitCommand};
+        use crate::dispatch::{submit_input, CommandResources};
+        use bevy::app::AppExit;
+
+        #[derive(Default, Resource)]
+        struct Typed(Vec<&'static str>);
+
+        #[allow(clippy::too_many_arguments)]
+        fn submit(
+            mut typed: ResMut<Typed>,
+            mut state: ResMut<ConsoleState>,
+            config: Res<ConsoleConfiguration>,
+            resources: CommandResources,
+            mut queue: ResMut<CommandQueue>,
+            time: Res<Time<Real>>,
+            mut command_entered: EventWriter<ConsoleCommandEntered>,
+            mut unknown_command: EventWriter<UnknownConsoleCommand>,
+        ) {
+            for input in typed.0.drain(..) {
+                submit_input(
+                    input,
+                    &mut state,
+                    &resources.lookup(&config),
+                    &mut queue,
+                    &mut command_entered,
+                    &mut unknown_command,
+                    time.elapsed(),
+                );
+            }
+        }
+
+        let mut app = App::new();
+        app.init_resource::<ConsoleState>()
+            .init_resource::<ConsoleConfiguration>()
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<CommandQueue>()
+            .init_resource::<Time<Real>>()
+            .init_resource::<Typed>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<UnknownConsoleCommand>()
+            .add_event::<PrintConsoleLine>()
+            .add_event::<AppExit>()
+            .add_systems(
+                Update,
+                (
+                    submit,
+                    exit_command,
+                    request_confirmations::<DefaultConsole>,
+                )
+                    .chain(),
+            );
+        app.world
+            .resource_mut::<ConsoleConfiguration>()
+            .register_console_command::<ExitCommand>();
+        let enter = |app: &mut App, input: &'static str, elapsed: u64| {
+            let mut time = app.world.resource_mut::<Time<Real>>();
+            time.update_with_duration(Duration::from_secs(elapsed));
+            app.world.resource_mut::<Typed>().0.push(input);
+            app.update();
+            let mut exits = app.world.resource_mut::<Events<AppExit>>();
+            let exited = !exits.is_empty();
+            exits.clear();
+            exited
+        };
+
+        // Pressing enter on an empty line confirms
+        assert!(!enter(&mut app, "exit", 10));
+        assert!(enter(&mut app, "", 1));
+
+        // Entering the command again confirms
+        assert!(!enter(&mut app, "exit", 0));
+        assert!(enter(&mut app, "quit", 4));
+
+        // The window expires after five seconds
+        assert!(!enter(&mut app, "exit", 0));
+        assert!(!enter(&mut app, "", 6));
+        assert!(app
+            .world
+            .resource::<ConsoleState>()
+            .pending_confirmation
+            .is_none());
+        let printed = app.world.resource::<Events<PrintConsoleLine>>();
+        let timed_out = printed
+            .get_reader()
+            .read(printed)
+            .any(|line| line.line.to_string() == "`exit` timed out and was cancelled");
+        assert!(timed_out);
+    }
+
+    #[cfg(feature = "default_commands")]
+    #[test]
+    fn test_version_command() {
+        use crate::commands::version::version_command;
+
+        let mut app = App::new();
+        app.init_resource::<ConsoleConfiguration>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, version_command);
+
+        assert_eq!(run_commands(&mut app, &["version"]), ["unknown"]);
+
+        app.world.resource_mut::<ConsoleConfiguration>().app_version = Some("1.2.0".to_owned());
+        // Every invocation of the frame replies
+        let lines = run_commands(&mut app, &["version", "version"]);
+        assert_eq!(lines[lines.len() - 2..], ["1.2.0", "1.2.0"]);
+    }
+
+    #[cfg(feature = "default_commands")]
+    #[test]
+    fn test_find_command() {
+        use crate::commands::find::find_command;
+
+        let mut config = ConsoleConfiguration::default();
+        config.register_console_command::<CountCommand>();
+        config.register_console_command::<EntityCommand>();
+        let mut cvars = ConsoleVariables::default();
+        cvars.register_cvar("counter_limit", 3_u32);
+        let mut app = App::new();
+        app.insert_resource(config)
+            .insert_resource(cvars)
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, find_command);
+
+        let lines = run_commands(&mut app, &["find COUNT"]);
+        assert_eq!(
+            lines,
+            ["  count - Counts invocations", "  counter_limit = 3"]
+        );
+
+        app.world
+            .resource_mut::<ConsoleConfiguration>()
+            .localization
+            .find_no_match = "Rien ne correspond à '{pattern}'".to_owned();
+        let lines = run_commands(&mut app, &["find teleport", "find --regex ("]);
+        let lines = &lines[lines.len() - 4..];
+        assert_eq!(lines[0], "Rien ne correspond à 'teleport'");
+        assert!(lines[1].contains("[failed]"));
+        assert!(lines[2].starts_with("Invalid pattern '(': "));
+        assert!(lines[3].contains("[failed]"));
+    }
+
+    #[cfg(feature = "default_commands")]
+    #[test]
+    fn test_dump_command() {
+        use crate::commands::dump::{dump_command, utc_date_time};
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let mut app = App::new();
+        app.init_resource::<ConsoleConfiguration>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleState>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, dump_command);
+        {
+            let mut state = app.world.resource_mut::<ConsoleState>();
+            let echo =
+                ScrollbackLine::echo("> ", "spawn cube").at(Duration::from_millis(3_723_004));
+            state.push_line(echo.with_origin(CommandOrigin::Remote(3)));
+            let reply = ScrollbackLine::new("spawned").at(Duration::from_millis(3_723_004));
+            state.push_line(reply.with_origin(CommandOrigin::Script));
+            state.push_line(ScrollbackLine::new("log line").at(Duration::from_millis(1500)));
+        }
+        let path = std::env::temp_dir().join("bevy_console_dump.log");
+        let dump = format!("dump {}", path.display());
+
+        let lines = run_commands(&mut app, &[&dump]);
+        assert!(lines[0].starts_with("Wrote 3 lines to "));
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text, "> spawn cube\nspawned\nlog line\n");
+
+        run_commands(&mut app, &[&format!("{dump} --origins --header")]);
+        let text = std::fs::read_to_string(&path).unwrap();
+        let (header, lines) = text.split_once("\n\n").unwrap();
+        assert!(header.starts_with("# version: unknown\n# os: "));
+        assert_eq!(
+            lines,
+            "[01:02:03.004 remote 3] > spawn cube\n\
+             [01:02:03.004 exec] spawned\n\
+             [00:00:01.500 local] log line\n"
+        );
+        std::fs::remove_file(path).unwrap();
+
+        let at = |seconds| UNIX_EPOCH + Duration::from_secs(seconds);
+        assert_eq!(utc_date_time(at(0)), "1970-01-01 00:00:00 UTC");
+        assert_eq!(utc_date_time(at(951_782_400)), "2000-02-29 00:00:00 UTC");
+        assert_eq!(utc_date_time(at(1_710_000_000)), "2024-03-09 16:00:00 UTC");
+        assert!(utc_date_time(SystemTime::now()).ends_with(" UTC"));
+    }
+
+    #[cfg(feature = "default_commands")]
+    #[test]
+    fn test_bind_refuses_console_shortcuts() {
+        use crate::commands::bind::{bind_command, shortcuts_command};
+        use crate::{ConsoleBindings, ConsoleShortcuts};
+
+        let mut app = App::new();
+        app.init_resource::<ConsoleConfiguration>()
+            .init_resource::<ConsoleBindings>()
+            .init_resource::<ConsoleShortcuts>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, (bind_command, shortcuts_command));
+
+        let lines = run_commands(&mut app, &["bind grave toggle"]);
+        assert_eq!(
+            lines[0],
+            "Grave is the console shortcut to open or close the console, add --force to bind it \
+             anyway"
+        );
+        assert!(app.world.resource::<ConsoleBindings>().bindings.is_empty());
+
+        run_commands(
+            &mut app,
+            &["bind grave toggle --force", "bind f5 \"save quick\""],
+        );
+        let bindings = &app.world.resource::<ConsoleBindings>().bindings;
+        assert_eq!(bindings.get(&KeyCode::Grave).unwrap(), "toggle");
+        assert_eq!(bindings.get(&KeyCode::F5).unwrap(), "save quick");
+
+        let lines = run_commands(&mut app, &["shortcuts"]);
+        // Replies of the earlier commands are still buffered
+        let header = lines.iter().position(|line| line.starts_with("keys"));
+        let lines = &lines[header.unwrap()..];
+        let row = |keys: &str| {
+            lines
+                .iter()
+                .position(|line| line.starts_with(keys))
+                .unwrap_or_else(|| panic!("no row for {keys} in {lines:?}"))
+        };
+        assert!(lines[row("Ctrl+L")].ends_with("console"));
+        assert!(lines[row("F5")].contains("save quick"));
+        assert!(lines[row("F5")].ends_with("bind"));
+        let grave = row("Grave");
+        assert!(lines[grave].contains("open or close the console"));
+        assert!(lines[grave + 1].contains("toggle"));
+        assert!(lines[grave + 1].ends_with("bind, clashes with the console"));
+    }
+
+    #[test]
+    fn test_command_stats_count_parse_failures() {
+        let mut app = App::new();
+        app.init_resource::<ConsoleCommandStats>()
+            .init_resource::<Counted>()
+            .init_resource::<Time<Real>>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, (count_command, record_command_stats).chain());
+
+        run_commands(
+            &mut app,
+            &["count 1", "count oops", "count 2", "count --help", "god"],
+        );
+
+        let stats = app.world.resource::<ConsoleCommandStats>();
+        // Asking for the help isn't a parse failure
+        assert_eq!(stats.commands["count"].invocations, 3);
+        assert_eq!(stats.commands["count"].parse_failures, 1);
+        assert_eq!(stats.commands["god"].invocations, 1);
+    }
+
+    #[test]
+    fn test_console_writes_lines() {
+        use std::fmt::Write;
+
+        fn write_text(mut console: Console) {
+            write!(console, "first ").unwrap();
+            write!(console, "line\nsecond").unwrap();
+            writeln!(console, " line\n").unwrap();
+            write!(console, "unfinished").unwrap();
+        }
+
+        let mut app = App::new();
+        app.add_event::<PrintConsoleLine>()
+            .add_systems(Update, write_text);
+        app.update();
+
+        // The partial line is printed when the system returns
+        let events = app.world.resource::<Events<PrintConsoleLine>>();
+        let lines = ManualEventReader::<PrintConsoleLine>::default()
+            .read(events)
+            .map(|event| event.line.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(lines, ["first line", "second line", "", "unfinished"]);
+    }
+
+    #[test]
+    fn test_console_runner() {
+        #[derive(Default, Resource)]
+        struct RunResults(Vec<Result<(), String>>);
+
+        fn run_lines(mut console: ConsoleRunner, mut results: ResMut<RunResults>) {
+            for line in ["count 1; count 2", "  ", "count 3; god", "count '4"] {
+                results.0.push(console.run(line));
+            }
+        }
+
+        let mut config = ConsoleConfiguration::default();
+        config.register_console_command::<CountCommand>();
+        let mut app = App::new();
+        app.insert_resource(config)
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<ConsoleState>()
+            .init_resource::<CommandQueue>()
+            .init_resource::<Time<Real>>()
+            .init_resource::<RunResults>()
+            .init_resource::<Counted>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<UnknownConsoleCommand>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, (run_lines, count_command).chain());
+        app.update();
+
+        let results = &app.world.resource::<RunResults>().0;
+        assert_eq!(results[0], Ok(()));
+        assert!(results[1].is_err());
+        assert!(results[2]
+            .as_ref()
+            .is_err_and(|err| err.starts_with("error: unknown command 'god'")));
+        assert_eq!(
+            results[3],
+            Err("parse error: unterminated quote".to_owned())
+        );
+        // Lines with an invalid command don't run any of their commands, and commands chained
+        // after an entered one wait for it to run, like typed ones
+        assert_eq!(app.world.resource::<Counted>().0, vec![1]);
+        assert_eq!(app.world.resource::<CommandQueue>().lines, ["count 2"]);
+    }
+
+    #[test]
+    fn test_console_runner_waits_and_confirms() {
+        #[derive(Default, Resource)]
+        struct Lines(Vec<&'static str>);
+
+        fn run_line(mut console: ConsoleRunner, mut lines: ResMut<Lines>) {
+            if let Some(line) = lines.0.pop() {
+                console.run(line).unwrap();
+            }
+        }
+
+        let mut config = ConsoleConfiguration::default();
+        config.register_console_command::<CountCommand>();
+        config.register_raw_console_command_with(
+            "wipe",
+            clap::Command::new("wipe"),
+            CommandMeta {
+                confirm: true,
+                ..default()
+            },
+        );
+        let mut app = App::new();
+        app.insert_resource(config)
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<ConsoleState>()
+            .init_resource::<CommandQueue>()
+            .init_resource::<Time<Real>>()
+            .init_resource::<Counted>()
+            .insert_resource(Lines(vec!["wipe", "count 1; wait 2; count 2"]))
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<UnknownConsoleCommand>()
+            .add_event::<PrintConsoleLine>()
+            .add_event::<CommandFailed>()
+            .add_event::<CommandParseFailed>()
+            .add_systems(
+                Update,
+                (
+                    run_line,
+                    run_queued_commands::<DefaultConsole>,
+                    count_command,
+                )
+                    .chain(),
+            );
+
+        app.update();
+        assert_eq!(app.world.resource::<Counted>().0, vec![1]);
+        // Run while waiting, so it's queued after the waiting commands
+        app.update();
+        assert_eq!(app.world.resource::<Counted>().0, vec![1, 2]);
+        assert!(app
+            .world
+            .resource::<ConsoleState>()
+            .pending_confirmation
+            .is_none());
+        app.update();
+        let state = app.world.resource::<ConsoleState>();
+        let pending = state.pending_confirmation.as_ref().unwrap();
+        assert_eq!(pending.command.raw, "wipe");
+    }
+
+    #[cfg(feature = "remote")]
+    #[test]
+    fn test_remote_lines_reply_to_their_connection() {
+        #[derive(Default, Resource)]
+        struct Lines(Vec<(&'static str, CommandOrigin)>);
+
+        fn run_line(mut console: ConsoleRunner, mut lines: ResMut<Lines>) {
+            if let Some((line, origin)) = lines.0.pop() {
+                let _ = console.run_from(line, origin);
+            }
+        }
+
+        let mut config = ConsoleConfiguration::default();
+        config.register_raw_console_command_with(
+            "wipe",
+            clap::Command::new("wipe"),
+            CommandMeta {
+                confirm: true,
+                ..default()
+            },
+        );
+        let mut app = App::new();
+        app.insert_resource(config)
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<ConsoleState>()
+            .init_resource::<CommandQueue>()
+            .init_resource::<Time<Real>>()
+            .insert_resource(Lines(vec![
+                ("y", CommandOrigin::Remote(1)),
+                ("y", CommandOrigin::Remote(2)),
+                ("wipe", CommandOrigin::Remote(1)),
+            ]))
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<UnknownConsoleCommand>()
+            .add_event::<PrintConsoleLine>()
+            .add_event::<CommandFailed>()
+            .add_event::<CommandParseFailed>()
+            .add_systems(
+                Update,
+                (run_line, run_queued_commands::<DefaultConsole>).chain(),
+            );
+
+        app.update();
+        let state = app.world.resource::<ConsoleState>();
+        assert!(state.pending_confirmation.is_some());
+        assert!(!state.remote_lines.is_empty());
+        assert!(state.remote_lines.iter().all(|(id, _)| *id == 1));
+
+        // Another connection can't answer the prompt
+        app.update();
+        assert!(app
+            .world
+            .resource::<ConsoleState>()
+            .pending_confirmation
+            .is_some());
+
+        app.update();
+        let mut confirmed = app.world.resource_mut::<Events<ConsoleCommandEntered>>();
+        let confirmed: Vec<_> = confirmed.drain().collect();
+        assert_eq!(confirmed.len(), 1);
+        assert!(confirmed[0].confirmed);
+        assert_eq!(confirmed[0].origin, CommandOrigin::Remote(1));
+        assert!(app
+            .world
+            .resource::<ConsoleState>()
+            .pending_confirmation
+            .is_none());
+    }
+
+    #[test]
+    fn test_cvar_overrides_last_for_the_command() {
+        /// Falls with the current gravity
+        #[derive(Parser, ConsoleCommand)]
+        #[command(name = "fall")]
+        struct FallCommand;
+
+        /// Loads a level with the current gravity, done in a later frame
+        #[derive(Parser, ConsoleCommand)]
+        #[command(name = "load")]
+        struct LoadCommand;
+
+        /// Gravity each command ran with, and the loads which aren't done
+        #[derive(Default, Resource)]
+        struct Ran(Vec<f32>, Vec<ConsoleCommandEntered>);
+
+        fn fall_command(
+            mut fall: ConsoleCommand<FallCommand>,
+            cvars: Res<ConsoleVariables>,
+            mut ran: ResMut<Ran>,
+        ) {
+            while let Some(Ok(FallCommand)) = fall.take() {
+                ran.0.extend(cvars.get::<f32>("gravity"));
+            }
+        }
+
+        fn load_command(
+            mut load: ConsoleCommand<LoadCommand>,
+            cvars: Res<ConsoleVariables>,
+            mut ran: ResMut<Ran>,
+        ) {
+            while let Some(Ok(LoadCommand)) = load.take() {
+                ran.0.extend(cvars.get::<f32>("gravity"));
+                ran.1.extend(load.entered().cloned());
+            }
+        }
+
+        let mut config = ConsoleConfiguration::default();
+        config.register_console_command::<FallCommand>();
+        config.register_console_command_with::<LoadCommand>(CommandMeta {
+            finishes_later: true,
+            ..default()
+        });
+        let mut cvars = ConsoleVariables::default();
+        cvars.register_cvar("gravity", 9.81_f32);
+        let mut app = App::new();
+        app.insert_resource(config)
+            .insert_resource(cvars)
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<ConsoleState>()
+            .init_resource::<CommandQueue>()
+            .init_resource::<Time<Real>>()
+            .init_resource::<Ran>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<UnknownConsoleCommand>()
+            .add_event::<PrintConsoleLine>()
+            .add_event::<CommandFailed>()
+            .add_event::<CommandParseFailed>()
+            .add_event::<CommandFinished>()
+            .add_event::<CvarChanged>()
+            .add_systems(
+                Update,
+                (
+                    run_queued_commands::<DefaultConsole>,
+                    apply_cvar_overrides::<DefaultConsole>,
+                    (fall_command, load_command),
+                    restore_cvar_overrides::<DefaultConsole>,
+                )
+                    .chain(),
+            );
+        let gravity = |app: &App| {
+            let cvars = app.world.resource::<ConsoleVariables>();
+            cvars.get::<f32>("gravity").unwrap()
+        };
+        let run = |app: &mut App, line: &str| {
+            let mut queue = app.world.resource_mut::<CommandQueue>();
+            queue.lines.push_back(line.into());
+            app.update();
+        };
+
+        run(&mut app, "gravity=0 fall");
+        assert_eq!(app.world.resource::<Ran>().0, [0.0]);
+        assert_eq!(gravity(&app), 9.81);
+        let state = app.world.resource::<ConsoleState>();
+        let echo = state.scrollback.iter().map(ScrollbackLine::plain_text);
+        assert!(echo
+            .collect::<Vec<_>>()
+            .contains(&"gravity = 0 until the command finishes".to_owned()));
+
+        // Commands which finish later keep the variable until they send that they finished
+        run(&mut app, "gravity=1.5 load");
+        app.update();
+        assert_eq!(app.world.resource::<Ran>().0, [0.0, 1.5]);
+        assert_eq!(gravity(&app), 1.5);
+        run(&mut app, "fall");
+        assert_eq!(app.world.resource::<Ran>().0, [0.0, 1.5, 1.5]);
+
+        let loaded = app.world.resource_mut::<Ran>().1.remove(0);
+        app.world.send_event(CommandFinished::new(loaded));
+        app.update();
+        assert_eq!(gravity(&app), 9.81);
+    }
+
+    #[test]
+    fn test_timings_are_printed_without_replies() {
+        fn silent_count_command(mut count: ConsoleCommand<CountCommand>) {
+            count.take_all();
+        }
+
+        let mut app = App::new();
+        app.insert_resource(ConsoleConfiguration {
+            show_timings: true,
+            ..default()
+        })
+        .init_resource::<CommandTimer>()
+        .add_event::<ConsoleCommandEntered>()
+        .add_event::<PrintConsoleLine>()
+        .add_systems(
+            Update,
+            (
+                start_command_timer,
+                silent_count_command,
+                print_command_timings,
+            )
+                .chain(),
+        );
+
+        run_commands(&mut app, &["count 1", "count 2"]);
+        let lines = run_commands(&mut app, &[]);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("(2 commands took "));
+    }
+
+    #[test]
+    fn test_reply_result_prints_each_line() {
+        fn reply_count(mut count: ConsoleCommand<CountCommand>) {
+            while let Some(Ok(CountCommand { amount })) = count.take() {
+                let result = match amount {
+                    0 => Err("nothing\nto count"),
+                    amount => Ok(amount),
+                };
+                reply_result!(count, result, "counted {}");
+            }
+        }
+
+        let mut app = App::new();
+        app.add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, reply_count);
+        let lines = run_commands(&mut app, &["count 2", "count 0"]);
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "counted 2");
+        assert!(lines[1].contains("[ok]"));
+        assert_eq!(lines[2..4], ["nothing", "to count"]);
+        assert!(lines[4].contains("[failed]"));
+    }
+
+    fn reset_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<ConsoleState>()
+            .init_resource::<ConsoleConfiguration>()
+            .init_resource::<CommandQueue>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleLineReader>()
+            .init_resource::<Time<Real>>()
+            .add_event::<ResetConsole>()
+            .add_event::<PrintConsoleLine>()
+            .add_event::<CvarChanged>()
+            .add_systems(
+                Update,
+                (reset_console, receive_console_line::<DefaultConsole>).chain(),
+            );
+        app
+    }
+
+    #[test]
+    fn test_reset_console_clears_state() {
+        let mut app = reset_app();
+        app.world
+            .resource_mut::<ConsoleVariables>()
+            .register_cvar("volume", 8_u8);
+
+        {
+            let mut state = app.world.resource_mut::<ConsoleState>();
+            state.buf = "half typed".to_owned();
+            state.scrollback.push(ScrollbackLine::new("line"));
+            state.history.insert(1, "previous".into());
+            state.history_index = 1;
+            state.scrolled_up = true;
+            let pending = ConsoleCommandEntered::new("wipe", Vec::new());
+            state.await_confirmation(pending, None, false);
+        }
+        {
+            let mut queue = app.world.resource_mut::<CommandQueue>();
+            queue.lines.push_back("echo later".into());
+            queue.wait = Some(Wait::Frames(3));
+        }
+        app.world
+            .resource_mut::<ConsoleVariables>()
+            .set("volume", 2_u8);
+
+        app.world.send_event(ResetConsole::default());
+        app.update();
+
+        let state = app.world.resource::<ConsoleState>();
+        assert!(state.buf.is_empty());
+        assert!(state.scrollback.is_empty());
+        assert_eq!(state.history.len(), 1);
+        assert_eq!(state.history_index, 0);
+        assert!(!state.scrolled_up);
+        assert!(state.pending_confirmation.is_none());
+        let queue = app.world.resource::<CommandQueue>();
+        assert!(queue.lines.is_empty());
+        assert_eq!(queue.wait, None);
+        // Variables are only reset when asked to
+        let cvars = app.world.resource::<ConsoleVariables>();
+        assert_eq!(cvars.get::<u8>("volume"), Some(2));
+    }
+
+    #[test]
+    fn test_reset_console_restores_cvars() {
+        let mut app = reset_app();
+        {
+            let mut cvars = app.world.resource_mut::<ConsoleVariables>();
+            cvars
+                .register_cvar("volume", 8_u8)
+                .register_cvar("muted", false);
+            cvars.set("volume", 2_u8);
+        }
+
+        app.world.send_event(ResetConsole { cvars: true });
+        app.update();
+
+        let cvars = app.world.resource::<ConsoleVariables>();
+        assert_eq!(cvars.get::<u8>("volume"), Some(8));
+        let events = app.world.resource::<Events<CvarChanged>>();
+        let changed = events
+            .get_reader()
+            .read(events)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changed,
+            vec![CvarChanged {
+                name: "volume".to_owned(),
+                value: "8".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reset_console_drops_stale_progress() {
+        let mut app = reset_app();
+        let progress = |line: &'static str| {
+            PrintConsoleLine::new(line.into())
+                .with_source("import")
+                .with_progress(Progress::Update("assets".into()))
+        };
+
+        app.world.send_event(progress("1/3"));
+        app.update();
+        // Sent before the reset, but not received yet
+        app.world.send_event(progress("2/3"));
+        app.world.send_event(ResetConsole::default());
+        app.update();
+        assert!(app.world.resource::<ConsoleState>().scrollback.is_empty());
+
+        // Updates after the reset start a new line
+        app.world.send_event(progress("3/3"));
+        app.update();
+        let state = app.world.resource::<ConsoleState>();
+        let lines = state
+            .scrollback
+            .iter()
+            .map(|line| line.text.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec!["3/3"]);
+    }
+
+    #[test]
+    fn test_clear_console_keeps_input_and_history() {
+        let mut app = App::new();
+        app.init_resource::<ConsoleState>()
+            .add_event::<ClearConsole>()
+            .add_systems(Update, clear_console);
+
+        {
+            let mut state = app.world.resource_mut::<ConsoleState>();
+            state.buf = "half typed".to_owned();
+            state.scrollback.push(ScrollbackLine::new("line"));
+            state.history.insert(1, "previous".into());
+            state.scrolled_up = true;
+            state.seen_lines = 1;
+        }
+
+        app.world.send_event(ClearConsole);
+        app.update();
+
+        let state = app.world.resource::<ConsoleState>();
+        assert_eq!(state.buf, "half typed");
+        assert!(state.scrollback.is_empty());
+        assert_eq!(state.history.len(), 2);
+        assert!(!state.scrolled_up);
+        assert_eq!(state.seen_lines, 0);
+    }
+
+    #[test]
+    fn test_scrollback_text_strips_styling() {
+        let mut state = ConsoleState::<DefaultConsole>::default();
+        state.scrollback.push(ScrollbackLine::new("first"));
+        state
+            .scrollback
+            .push(ScrollbackLine::new(colored(AnsiColor::Red, "second")));
+        let mut repeated = ScrollbackLine::new("third");
+        repeated.count = 3;
+        state.scrollback.push(repeated);
+
+        assert_eq!(state.scrollback_text(3), "first\nsecond\nthird (x3)");
+        assert_eq!(state.scrollback_text(1), "third (x3)");
+        assert_eq!(state.scrollback_text(10), state.scrollback_text(3));
+    }
+
+    #[test]
+    fn test_receive_console_line_keeps_sources_apart() {
+        let mut app = App::new();
+        app.init_resource::<ConsoleState>()
+            .insert_resource(ConsoleConfiguration {
+                collapse_duplicates: true,
+                ..Default::default()
+            })
+            .init_resource::<Time<Real>>()
+            .init_resource::<ConsoleLineReader>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, receive_console_line::<DefaultConsole>);
+
+        app.world
+            .send_event(PrintConsoleLine::new("done".into()).with_source("spawn"));
+        app.world
+            .send_event(PrintConsoleLine::new("done".into()).with_source("spawn"));
+        app.world
+            .send_event(PrintConsoleLine::new("done".into()).with_source("despawn"));
+        app.world.send_event(PrintConsoleLine::new("done".into()));
+        app.update();
+
+        let state = app.world.resource::<ConsoleState>();
+        let lines = state
+            .scrollback
+            .iter()
+            .map(|line| (line.source.as_deref(), line.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![(Some("spawn"), 2), (Some("despawn"), 1), (None, 1)]
+        );
+    }
+
+    #[test]
+    fn test_replies_follow_their_echo() {
+        fn reply_count(mut count: ConsoleCommand<CountCommand>) {
+            while let Some(Ok(CountCommand { amount })) = count.take() {
+                reply!(count, "counted {amount}");
+                count.ok();
+            }
+        }
+
+        fn print_earlier(mut console_line: EventWriter<PrintConsoleLine>) {
+            console_line.send(PrintConsoleLine::new("earlier".into()));
+        }
+
+        let mut config = ConsoleConfiguration::default();
+        config.register_console_command::<CountCommand>();
+        let mut app = App::new();
+        app.insert_resource(config)
+            .init_resource::<ConsoleState>()
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<CommandQueue>()
+            .init_resource::<ConsoleLineReader>()
+            .init_resource::<Time<Real>>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<UnknownConsoleCommand>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(
+                Update,
+                (
+                    print_earlier,
+                    receive_console_line::<DefaultConsole>,
+                    run_queued_commands::<DefaultConsole>,
+                    reply_count,
+                    receive_console_line::<DefaultConsole>,
+                )
+                    .chain(),
+            );
+        let mut queue = app.world.resource_mut::<CommandQueue>();
+        queue.lines.push_back("count 2".into());
+        app.update();
+
+        let state = app.world.resource::<ConsoleState>();
+        let lines = state
+            .scrollback
+            .iter()
+            .map(|line| line.text.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[..3], ["earlier", "count 2", "counted 2"]);
+        assert!(lines[3].contains("[ok]"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_ron_round_trip() {
+        let mut config = ConsoleConfiguration {
+            keys: vec![ToggleConsoleKey::ScanCode(41)],
+            layout: ConsoleLayout::BottomPanel,
+            history_size: 200,
+            ..default()
+        };
+        config.register_console_command::<CountCommand>();
+
+        let ron = config.to_ron_string().unwrap();
+        let loaded = ConsoleConfiguration::from_ron_str(&ron).unwrap();
+        assert!(matches!(loaded.keys[..], [ToggleConsoleKey::ScanCode(41)]));
+        assert_eq!(loaded.layout, ConsoleLayout::BottomPanel);
+        assert_eq!(loaded.history_size, 200);
+        assert!(loaded.commands.is_empty());
+
+        // Fields left out keep their default value
+        let loaded = ConsoleConfiguration::from_ron_str("(history_size: 10)").unwrap();
+        assert_eq!(loaded.history_size, 10);
+        assert_eq!(loaded.symbol, "> ");
+    }
+
+    #[test]
+    fn test_scrollback_is_capped() {
+        let mut app = App::new();
+        app.init_resource::<ConsoleState>()
+            .insert_resource(ConsoleConfiguration {
+                scrollback_size: 2,
+                ..Default::default()
+            })
+            .init_resource::<Time<Real>>()
+            .init_resource::<ConsoleLineReader>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, receive_console_line::<DefaultConsole>);
+
+        app.world.resource_mut::<ConsoleState>().seen_lines = 1;
+        for line in ["one", "two", "three"] {
+            app.world.send_event(PrintConsoleLine::new(line.into()));
+        }
+        app.update();
+
+        let state = app.world.resource::<ConsoleState>();
+        assert_eq!(state.scrollback_text(usize::MAX), "two\nthree");
+        assert_eq!(state.seen_lines, 0);
+    }
+
+    #[test]
+    fn test_progress_lines_update_in_place() {
+        let mut app = App::new();
+        app.init_resource::<ConsoleState>()
+            .init_resource::<ConsoleConfiguration>()
+            .init_resource::<Time<Real>>()
+            .init_resource::<ConsoleLineReader>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, receive_console_line::<DefaultConsole>);
+
+        let progress = |line: &str, progress: Progress| {
+            PrintConsoleLine::new(line.to_owned().into())
+                .with_source("import")
+                .with_progress(progress)
+        };
+
+        app.world
+            .send_event(progress("0/2", Progress::Update("assets".into())));
+        app.world.send_event(PrintConsoleLine::new("other".into()));
+        app.world
+            .send_event(progress("1/2", Progress::Update("assets".into())));
+        app.world
+            .send_event(progress("done", Progress::Done("assets".into())));
+        app.world
+            .send_event(progress("0/1", Progress::Update("assets".into())));
+        app.update();
+
+        let state = app.world.resource::<ConsoleState>();
+        let lines = state
+            .scrollback
+            .iter()
+            .map(|line| line.text.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec!["done", "other", "0/1"]);
+    }
+
+    #[test]
+    fn test_console_toggle_events() {
+        let mut app = App::new();
+        app.init_resource::<ConsoleState>()
+            .init_resource::<ConsoleOpen>()
+            .add_event::<ConsoleOpened>()
+            .add_event::<ConsoleClosed>()
+            .add_systems(Update, send_console_toggles::<DefaultConsole>);
+        let toggles = |app: &mut App| {
+            let mut opened = app.world.resource_mut::<Events<ConsoleOpened>>();
+            let mut toggles = opened
+                .drain()
+                .map(|event| (true, event.source))
+                .collect::<Vec<_>>();
+            let mut closed = app.world.resource_mut::<Events<ConsoleClosed>>();
+            toggles.extend(closed.drain().map(|event| (false, event.source)));
+            toggles
+        };
+
+        app.update();
+        assert_eq!(toggles(&mut app), []);
+
+        app.world.resource_mut::<ConsoleOpen>().open = true;
+        app.world.resource_mut::<ConsoleState>().toggle_source = Some(ConsoleToggleSource::Key);
+        app.update();
+        assert_eq!(toggles(&mut app), [(true, ConsoleToggleSource::Key)]);
+
+        app.world.resource_mut::<ConsoleOpen>().open = false;
+        app.update();
+        assert_eq!(toggles(&mut app), [(false, ConsoleToggleSource::Code)]);
+
+        // Reopened and closed again before the system ran
+        app.world.resource_mut::<ConsoleState>().toggle_source =
+            Some(ConsoleToggleSource::CloseButton);
+        app.update();
+        assert_eq!(toggles(&mut app), []);
+        app.world.resource_mut::<ConsoleOpen>().open = true;
+        app.update();
+        assert_eq!(toggles(&mut app), [(true, ConsoleToggleSource::Code)]);
+    }
+
+    #[test]
+    fn test_pause_time_while_open() {
+        let mut app = App::new();
+        app.init_resource::<ConsoleConfiguration>()
+            .init_resource::<Time<Virtual>>()
+            .add_event::<ConsoleOpened>()
+            .add_event::<ConsoleClosed>()
+            .add_systems(Update, pause_time);
+        app.world.resource_mut::<ConsoleConfiguration>().pause_time = true;
+        let source = ConsoleToggleSource::Key;
+
+        app.world.send_event(ConsoleOpened {
+            source,
+            marker: PhantomData,
+        });
+        app.update();
+        assert!(app.world.resource::<Time<Virtual>>().is_paused());
+        app.world.send_event(ConsoleClosed {
+            source,
+            marker: PhantomData,
+        });
+        app.update();
+        assert!(!app.world.resource::<Time<Virtual>>().is_paused());
+
+        // Time paused by the game stays paused
+        app.world.resource_mut::<Time<Virtual>>().pause();
+        app.world.send_event(ConsoleOpened {
+            source,
+            marker: PhantomData,
+        });
+        app.update();
+        app.world.send_event(ConsoleClosed {
+            source,
+            marker: PhantomData,
+        });
+        app.update();
+        assert!(app.world.resource::<Time<Virtual>>().is_paused());
     }
 }
@@ -0,0 +1,1238 @@
+use std::any::{self, Any, TypeId};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::iter;
+use std::mem;
+use std::str::FromStr;
+
+use bevy::ecs::event::ManualEventReader;
+use bevy::prelude::*;
+use bevy::reflect::GetPath;
+use clap::builder::styling::AnsiColor;
+
+use crate::console::{
+    colored, CommandFinished, CommandOrigin, ConsoleCommandEntered, ConsoleConfiguration,
+};
+use crate::instance::ConsoleMarker;
+use crate::localization::fill;
+use crate::{ConsoleLocalization, PrintConsoleLine};
+
+/// Console variables, values which can be read and set from the console by typing their name.
+///
+/// Typing the name of a variable prints its value, typing the name followed by a value sets it.
+/// Systems can read the values with [`ConsoleVariables::get`], use change detection on this
+/// resource, or listen to [`CvarChanged`] events for changes made from the console.
+///
+/// With the `default_commands` feature, `toggle`, `inc`, `dec` and `reset` change variables
+/// as well, e.g. for key bindings.
+///
+/// Fields of [`Reflect`] resources can be exposed as well, see
+/// [`ConsoleVariables::register_cvar_resource`].
+///
+/// Variables can be set for a single command by prefixing it with `name=value`, like
+/// `gravity=0 spawn_level`. They are set before the command systems run and restored at the end
+/// of the frame, or once the command replies `[ok]` or `[failed]` if it's registered with
+/// [`CommandMeta::finishes_later`](crate::CommandMeta::finishes_later).
+///
+/// # Example
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_console::ConsoleVariables;
+/// fn setup(mut cvars: ResMut<ConsoleVariables>) {
+///     cvars.register_cvar("gravity", 9.81_f32);
+/// }
+///
+/// fn apply_gravity(cvars: Res<ConsoleVariables>) {
+///     let gravity = cvars.get::<f32>("gravity").unwrap_or(9.81);
+/// }
+/// ```
+#[derive(Default, Resource)]
+pub struct ConsoleVariables {
+    vars: BTreeMap<String, Box<dyn CvarValue>>,
+    resources: BTreeMap<String, CvarResource>,
+    /// Copies of the registered resources by prefix, interpolated without access to the world
+    snapshots: BTreeMap<String, Box<dyn Reflect>>,
+    /// Variables set for the duration of a command, oldest first
+    overrides: Vec<CvarOverride>,
+    /// Id of the next command variables are set for
+    next_override: u64,
+}
+
+/// A variable set for the duration of a command, see [`apply_cvar_overrides`].
+struct CvarOverride {
+    /// Id of the command it was set for, shared by the variables set for the same command
+    id: u64,
+    command: OverriddenCommand,
+    /// Restored once the command finishes rather than at the end of the frame
+    until_finished: bool,
+    name: String,
+    /// Value set for the command, as printed in the console
+    value: String,
+    /// Value before the command, restored when it finishes
+    previous: String,
+}
+
+/// The command a variable was set for, as the [`CommandFinished`] it sends identifies it.
+#[derive(Debug, PartialEq)]
+struct OverriddenCommand {
+    console: TypeId,
+    raw: String,
+    origin: CommandOrigin,
+}
+
+impl OverriddenCommand {
+    fn of<M: ConsoleMarker>(command: &ConsoleCommandEntered<M>) -> Self {
+        Self {
+            console: TypeId::of::<M>(),
+            raw: command.raw.clone(),
+            origin: command.origin,
+        }
+    }
+}
+
+/// Registers [`Reflect`] resources as console variables.
+pub trait RegisterCvarResource {
+    /// Exposes the fields of the resource `R` as console variables named `prefix.field`, see
+    /// [`ConsoleVariables::register_cvar_resource`].
+    fn register_cvar_resource<R: Resource + Reflect>(
+        &mut self,
+        prefix: impl Into<String>,
+    ) -> &mut Self;
+}
+
+impl RegisterCvarResource for App {
+    fn register_cvar_resource<R: Resource + Reflect>(
+        &mut self,
+        prefix: impl Into<String>,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(ConsoleVariables::default)
+            .register_cvar_resource::<R>(prefix);
+        self
+    }
+}
+
+/// Reflected access to a resource registered as console variables.
+#[derive(Clone, Copy)]
+struct CvarResource {
+    type_name: &'static str,
+    get: fn(&World) -> Option<&dyn Reflect>,
+    get_mut: fn(&mut World) -> Option<Mut<'_, dyn Reflect>>,
+    /// Whether the resource changed since the exclusive system calling this last ran
+    is_changed: fn(&World) -> bool,
+}
+
+fn reflect_resource<R: Resource + Reflect>(world: &World) -> Option<&dyn Reflect> {
+    world
+        .get_resource::<R>()
+        .map(|resource| resource as &dyn Reflect)
+}
+
+fn reflect_resource_mut<R: Resource + Reflect>(world: &mut World) -> Option<Mut<'_, dyn Reflect>> {
+    world
+        .get_resource_mut::<R>()
+        .map(|resource| resource.map_unchanged(|resource| resource as &mut dyn Reflect))
+}
+
+/// Sent when a console variable is set from the console.
+#[derive(Clone, Debug, Event, PartialEq, Eq)]
+pub struct CvarChanged {
+    /// Name of the variable
+    pub name: String,
+    /// New value of the variable, as printed in the console
+    pub value: String,
+}
+
+/// Type erased console variable.
+trait CvarValue: Send + Sync {
+    fn display(&self) -> String;
+    fn check(&self, text: &str) -> Result<(), String>;
+    fn set_from_str(&mut self, text: &str) -> Result<(), String>;
+    fn type_name(&self) -> &'static str;
+    fn reset(&mut self);
+    #[cfg(feature = "default_commands")]
+    fn bounds(&self) -> Option<(f64, f64)>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct TypedCvar<T> {
+    value: T,
+    default: T,
+    /// Keeps values within the range the variable was registered with
+    clamp: Option<Box<dyn Fn(T) -> T + Send + Sync>>,
+    /// Range of a numeric variable, which `inc` and `dec` clamp to before converting back
+    #[cfg(feature = "default_commands")]
+    bounds: Option<(f64, f64)>,
+}
+
+impl<T> TypedCvar<T> {
+    fn clamped(&self, value: T) -> T {
+        match &self.clamp {
+            Some(clamp) => clamp(value),
+            None => value,
+        }
+    }
+}
+
+impl<T> CvarValue for TypedCvar<T>
+where
+    T: FromStr + Display + Clone + Send + Sync + 'static,
+    T::Err: Display,
+{
+    fn display(&self) -> String {
+        self.value.to_string()
+    }
+
+    fn check(&self, text: &str) -> Result<(), String> {
+        text.parse::<T>().map(drop).map_err(|err| err.to_string())
+    }
+
+    fn set_from_str(&mut self, text: &str) -> Result<(), String> {
+        let value = text.parse().map_err(|err: T::Err| err.to_string())?;
+        self.value = self.clamped(value);
+        Ok(())
+    }
+
+    fn type_name(&self) -> &'static str {
+        // Leave out the module path, e.g. `alloc::string::`
+        let name = any::type_name::<T>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
+
+    fn reset(&mut self) {
+        self.value = self.default.clone();
+    }
+
+    #[cfg(feature = "default_commands")]
+    fn bounds(&self) -> Option<(f64, f64)> {
+        self.bounds
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl ConsoleVariables {
+    /// Registers a variable with its initial value, replacing a variable with the same name.
+    ///
+    /// The value is parsed with [`FromStr`] when set from the console and printed with
+    /// [`Display`]. The initial value is also the default restored by `reset <name>`.
+    pub fn register_cvar<T>(&mut self, name: impl Into<String>, value: T) -> &mut Self
+    where
+        T: FromStr + Display + Clone + Send + Sync + 'static,
+        T::Err: Display,
+    {
+        self.insert_cvar(
+            name.into(),
+            TypedCvar {
+                value: value.clone(),
+                default: value,
+                clamp: None,
+                #[cfg(feature = "default_commands")]
+                bounds: None,
+            },
+        )
+    }
+
+    /// Registers a variable like [`ConsoleVariables::register_cvar`], keeping its value between
+    /// `min` and `max`.
+    ///
+    /// Values outside the range are clamped, whether they are set from the console, with
+    /// `inc`/`dec` or with [`ConsoleVariables::set`].
+    pub fn register_cvar_range<T>(
+        &mut self,
+        name: impl Into<String>,
+        value: T,
+        min: T,
+        max: T,
+    ) -> &mut Self
+    where
+        T: FromStr + Display + PartialOrd + Clone + Send + Sync + 'static,
+        T::Err: Display,
+    {
+        #[cfg(feature = "default_commands")]
+        let bounds = min
+            .to_string()
+            .parse()
+            .ok()
+            .zip(max.to_string().parse().ok());
+        let clamp = move |value: T| {
+            if value < min {
+                min.clone()
+            } else if value > max {
+                max.clone()
+            } else {
+                value
+            }
+        };
+        let value = clamp(value);
+        self.insert_cvar(
+            name.into(),
+            TypedCvar {
+                value: value.clone(),
+                default: value,
+                clamp: Some(Box::new(clamp)),
+                #[cfg(feature = "default_commands")]
+                bounds,
+            },
+        )
+    }
+
+    fn insert_cvar<T>(&mut self, name: String, cvar: TypedCvar<T>) -> &mut Self
+    where
+        T: FromStr + Display + Clone + Send + Sync + 'static,
+        T::Err: Display,
+    {
+        if self.vars.contains_key(&name) {
+            warn!("console variable '{name}' already registered and was overwritten");
+        }
+        self.vars.insert(name, Box::new(cvar));
+        self
+    }
+
+    /// Exposes the fields of the resource `R` as console variables named `prefix.field`.
+    ///
+    /// Nested fields are separated by dots, e.g. `gfx.shadows.distance`. Numbers, `bool` and
+    /// `String` fields can be set, fields of other types can only be printed. Setting a field
+    /// triggers change detection of the resource.
+    pub fn register_cvar_resource<R: Resource + Reflect>(
+        &mut self,
+        prefix: impl Into<String>,
+    ) -> &mut Self {
+        let prefix = prefix.into();
+        if self.resources.contains_key(&prefix) {
+            warn!("console variable prefix '{prefix}' already registered and was overwritten");
+        }
+        self.resources.insert(
+            prefix,
+            CvarResource {
+                type_name: any::type_name::<R>(),
+                get: reflect_resource::<R>,
+                get_mut: reflect_resource_mut::<R>,
+                is_changed: World::is_resource_changed::<R>,
+            },
+        );
+        self
+    }
+
+    /// Returns the value of a variable, `None` if it doesn't exist or has a different type.
+    pub fn get<T: Clone + 'static>(&self, name: &str) -> Option<T> {
+        self.vars
+            .get(name)?
+            .as_any()
+            .downcast_ref::<TypedCvar<T>>()
+            .map(|cvar| cvar.value.clone())
+    }
+
+    /// Sets the value of a variable, returning `false` if it doesn't exist or has a different
+    /// type.
+    pub fn set<T: 'static>(&mut self, name: &str, value: T) -> bool {
+        let cvar = self
+            .vars
+            .get_mut(name)
+            .and_then(|cvar| cvar.as_any_mut().downcast_mut::<TypedCvar<T>>());
+        match cvar {
+            Some(cvar) => {
+                cvar.value = cvar.clamped(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a variable with the given name exists.
+    pub fn contains(&self, name: &str) -> bool {
+        self.vars.contains_key(name)
+    }
+
+    /// Names of the variables with their values as printed in the console, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, String)> {
+        self.vars
+            .iter()
+            .map(|(name, cvar)| (name.as_str(), cvar.display()))
+    }
+
+    /// Whether `name` is a variable or a field of a registered resource.
+    pub(crate) fn handles(&self, name: &str) -> bool {
+        self.contains(name) || self.resource_field(name).is_some()
+    }
+
+    #[cfg(feature = "default_commands")]
+    /// Prefixes of the registered resources with their type names, sorted by prefix.
+    pub(crate) fn resource_prefixes(&self) -> impl Iterator<Item = (&str, &'static str)> {
+        self.resources
+            .iter()
+            .map(|(prefix, resource)| (prefix.as_str(), resource.type_name))
+    }
+
+    /// The registered resource `name` belongs to and the path of the field within it.
+    fn resource_field<'n>(&self, name: &'n str) -> Option<(CvarResource, &'n str)> {
+        self.resources.iter().find_map(|(prefix, resource)| {
+            let path = name.strip_prefix(prefix.as_str())?;
+            if path.is_empty() {
+                Some((*resource, path))
+            } else {
+                path.strip_prefix('.').map(|path| (*resource, path))
+            }
+        })
+    }
+
+    /// Names of the variables, sorted.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.vars.keys().map(String::as_str)
+    }
+
+    /// Value of a variable as printed in the console.
+    pub(crate) fn display(&self, name: &str) -> Option<String> {
+        self.vars.get(name).map(|cvar| cvar.display())
+    }
+
+    /// Value of a field of a registered resource as of the last snapshot, see
+    /// [`snapshot_cvar_resources`].
+    fn display_snapshot(&self, name: &str) -> Option<String> {
+        self.snapshots.iter().find_map(|(prefix, snapshot)| {
+            let path = name.strip_prefix(prefix.as_str())?;
+            if path.is_empty() {
+                return Some(display_reflect(snapshot.as_ref()));
+            }
+            let field = snapshot.reflect_path(path.strip_prefix('.')?).ok()?;
+            Some(display_reflect(field))
+        })
+    }
+
+    /// Parses and sets the value of a variable, describing why if it fails.
+    pub(crate) fn set_from_str(&mut self, name: &str, text: &str) -> Result<(), CvarError> {
+        let cvar = self.cvar_mut(name)?;
+        cvar.set_from_str(text)
+            .map_err(|err| invalid_value(name, text, cvar.as_ref(), &err))
+    }
+
+    /// Checks that a variable exists and `text` can be parsed as its value, without setting it.
+    pub(crate) fn check(&self, name: &str, text: &str) -> Result<(), CvarError> {
+        let cvar = self
+            .vars
+            .get(name)
+            .ok_or_else(|| CvarError::Unknown(name.to_owned()))?;
+        cvar.check(text)
+            .map_err(|err| invalid_value(name, text, cvar.as_ref(), &err))
+    }
+
+    /// Sets variables for the duration of `command`, returning the names and values of the
+    /// variables which changed.
+    ///
+    /// A variable already set for an unfinished command is restored to its value from before
+    /// that command.
+    fn override_values<M: ConsoleMarker>(
+        &mut self,
+        command: &ConsoleCommandEntered<M>,
+        until_finished: bool,
+    ) -> Vec<(String, String)> {
+        let id = self.next_override;
+        self.next_override += 1;
+        let mut changed = Vec::new();
+        for (name, text) in &command.overrides {
+            let Some(current) = self.display(name) else {
+                continue;
+            };
+            if let Err(err) = self.set_from_str(name, text) {
+                warn!("console variable '{name}' couldn't be set for a command: {err}");
+                continue;
+            }
+            let value = self.display(name).unwrap_or_default();
+            let previous = self
+                .overrides
+                .iter()
+                .find(|set| set.name == *name)
+                .map_or(current.clone(), |set| set.previous.clone());
+            self.overrides.push(CvarOverride {
+                id,
+                command: OverriddenCommand::of(command),
+                until_finished,
+                name: name.clone(),
+                value: value.clone(),
+                previous,
+            });
+            if value != current {
+                changed.push((name.clone(), value));
+            }
+        }
+        changed
+    }
+
+    /// Restores the variables set for the oldest unfinished `command`, returning the names and
+    /// values of the variables which changed.
+    fn finish_override<M: ConsoleMarker>(
+        &mut self,
+        command: &ConsoleCommandEntered<M>,
+    ) -> Vec<(String, String)> {
+        let command = OverriddenCommand::of(command);
+        match self.overrides.iter().find(|set| set.command == command) {
+            Some(set) => {
+                let id = set.id;
+                self.restore_overrides(|set| set.id == id)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Restores the variables set for the commands which finished this frame, returning the
+    /// names and values of the variables which changed.
+    fn end_frame_overrides(&mut self) -> Vec<(String, String)> {
+        self.restore_overrides(|set| !set.until_finished)
+    }
+
+    /// Restores the variables set for every unfinished command, returning the names and values
+    /// of the variables which changed.
+    pub(crate) fn end_overrides(&mut self) -> Vec<(String, String)> {
+        self.restore_overrides(|_| true)
+    }
+
+    /// Restores the variables of the `ended` overrides, unless they were changed since or are
+    /// still set for another command.
+    fn restore_overrides(
+        &mut self,
+        ended: impl Fn(&CvarOverride) -> bool,
+    ) -> Vec<(String, String)> {
+        let (ended, kept): (Vec<_>, Vec<_>) = mem::take(&mut self.overrides)
+            .into_iter()
+            .partition(|set| ended(set));
+        self.overrides = kept;
+        let mut restored = Vec::new();
+        for set in ended {
+            let still_set = self.overrides.iter().any(|kept| kept.name == set.name);
+            if still_set || self.display(&set.name).as_ref() != Some(&set.value) {
+                continue;
+            }
+            if self.set_from_str(&set.name, &set.previous).is_ok() {
+                restored.push((set.name, set.previous));
+            }
+        }
+        restored
+    }
+
+    /// Replaces `$name` and `${name}` in a command line token with the values of variables.
+    ///
+    /// Fields of registered resources are named by their path, like `$gfx.shadow_distance`.
+    /// `$$` is a literal `$`, as is a `$` which isn't followed by a name.
+    pub(crate) fn interpolate(
+        &self,
+        token: &str,
+        localization: &ConsoleLocalization,
+    ) -> Result<String, String> {
+        let mut interpolated = String::with_capacity(token.len());
+        let mut rest = token;
+        while let Some(index) = rest.find('$') {
+            interpolated.push_str(&rest[..index]);
+            rest = &rest[index + 1..];
+
+            let (name, after) = if let Some(after) = rest.strip_prefix('$') {
+                interpolated.push('$');
+                rest = after;
+                continue;
+            } else if let Some(braced) = rest.strip_prefix('{') {
+                let end = braced
+                    .find('}')
+                    .ok_or_else(|| fill(&localization.unclosed_variable, &[("token", &token)]))?;
+                (&braced[..end], &braced[end + 1..])
+            } else {
+                let end = rest
+                    .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '.')
+                    .unwrap_or(rest.len());
+                let name = rest[..end].trim_end_matches('.');
+                if name.is_empty() || name.starts_with('.') {
+                    interpolated.push('$');
+                    continue;
+                }
+                // A variable may be followed by a dot and text, like `$name.cfg`
+                let name = iter::successors(Some(name), |name| Some(name.rsplit_once('.')?.0))
+                    .find(|name| self.handles(name))
+                    .unwrap_or(name);
+                rest.split_at(name.len())
+            };
+
+            let value = self
+                .display(name)
+                .or_else(|| self.display_snapshot(name))
+                .ok_or_else(|| {
+                    fill(
+                        &localization.unknown_variable,
+                        &[("name", &name), ("token", &token)],
+                    )
+                })?;
+            interpolated.push_str(&value);
+            rest = after;
+        }
+        interpolated.push_str(rest);
+        Ok(interpolated)
+    }
+
+    #[cfg(feature = "default_commands")]
+    /// Flips a `bool` variable, returning its new value.
+    pub(crate) fn toggle(&mut self, name: &str) -> Result<String, CvarError> {
+        let cvar = self
+            .cvar_mut(name)?
+            .as_any_mut()
+            .downcast_mut::<TypedCvar<bool>>()
+            .ok_or_else(|| CvarError::NotBool(name.to_owned()))?;
+        cvar.value = !cvar.value;
+        Ok(cvar.value.to_string())
+    }
+
+    #[cfg(feature = "default_commands")]
+    /// Adds `step` to a numeric variable, returning its new value.
+    ///
+    /// The sum is clamped to the range the variable was registered with, and rounded to the
+    /// nearest whole number for integers.
+    pub(crate) fn add(&mut self, name: &str, step: f64) -> Result<String, CvarError> {
+        let cvar = self.cvar_mut(name)?;
+        let value = cvar
+            .display()
+            .parse::<f64>()
+            .map_err(|_| CvarError::NotNumber(name.to_owned()))?;
+        let (min, max) = cvar.bounds().unwrap_or((f64::MIN, f64::MAX));
+        let sum = (value + step).clamp(min, max);
+        if cvar.set_from_str(&sum.to_string()).is_err() {
+            let whole = sum.round().clamp(min.ceil(), max.floor());
+            self.set_from_str(name, &whole.to_string())?;
+        }
+        Ok(self.display(name).unwrap_or_default())
+    }
+
+    #[cfg(feature = "default_commands")]
+    /// Restores the value a variable was registered with, returning it.
+    pub(crate) fn reset(&mut self, name: &str) -> Result<String, CvarError> {
+        let cvar = self.cvar_mut(name)?;
+        cvar.reset();
+        Ok(cvar.display())
+    }
+
+    /// Restores the value every variable was registered with, returning the names and values of
+    /// the variables which changed.
+    pub(crate) fn reset_all(&mut self) -> Vec<(String, String)> {
+        self.overrides.clear();
+        self.vars
+            .iter_mut()
+            .filter_map(|(name, cvar)| {
+                let previous = cvar.display();
+                cvar.reset();
+                let value = cvar.display();
+                (value != previous).then(|| (name.clone(), value))
+            })
+            .collect()
+    }
+
+    fn cvar_mut(&mut self, name: &str) -> Result<&mut Box<dyn CvarValue>, CvarError> {
+        self.vars
+            .get_mut(name)
+            .ok_or_else(|| CvarError::Unknown(name.to_owned()))
+    }
+}
+
+/// Why a console variable couldn't be read or set, printed with
+/// [`CvarError::describe`] in the words of the console's localization.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum CvarError {
+    /// No variable has the name
+    Unknown(String),
+    #[cfg(feature = "default_commands")]
+    /// Toggled variable isn't a `bool`
+    NotBool(String),
+    #[cfg(feature = "default_commands")]
+    /// Variable changed by an amount isn't a number
+    NotNumber(String),
+    /// Text can't be parsed as the value of the variable
+    InvalidValue {
+        value: String,
+        name: String,
+        type_name: &'static str,
+        error: String,
+    },
+    /// Resource of the variable fields isn't in the world
+    NoResource(&'static str),
+    /// Resource has no field at the path
+    NoField {
+        path: String,
+        type_name: &'static str,
+        error: String,
+    },
+    /// Resource was set as a whole
+    NotAField,
+    /// Text can't be parsed as the value of the field
+    InvalidField {
+        value: String,
+        type_name: &'static str,
+        error: String,
+    },
+    /// Field is of a type which can't be parsed
+    Unsupported(String),
+}
+
+impl CvarError {
+    /// Describes the error with the messages of `localization`.
+    pub(crate) fn describe(&self, localization: &ConsoleLocalization) -> String {
+        match self {
+            Self::Unknown(name) => fill(&localization.no_variable, &[("name", name)]),
+            #[cfg(feature = "default_commands")]
+            Self::NotBool(name) => fill(&localization.variable_not_bool, &[("name", name)]),
+            #[cfg(feature = "default_commands")]
+            Self::NotNumber(name) => fill(&localization.variable_not_number, &[("name", name)]),
+            Self::InvalidValue {
+                value,
+                name,
+                type_name,
+                error,
+            } => fill(
+                &localization.invalid_variable_value,
+                &[
+                    ("value", value),
+                    ("name", name),
+                    ("type", type_name),
+                    ("error", error),
+                ],
+            ),
+            Self::NoResource(type_name) => fill(&localization.no_resource, &[("type", type_name)]),
+            Self::NoField {
+                path,
+                type_name,
+                error,
+            } => fill(
+                &localization.no_resource_field,
+                &[("path", path), ("type", type_name), ("error", error)],
+            ),
+            Self::NotAField => localization.resource_not_field.clone(),
+            Self::InvalidField {
+                value,
+                type_name,
+                error,
+            } => fill(
+                &localization.invalid_field_value,
+                &[("value", value), ("type", type_name), ("error", error)],
+            ),
+            Self::Unsupported(type_name) => {
+                fill(&localization.unsupported_field, &[("type", type_name)])
+            }
+        }
+    }
+}
+
+impl Display for CvarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.describe(&ConsoleLocalization::default()))
+    }
+}
+
+/// Why `text` isn't a valid value for the variable `name`.
+fn invalid_value(name: &str, text: &str, cvar: &dyn CvarValue, err: &str) -> CvarError {
+    CvarError::InvalidValue {
+        value: text.to_owned(),
+        name: name.to_owned(),
+        type_name: cvar.type_name(),
+        error: err.to_owned(),
+    }
+}
+
+/// Sets the variables entered before commands, like `gravity=0 spawn_level`, before the command
+/// systems run.
+pub(crate) fn apply_cvar_overrides<M: ConsoleMarker>(
+    mut events: EventReader<ConsoleCommandEntered<M>>,
+    mut cvars: ResMut<ConsoleVariables>,
+    config: Res<ConsoleConfiguration<M>>,
+    mut changed: EventWriter<CvarChanged>,
+) {
+    for event in events.read() {
+        if event.overrides.is_empty() {
+            continue;
+        }
+        let until_finished = config
+            .commands
+            .get(&event.command_name)
+            .is_some_and(|registered| registered.meta.finishes_later);
+        for (name, value) in cvars.override_values(event, until_finished) {
+            changed.send(CvarChanged { name, value });
+        }
+    }
+}
+
+/// Restores the variables set for the commands which finished, see [`apply_cvar_overrides`].
+pub(crate) fn restore_cvar_overrides<M: ConsoleMarker>(
+    mut finished: EventReader<CommandFinished<M>>,
+    mut cvars: ResMut<ConsoleVariables>,
+    mut changed: EventWriter<CvarChanged>,
+) {
+    let mut restored = Vec::new();
+    for event in finished.read() {
+        restored.extend(cvars.finish_override(&event.command));
+    }
+    // Checked first, so the variables don't look changed every frame
+    if cvars.overrides.iter().any(|set| !set.until_finished) {
+        restored.extend(cvars.end_frame_overrides());
+    }
+    for (name, value) in restored {
+        changed.send(CvarChanged { name, value });
+    }
+}
+
+/// Prints or sets console variables entered as commands.
+pub(crate) fn cvar_commands(
+    mut events: EventReader<ConsoleCommandEntered>,
+    mut cvars: ResMut<ConsoleVariables>,
+    config: Res<ConsoleConfiguration>,
+    mut console_line: EventWriter<PrintConsoleLine>,
+    mut changed: EventWriter<CvarChanged>,
+) {
+    for event in events.read() {
+        let name = event.command_name.as_str();
+        if !cvars.contains(name) {
+            continue;
+        }
+
+        let line = match event.args.as_slice() {
+            [] => format!("{name} = {}", cvars.display(name).unwrap_or_default()).into(),
+            [value] => match cvars.set_from_str(name, value) {
+                Ok(()) => {
+                    let value = cvars.display(name).unwrap_or_default();
+                    changed.send(CvarChanged {
+                        name: name.to_owned(),
+                        value: value.clone(),
+                    });
+                    format!("{name} = {value}").into()
+                }
+                Err(err) => {
+                    let error = err.describe(&config.localization);
+                    let error = fill(&config.localization.variable_error, &[("error", &error)]);
+                    colored(AnsiColor::Red, &error)
+                }
+            },
+            _ => {
+                let usage = fill(&config.localization.variable_usage, &[("name", &name)]);
+                colored(AnsiColor::Red, &usage)
+            }
+        };
+        console_line.send(
+            PrintConsoleLine::new(line)
+                .with_source(name)
+                .with_origin(event.origin),
+        );
+    }
+}
+
+/// Prints or sets fields of resources registered as console variables.
+pub(crate) fn cvar_resource_commands(
+    world: &mut World,
+    mut reader: Local<ManualEventReader<ConsoleCommandEntered>>,
+) {
+    let events = world.resource::<Events<ConsoleCommandEntered>>();
+    let entered = reader.read(events).cloned().collect::<Vec<_>>();
+
+    for event in entered {
+        let name = event.command_name.as_str();
+        let Some((resource, path)) = world.resource::<ConsoleVariables>().resource_field(name)
+        else {
+            continue;
+        };
+
+        let line = match event.args.as_slice() {
+            [] => read_resource_field(world, resource, path)
+                .map(|value| format!("{name} = {value}").into()),
+            [value] => write_resource_field(world, resource, path, value).map(|value| {
+                world.send_event(CvarChanged {
+                    name: name.to_owned(),
+                    value: value.clone(),
+                });
+                format!("{name} = {value}").into()
+            }),
+            _ => {
+                let localization = &world.resource::<ConsoleConfiguration>().localization;
+                let usage = fill(&localization.variable_usage, &[("name", &name)]);
+                Ok(colored(AnsiColor::Red, &usage))
+            }
+        };
+        let line = line.unwrap_or_else(|err| {
+            let localization = &world.resource::<ConsoleConfiguration>().localization;
+            let error = fill(
+                &localization.variable_error,
+                &[("error", &err.describe(localization))],
+            );
+            colored(AnsiColor::Red, &error)
+        });
+        world.send_event(
+            PrintConsoleLine::new(line)
+                .with_source(name)
+                .with_origin(event.origin),
+        );
+    }
+}
+
+/// Copies the registered resources which changed since the last frame, so their fields can be
+/// interpolated into command lines, which are parsed without access to the world.
+pub(crate) fn snapshot_cvar_resources(world: &mut World) {
+    world.resource_scope(|world, mut cvars: Mut<ConsoleVariables>| {
+        // Snapshots aren't values set from the console, so changing them isn't a change
+        let cvars = cvars.bypass_change_detection();
+        let stale = cvars
+            .resources
+            .iter()
+            .filter(|(prefix, resource)| {
+                !cvars.snapshots.contains_key(*prefix) || (resource.is_changed)(world)
+            })
+            .map(|(prefix, resource)| (prefix.clone(), *resource))
+            .collect::<Vec<_>>();
+        for (prefix, resource) in stale {
+            match (resource.get)(world) {
+                Some(value) => cvars.snapshots.insert(prefix, value.clone_value()),
+                None => cvars.snapshots.remove(&prefix),
+            };
+        }
+    });
+}
+
+/// Prints a field of a registered resource, or the whole resource for an empty path.
+fn read_resource_field(
+    world: &World,
+    resource: CvarResource,
+    path: &str,
+) -> Result<String, CvarError> {
+    let value = (resource.get)(world).ok_or(CvarError::NoResource(resource.type_name))?;
+    if path.is_empty() {
+        return Ok(display_reflect(value));
+    }
+
+    let field = value
+        .reflect_path(path)
+        .map_err(|err| no_field(path, resource, err))?;
+    Ok(display_reflect(field))
+}
+
+/// Sets a field of a registered resource, returning its new value as printed in the console.
+fn write_resource_field(
+    world: &mut World,
+    resource: CvarResource,
+    path: &str,
+    text: &str,
+) -> Result<String, CvarError> {
+    if path.is_empty() {
+        return Err(CvarError::NotAField);
+    }
+    let mut value = (resource.get_mut)(world).ok_or(CvarError::NoResource(resource.type_name))?;
+
+    // Change detection is only triggered once the field was actually set
+    let field = value
+        .bypass_change_detection()
+        .reflect_path_mut(path)
+        .map_err(|err| no_field(path, resource, err))?;
+    set_reflect_field(field, text)?;
+    let display = display_reflect(field);
+    value.set_changed();
+    Ok(display)
+}
+
+/// Why a resource has no field at `path`.
+fn no_field(path: &str, resource: CvarResource, err: impl Display) -> CvarError {
+    CvarError::NoField {
+        path: path.to_owned(),
+        type_name: resource.type_name,
+        error: err.to_string(),
+    }
+}
+
+/// Parses `text` into a reflected field of a supported type.
+fn set_reflect_field(field: &mut dyn Reflect, text: &str) -> Result<(), CvarError> {
+    macro_rules! parse_as {
+        ($($ty:ty),*) => {
+            $(
+                if let Some(value) = field.downcast_mut::<$ty>() {
+                    *value = text.parse::<$ty>().map_err(|err| CvarError::InvalidField {
+                        value: text.to_owned(),
+                        type_name: stringify!($ty),
+                        error: err.to_string(),
+                    })?;
+                    return Ok(());
+                }
+            )*
+        };
+    }
+    parse_as!(
+        f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, bool, String
+    );
+
+    Err(CvarError::Unsupported(field.reflect_type_path().to_owned()))
+}
+
+/// Formats a reflected value, with [`Display`] for the types that can be set.
+fn display_reflect(value: &dyn Reflect) -> String {
+    macro_rules! display_as {
+        ($($ty:ty),*) => {
+            $(
+                if let Some(value) = value.downcast_ref::<$ty>() {
+                    return value.to_string();
+                }
+            )*
+        };
+    }
+    display_as!(
+        f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, bool, String
+    );
+
+    format!("{value:?}")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{ConsoleCommandEntered, ConsoleConfiguration};
+
+    #[test]
+    fn test_cvar_get_and_set() {
+        let mut cvars = ConsoleVariables::default();
+        cvars
+            .register_cvar("gravity", 9.81_f32)
+            .register_cvar("name", "player".to_owned());
+
+        assert_eq!(cvars.get::<f32>("gravity"), Some(9.81));
+        assert_eq!(cvars.get::<i32>("gravity"), None);
+        assert_eq!(cvars.get::<f32>("missing"), None);
+
+        assert!(cvars.set("gravity", 3.0_f32));
+        assert!(!cvars.set("gravity", 3_i32));
+        assert_eq!(cvars.get::<f32>("gravity"), Some(3.0));
+
+        assert_eq!(cvars.set_from_str("name", "enemy"), Ok(()));
+        assert_eq!(cvars.display("name").as_deref(), Some("enemy"));
+        assert_eq!(
+            cvars
+                .set_from_str("gravity", "down")
+                .map_err(|err| err.to_string()),
+            Err("invalid value 'down' for 'gravity' of type f32: invalid float literal".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let localization = ConsoleLocalization::default();
+        let mut cvars = ConsoleVariables::default();
+        cvars
+            .register_cvar("spawn_x", 12)
+            .register_cvar("name", "player".to_owned());
+
+        assert_eq!(
+            cvars.interpolate("$spawn_x", &localization),
+            Ok("12".to_owned())
+        );
+        assert_eq!(
+            cvars.interpolate("${name}_1:$spawn_x,", &localization),
+            Ok("player_1:12,".to_owned())
+        );
+        assert_eq!(
+            cvars.interpolate("$$spawn_x costs 5$", &localization),
+            Ok("$spawn_x costs 5$".to_owned())
+        );
+        assert_eq!(
+            cvars.interpolate("$spawn_y", &localization),
+            Err("error: unknown console variable 'spawn_y' in '$spawn_y'".to_owned())
+        );
+        assert!(cvars.interpolate("${name", &localization).is_err());
+    }
+
+    #[cfg(feature = "default_commands")]
+    #[test]
+    fn test_cvar_helpers() {
+        let mut cvars = ConsoleVariables::default();
+        cvars
+            .register_cvar("debug_draw", false)
+            .register_cvar("gravity", 9.5_f32)
+            .register_cvar_range("volume", 8_u8, 0, 10);
+
+        assert_eq!(cvars.toggle("debug_draw"), Ok("true".to_owned()));
+        assert_eq!(
+            cvars.toggle("gravity").map_err(|err| err.to_string()),
+            Err("'gravity' is not a bool".to_owned())
+        );
+
+        assert_eq!(cvars.add("gravity", 0.5), Ok("10".to_owned()));
+        assert_eq!(cvars.add("volume", 5.0), Ok("10".to_owned()));
+        assert_eq!(cvars.add("volume", -20.0), Ok("0".to_owned()));
+        assert_eq!(cvars.add("volume", 2.6), Ok("3".to_owned()));
+        assert_eq!(cvars.add("volume", -0.4), Ok("3".to_owned()));
+        assert_eq!(
+            cvars.add("missing", 1.0).map_err(|err| err.to_string()),
+            Err("no console variable named 'missing'".to_owned())
+        );
+        assert_eq!(
+            cvars.add("debug_draw", 1.0).map_err(|err| err.to_string()),
+            Err("'debug_draw' is not a number".to_owned())
+        );
+        assert!(cvars.set("volume", 200_u8));
+        assert_eq!(cvars.get::<u8>("volume"), Some(10));
+
+        assert_eq!(cvars.reset("volume"), Ok("8".to_owned()));
+        assert_eq!(cvars.reset("debug_draw"), Ok("false".to_owned()));
+        assert_eq!(
+            cvars.reset("missing").map_err(|err| err.to_string()),
+            Err("no console variable named 'missing'".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_cvar_commands() {
+        let mut app = App::new();
+        app.init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleConfiguration>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_event::<CvarChanged>()
+            .add_systems(Update, cvar_commands);
+        app.world
+            .resource_mut::<ConsoleVariables>()
+            .register_cvar("gravity", 9.81_f32);
+
+        app.world.send_event(ConsoleCommandEntered::new(
+            "gravity",
+            vec!["3.5".to_owned()],
+        ));
+        app.update();
+
+        assert_eq!(
+            app.world
+                .resource::<ConsoleVariables>()
+                .get::<f32>("gravity"),
+            Some(3.5)
+        );
+        let events = app.world.resource::<Events<CvarChanged>>();
+        let changes = events
+            .get_reader()
+            .read(events)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![CvarChanged {
+                name: "gravity".to_owned(),
+                value: "3.5".to_owned()
+            }]
+        );
+    }
+
+    #[derive(Default, Reflect, Resource)]
+    struct GraphicsSettings {
+        shadow_distance: f32,
+        shadows: Shadows,
+        cascades: Vec<u32>,
+    }
+
+    #[derive(Default, Reflect)]
+    struct Shadows {
+        enabled: bool,
+    }
+
+    #[test]
+    fn test_resource_fields() {
+        let mut world = World::new();
+        world.init_resource::<GraphicsSettings>();
+        let mut cvars = ConsoleVariables::default();
+        cvars.register_cvar_resource::<GraphicsSettings>("gfx");
+
+        let (resource, path) = cvars.resource_field("gfx.shadows.enabled").unwrap();
+        assert_eq!(path, "shadows.enabled");
+        assert_eq!(
+            write_resource_field(&mut world, resource, path, "true"),
+            Ok("true".to_owned())
+        );
+        assert!(world.resource::<GraphicsSettings>().shadows.enabled);
+
+        let (resource, path) = cvars.resource_field("gfx.shadow_distance").unwrap();
+        assert_eq!(
+            write_resource_field(&mut world, resource, path, "120"),
+            Ok("120".to_owned())
+        );
+        assert_eq!(
+            read_resource_field(&world, resource, path),
+            Ok("120".to_owned())
+        );
+        assert!(write_resource_field(&mut world, resource, path, "far").is_err());
+
+        let (resource, path) = cvars.resource_field("gfx.cascades").unwrap();
+        assert_eq!(
+            write_resource_field(&mut world, resource, path, "4").map_err(|err| err.to_string()),
+            Err("fields of type alloc::vec::Vec<u32> can't be set from the console".to_owned())
+        );
+
+        assert!(cvars.resource_field("gfxx.shadow_distance").is_none());
+        assert!(cvars.handles("gfx.anything"));
+    }
+
+    #[test]
+    fn test_interpolate_resource_fields() {
+        let mut app = App::new();
+        app.init_resource::<GraphicsSettings>()
+            .init_resource::<ConsoleVariables>()
+            .register_cvar_resource::<GraphicsSettings>("gfx")
+            .add_systems(Update, snapshot_cvar_resources);
+        app.world
+            .resource_mut::<ConsoleVariables>()
+            .register_cvar("name", "shadows".to_owned());
+        app.update();
+
+        let localization = ConsoleLocalization::default();
+        let cvars = app.world.resource::<ConsoleVariables>();
+        assert_eq!(
+            cvars.interpolate("$gfx.shadows.enabled,${gfx.shadow_distance}", &localization),
+            Ok("false,0".to_owned())
+        );
+        assert_eq!(
+            cvars.interpolate("$name.cfg", &localization),
+            Ok("shadows.cfg".to_owned())
+        );
+        assert!(cvars.interpolate("$gfx.fog", &localization).is_err());
+
+        app.world.resource_mut::<GraphicsSettings>().shadow_distance = 80.0;
+        app.update();
+        let cvars = app.world.resource::<ConsoleVariables>();
+        assert_eq!(
+            cvars.interpolate("$gfx.shadow_distance.", &localization),
+            Ok("80.".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_setting_resource_field_triggers_change_detection() {
+        #[derive(Default, Resource)]
+        struct SettingsChanged(bool);
+
+        fn detect_changes(settings: Res<GraphicsSettings>, mut changed: ResMut<SettingsChanged>) {
+            changed.0 = settings.is_changed();
+        }
+
+        let mut app = App::new();
+        app.init_resource::<GraphicsSettings>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<SettingsChanged>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<PrintConsoleLine>()
+            .add_event::<CvarChanged>()
+            .register_cvar_resource::<GraphicsSettings>("gfx")
+            .add_systems(Update, (cvar_resource_commands, detect_changes).chain());
+        app.update();
+        app.update();
+        assert!(!app.world.resource::<SettingsChanged>().0);
+
+        app.world.send_event(ConsoleCommandEntered::new(
+            "gfx.shadow_distance",
+            vec!["64".to_owned()],
+        ));
+        app.update();
+
+        assert_eq!(
+            app.world.resource::<GraphicsSettings>().shadow_distance,
+            64.0
+        );
+        assert!(app.world.resource::<SettingsChanged>().0);
+    }
+}
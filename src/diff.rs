@@ -0,0 +1,207 @@
+use clap::builder::styling::AnsiColor;
+use clap::builder::StyledStr;
+
+use crate::console::colored;
+use crate::localization::Plural;
+
+/// Number of unchanged lines kept around each change
+const CONTEXT_LINES: usize = 3;
+
+/// Largest LCS table computed before giving up on a minimal diff
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Computes a line-based diff between `old` and `new`.
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut diff = old[..prefix]
+        .iter()
+        .map(|&line| DiffLine::Same(line))
+        .collect::<Vec<_>>();
+
+    if old_middle.len() * new_middle.len() > MAX_TABLE_SIZE {
+        diff.extend(old_middle.iter().map(|&line| DiffLine::Removed(line)));
+        diff.extend(new_middle.iter().map(|&line| DiffLine::Added(line)));
+    } else {
+        diff.extend(lcs_diff(old_middle, new_middle));
+    }
+
+    diff.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|&line| DiffLine::Same(line)),
+    );
+    diff
+}
+
+/// Classic longest-common-subsequence diff, removals before additions.
+fn lcs_diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let width = new.len() + 1;
+    let mut table = vec![0u32; (old.len() + 1) * width];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i * width + j] = if old[i] == new[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            diff.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|&line| DiffLine::Removed(line)));
+    diff.extend(new[j..].iter().map(|&line| DiffLine::Added(line)));
+    diff
+}
+
+/// Renders a line-based diff between `old` and `new` as styled console lines.
+///
+/// Removed lines are prefixed with `-` in red, added lines with `+` in green and
+/// unchanged runs longer than the surrounding context are collapsed into a single line.
+/// With the styling stripped the output reads as a unified diff body.
+pub(crate) fn render_diff(old: &str, new: &str, unchanged_lines: &Plural) -> Vec<StyledStr> {
+    let diff = diff_lines(old, new);
+    let mut lines = Vec::new();
+
+    let mut index = 0;
+    while index < diff.len() {
+        if let DiffLine::Same(_) = diff[index] {
+            let run_end = diff[index..]
+                .iter()
+                .position(|line| !matches!(line, DiffLine::Same(_)))
+                .map_or(diff.len(), |offset| index + offset);
+
+            let keep_before = if index == 0 { 0 } else { CONTEXT_LINES };
+            let keep_after = if run_end == diff.len() {
+                0
+            } else {
+                CONTEXT_LINES
+            };
+
+            let run_len = run_end - index;
+            if run_len > keep_before + keep_after {
+                lines.extend(diff[index..index + keep_before].iter().map(render_line));
+                let collapsed = unchanged_lines.fill(run_len - keep_before - keep_after, &[]);
+                lines.push(colored(AnsiColor::BrightBlack, &format!("  {collapsed}")));
+                lines.extend(diff[run_end - keep_after..run_end].iter().map(render_line));
+            } else {
+                lines.extend(diff[index..run_end].iter().map(render_line));
+            }
+            index = run_end;
+        } else {
+            lines.push(render_line(&diff[index]));
+            index += 1;
+        }
+    }
+
+    lines
+}
+
+fn render_line(line: &DiffLine) -> StyledStr {
+    match line {
+        DiffLine::Same(text) => format!("  {text}").into(),
+        DiffLine::Removed(text) => colored(AnsiColor::Red, &format!("- {text}")),
+        DiffLine::Added(text) => colored(AnsiColor::Green, &format!("+ {text}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConsoleLocalization;
+
+    #[test]
+    fn test_diff_lines_changed_line() {
+        let diff = diff_lines("a\nb\nc", "a\nx\nc");
+
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Same("a"),
+                DiffLine::Removed("b"),
+                DiffLine::Added("x"),
+                DiffLine::Same("c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_lines_insertion_and_removal() {
+        let diff = diff_lines("a\nb\nc", "b\nc\nd");
+
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Removed("a"),
+                DiffLine::Same("b"),
+                DiffLine::Same("c"),
+                DiffLine::Added("d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_diff_collapses_unchanged_lines() {
+        let old = (0..20)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let new = old.replace("10", "ten");
+
+        let unchanged_lines = ConsoleLocalization::default().unchanged_lines;
+        let rendered = render_diff(&old, &new, &unchanged_lines)
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            rendered,
+            vec![
+                "  … 7 unchanged lines",
+                "  7",
+                "  8",
+                "  9",
+                "- 10",
+                "+ ten",
+                "  11",
+                "  12",
+                "  13",
+                "  … 6 unchanged lines",
+            ]
+        );
+    }
+}
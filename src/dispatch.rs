@@ -0,0 +1,1520 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::time::Duration;
+
+use bevy::ecs::component::Tick;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use clap::builder::{styling::AnsiColor, StyledStr};
+use clap::error::ErrorKind;
+use shlex::Shlex;
+
+use crate::console::{
+    colored, confirmation_cancelled, confirmation_prompt, parse_wait, CommandFailed, CommandOrigin,
+    CommandParseFailed, CommandQueue, ConsoleCommandEntered, ConsoleConfiguration, ConsoleState,
+    CvarOverrides, QueuedLine, ScrollbackLine, UnknownConsoleCommand, Wait,
+};
+use crate::instance::{ConsoleMarker, DefaultConsole};
+use crate::localization::fill;
+use crate::script::{stop_strict_script, ScriptLine};
+use crate::{ConsoleAliases, ConsoleGates, ConsoleLocalization, ConsoleVariables, InputAction};
+
+/// Whether the input line would run, shown while it's typed.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum InputValidation {
+    /// Every command of the line can run
+    Valid,
+    /// A command of the line can't run, with the first paragraph of the reason
+    Invalid(String),
+    /// A command of the line isn't registered
+    Unknown,
+}
+
+/// Checks the commands of the input line like entering it would, without running them.
+///
+/// Returns `None` for a blank line. Invalid commands take precedence over unknown ones.
+pub(crate) fn validate_input<M: ConsoleMarker>(
+    buf: &str,
+    lookup: &CommandLookup<M>,
+) -> Option<InputValidation> {
+    if buf.trim().is_empty() {
+        return None;
+    }
+    let config = lookup.config;
+    let mut validation = InputValidation::Valid;
+    for command in split_commands(buf, &|name| config.has_raw_args(name)) {
+        let entered = match parse_wait(command, Duration::ZERO, &config.localization) {
+            Some(Ok(_)) => continue,
+            Some(Err(error)) => return Some(InputValidation::Invalid(error)),
+            None => parse_command_line(command, lookup),
+        };
+        let entered = match entered {
+            Ok(Some(entered)) => entered,
+            Ok(None) => continue,
+            Err(CommandLineError::Unknown(..)) => {
+                validation = InputValidation::Unknown;
+                continue;
+            }
+            Err(CommandLineError::Invalid(error)) => return Some(InputValidation::Invalid(error)),
+        };
+        // Console variables are parsed by their own command system
+        let Some(registered) = config.commands.get(&entered.command_name) else {
+            continue;
+        };
+        let Err(error) = registered
+            .command
+            .clone()
+            .try_get_matches_from(&entered.args)
+        else {
+            continue;
+        };
+        // Asking for help prints it rather than failing
+        let help = matches!(
+            error.kind(),
+            ErrorKind::DisplayHelp
+                | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+                | ErrorKind::DisplayVersion
+        );
+        if !help {
+            let rendered = error.render().to_string();
+            let reason = rendered
+                .lines()
+                .map(str::trim)
+                .take_while(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            return Some(InputValidation::Invalid(reason));
+        }
+    }
+    Some(validation)
+}
+
+/// Echoes a line of input to the scrollback and sends it as a command, like pressing enter.
+///
+/// The line is passed through the configured input filter first.
+pub(crate) fn submit_input<M: ConsoleMarker>(
+    input: &str,
+    state: &mut ConsoleState<M>,
+    lookup: &CommandLookup<M>,
+    queue: &mut CommandQueue<M>,
+    command_entered: &mut EventWriter<ConsoleCommandEntered<M>>,
+    unknown_command: &mut EventWriter<UnknownConsoleCommand<M>>,
+    now: Duration,
+) {
+    let config = lookup.config;
+
+    // The line after a confirmation prompt answers it, instead of being run
+    let origin = CommandOrigin::Local;
+    if answer_confirmation(input, origin, true, state, config, command_entered, now) {
+        return;
+    }
+
+    let original = input;
+    let (input, filtered) = match &config.input_filter {
+        Some(input_filter) if !input.trim().is_empty() => match input_filter(input) {
+            InputAction::Execute(line) => (line, true),
+            InputAction::Print(text) => {
+                echo_input(input, origin, state, config, now);
+                state.push_line(ScrollbackLine::new(text).at(now));
+                record_history(input, state, config);
+                state.trim_scrollback(config.scrollback_size);
+                state.scroll_to_bottom = true;
+                return;
+            }
+            InputAction::Ignore => return,
+        },
+        _ => (input.to_owned(), false),
+    };
+
+    // History references are expanded before the input is echoed or added to the history
+    let input = match state.expand_history(&input) {
+        Ok(input) => input,
+        Err(err) => {
+            let error = colored(AnsiColor::Red, &err);
+            state.push_line(ScrollbackLine::new(error).at(now));
+            state.buf = original.to_owned();
+            state.scroll_to_bottom = true;
+            return;
+        }
+    };
+    let input = input.as_str();
+
+    // Lines which can't be split into arguments are kept to be fixed, rather than run
+    let is_raw = |name: &str| lookup.config.has_raw_args(name);
+    let split = split_commands(input, &is_raw)
+        .into_iter()
+        .filter(|command| !command.split_whitespace().next().is_some_and(is_raw))
+        .try_for_each(|command| split_args(command, &config.localization).map(drop));
+    if let Err(err) = split {
+        let error = colored(AnsiColor::Red, &err);
+        state.push_line(ScrollbackLine::new(error).at(now));
+        state.buf = original.to_owned();
+        state.scroll_to_bottom = true;
+        return;
+    }
+
+    // Commands still waiting to run would have surprising effects after new input
+    if !queue.lines.is_empty() {
+        let cancelled = config
+            .localization
+            .cancelled_waiting_commands
+            .fill(queue.lines.len(), &[]);
+        state.push_line(ScrollbackLine::new(cancelled).at(now));
+    }
+    queue.lines.clear();
+    queue.wait = None;
+    queue.script = None;
+
+    record_history(input, state, config);
+    // The prompt line shows what was typed, not what the input filter made of it
+    echo_input(
+        if filtered { original } else { input },
+        origin,
+        state,
+        config,
+        now,
+    );
+    run_commands(
+        input,
+        None,
+        origin,
+        state,
+        lookup,
+        queue,
+        command_entered,
+        unknown_command,
+        now,
+    );
+}
+
+/// Adds a non-blank entered line to the history.
+fn record_history<M: ConsoleMarker>(
+    input: &str,
+    state: &mut ConsoleState<M>,
+    config: &ConsoleConfiguration<M>,
+) {
+    if !input.trim().is_empty() {
+        state.history.insert(1, input.to_owned().into());
+        state.input_recorded = true;
+        if state.history.len() > config.history_size + 1 {
+            state.history.pop_back();
+        }
+    }
+}
+
+/// Echoes and runs a command line as if it was entered, without adding it to the history.
+pub(crate) fn run_command_line<M: ConsoleMarker>(
+    queued: &QueuedLine,
+    state: &mut ConsoleState<M>,
+    lookup: &CommandLookup<M>,
+    queue: &mut CommandQueue<M>,
+    command_entered: &mut EventWriter<ConsoleCommandEntered<M>>,
+    unknown_command: &mut EventWriter<UnknownConsoleCommand<M>>,
+    now: Duration,
+) -> CommandsRun {
+    if !queued.chained {
+        echo_input(&queued.line, queued.origin, state, lookup.config, now);
+    }
+    run_commands(
+        &queued.line,
+        queued.script.as_ref(),
+        queued.origin,
+        state,
+        lookup,
+        queue,
+        command_entered,
+        unknown_command,
+        now,
+    )
+}
+
+/// Adds an entered line to the scrollback after the prompt symbol.
+fn echo_input<M: ConsoleMarker>(
+    input: &str,
+    origin: CommandOrigin,
+    state: &mut ConsoleState<M>,
+    config: &ConsoleConfiguration<M>,
+    now: Duration,
+) {
+    let line = if input.trim().is_empty() {
+        ScrollbackLine::new(StyledStr::new())
+    } else {
+        ScrollbackLine::echo(&config.symbol, input.to_owned())
+    };
+    state.push_line(line.at(now).with_origin(origin));
+}
+
+/// Answers the pending confirmation with `input` if it was asked for a command entered at
+/// `origin`, returning whether it did.
+pub(crate) fn answer_confirmation<M: ConsoleMarker>(
+    input: &str,
+    origin: CommandOrigin,
+    echo: bool,
+    state: &mut ConsoleState<M>,
+    config: &ConsoleConfiguration<M>,
+    command_entered: &mut EventWriter<ConsoleCommandEntered<M>>,
+    now: Duration,
+) -> bool {
+    let Some(pending) = state.pending_confirmation.take_if(|pending| {
+        pending.command.origin.connection() == origin.connection() && !pending.expired(now)
+    }) else {
+        return false;
+    };
+
+    if pending.repeat && !repeats_command(input, &pending.command, config) {
+        // Moving on cancels the confirmation, the line runs as usual
+        let message = &config.localization.confirmation_cancelled;
+        let cancelled = confirmation_cancelled(&pending.command, message);
+        state.push_line(ScrollbackLine::new(cancelled).at(now).with_origin(origin));
+        return false;
+    }
+    if echo {
+        echo_input(input, origin, state, config, now);
+    }
+    if pending.repeat || config.localization.confirms(input) {
+        command_entered.send(ConsoleCommandEntered {
+            confirmed: true,
+            ..pending.command
+        });
+    } else {
+        let message = &config.localization.confirmation_cancelled;
+        let cancelled = confirmation_cancelled(&pending.command, message);
+        state.push_line(ScrollbackLine::new(cancelled).at(now).with_origin(origin));
+    }
+    state.scroll_to_bottom = true;
+    true
+}
+
+/// Whether `input` is empty or enters the same command as `command`, confirming it if it asked
+/// with [`ConsoleCommand::confirm_repeated`](crate::ConsoleCommand::confirm_repeated).
+fn repeats_command<M: ConsoleMarker>(
+    input: &str,
+    command: &ConsoleCommandEntered<M>,
+    config: &ConsoleConfiguration<M>,
+) -> bool {
+    let Some(name) = input.split_whitespace().next() else {
+        return true;
+    };
+    config
+        .resolve_command(name)
+        .is_some_and(|name| Some(name) == config.resolve_command(&command.command_name))
+}
+
+/// What running the commands of a command line did.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct CommandsRun {
+    /// A command was entered, which runs later in the frame
+    entered: bool,
+    /// A command couldn't be run
+    failed: bool,
+}
+
+/// Runs the commands of a command line, printing why those which can't be run failed.
+///
+/// The commands chained after an entered command are queued to be resolved once it ran, so they
+/// see its effects, and a `wait` command queues those chained after it to run once it's over.
+#[allow(clippy::too_many_arguments)]
+fn run_commands<M: ConsoleMarker>(
+    input: &str,
+    script: Option<&ScriptLine>,
+    origin: CommandOrigin,
+    state: &mut ConsoleState<M>,
+    lookup: &CommandLookup<M>,
+    queue: &mut CommandQueue<M>,
+    command_entered: &mut EventWriter<ConsoleCommandEntered<M>>,
+    unknown_command: &mut EventWriter<UnknownConsoleCommand<M>>,
+    now: Duration,
+) -> CommandsRun {
+    let config = lookup.config;
+    let commands = split_expanded_commands(input, lookup);
+    let mut run = CommandsRun::default();
+    for (index, command) in commands.iter().enumerate() {
+        let wait = parse_wait(command, now, &config.localization);
+        if run.entered && wait.is_none() {
+            queue_chained(queue, &commands[index..], script, origin);
+            break;
+        }
+        let parsed = match wait {
+            Some(Ok(wait)) => {
+                queue.wait = Some(wait);
+                queue_chained(queue, &commands[index + 1..], script, origin);
+                break;
+            }
+            Some(Err(err)) => Err(err.into()),
+            None => parse_command_line(command, lookup),
+        };
+        let line = |text: StyledStr| ScrollbackLine::new(text).at(now).with_origin(origin);
+        if let Ok(Some(command)) = &parsed {
+            if let Some(warning) = deprecation_warning(config, command) {
+                state.push_line(line(warning));
+            }
+            if let Some(note) = override_note(config, command) {
+                state.push_line(line(note));
+            }
+        }
+        let parsed = parsed
+            .map(|command| command.map(|command| ConsoleCommandEntered { origin, ..command }));
+        let error = match parsed {
+            Ok(Some(command)) if needs_confirmation(config, &command) => {
+                let deadline = config.confirmation_timeout.map(|timeout| now + timeout);
+                let localization = &config.localization;
+                let prompt = fill(&localization.confirm_command, &[("command", &command.raw)]);
+                if let Some(replaced) = state.await_confirmation(command, deadline, false) {
+                    let message = &localization.confirmation_cancelled;
+                    let cancelled = confirmation_cancelled(&replaced, message);
+                    state.push_line(line(cancelled));
+                }
+                let prompt = confirmation_prompt(&prompt.into(), localization);
+                state.push_line(line(prompt));
+                continue;
+            }
+            Ok(Some(command)) => {
+                command_entered.send(command);
+                run.entered = true;
+                continue;
+            }
+            Ok(None) => continue,
+            // Left for the app to handle, as if it was registered
+            Err(CommandLineError::Unknown(unknown, _)) if !config.print_unknown_command_error => {
+                unknown_command.send(unknown);
+                run.entered = true;
+                continue;
+            }
+            Err(CommandLineError::Unknown(unknown, error)) => {
+                unknown_command.send(unknown);
+                error
+            }
+            Err(CommandLineError::Invalid(error)) => error,
+        };
+        run.failed = true;
+        state.push_line(line(colored(AnsiColor::Red, &error)));
+    }
+
+    state.trim_scrollback(config.scrollback_size);
+    state.scroll_to_bottom = true;
+    run
+}
+
+/// Splits a command line into its commands, splitting the expansions of aliases into several
+/// commands too.
+pub(crate) fn split_expanded_commands<M: ConsoleMarker>(
+    input: &str,
+    lookup: &CommandLookup<M>,
+) -> Vec<String> {
+    let mut commands = Vec::new();
+    split_expanded(input, lookup, &mut Vec::new(), &mut commands);
+    commands
+}
+
+/// Pushes the commands of `input` to `commands`, skipping the aliases in `expanding` so aliases
+/// referring to each other can't loop forever.
+fn split_expanded<M: ConsoleMarker>(
+    input: &str,
+    lookup: &CommandLookup<M>,
+    expanding: &mut Vec<String>,
+    commands: &mut Vec<String>,
+) {
+    let config = lookup.config;
+    let is_raw = |name: &str| config.has_raw_args(name);
+    for command in split_commands(input, &is_raw) {
+        let name = command.split_whitespace().next().unwrap_or_default();
+        let expanded = lookup.aliases.expand(config, command);
+        let parts = split_commands(&expanded, &is_raw);
+        if parts.len() < 2 || expanding.iter().any(|alias| alias == name) {
+            commands.push(command.trim().to_owned());
+            continue;
+        }
+        expanding.push(name.to_owned());
+        for part in parts {
+            split_expanded(part, lookup, expanding, commands);
+        }
+        expanding.pop();
+    }
+}
+
+/// Queues chained commands to run before the other queued lines, without echoing them again.
+fn queue_chained<M: ConsoleMarker>(
+    queue: &mut CommandQueue<M>,
+    commands: &[String],
+    script: Option<&ScriptLine>,
+    origin: CommandOrigin,
+) {
+    for command in commands.iter().rev() {
+        queue.lines.push_front(QueuedLine {
+            line: command.clone(),
+            script: script.cloned(),
+            chained: true,
+            origin,
+        });
+    }
+}
+
+/// Dim warning for a command entered by a deprecated alias, naming the command to use instead.
+fn deprecation_warning<M: ConsoleMarker>(
+    config: &ConsoleConfiguration<M>,
+    command: &ConsoleCommandEntered<M>,
+) -> Option<StyledStr> {
+    let name = command.raw.split_whitespace().next()?;
+    let replacement = config.deprecation(name)?;
+    let warning = fill(
+        &config.localization.deprecated_alias,
+        &[("name", &name), ("command", &replacement)],
+    );
+    let style = AnsiColor::Yellow.on_default().dimmed();
+    Some(format!("{}{warning}{}", style.render(), style.render_reset()).into())
+}
+
+/// Dim note naming the console variables set for the duration of a command.
+fn override_note<M: ConsoleMarker>(
+    config: &ConsoleConfiguration<M>,
+    command: &ConsoleCommandEntered<M>,
+) -> Option<StyledStr> {
+    if command.overrides.is_empty() {
+        return None;
+    }
+    let variables = command
+        .overrides
+        .iter()
+        .map(|(name, value)| format!("{name} = {value}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let note = fill(
+        &config.localization.temporary_variables,
+        &[("variables", &variables)],
+    );
+    let style = AnsiColor::BrightBlack.on_default().dimmed();
+    Some(format!("{}{note}{}", style.render(), style.render_reset()).into())
+}
+
+/// Whether `command` is registered with [`CommandMeta::confirm`](crate::CommandMeta::confirm)
+/// and wasn't confirmed yet.
+fn needs_confirmation<M: ConsoleMarker>(
+    config: &ConsoleConfiguration<M>,
+    command: &ConsoleCommandEntered<M>,
+) -> bool {
+    !command.confirmed
+        && config
+            .commands
+            .get(&command.command_name)
+            .is_some_and(|registered| registered.meta.confirm)
+}
+
+/// Splits a command line into the commands separated by `;`, skipping empty commands.
+///
+/// Quoted or escaped semicolons don't separate commands, like in the shell. A command for which
+/// `is_raw` is true takes the rest of the line, semicolons included.
+pub(crate) fn split_commands<'a>(input: &'a str, is_raw: &dyn Fn(&str) -> bool) -> Vec<&'a str> {
+    let raw_from = |start: usize| {
+        let name = input[start..].split_whitespace().next();
+        name.is_some_and(is_raw)
+    };
+    let mut commands = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut raw = raw_from(0);
+    for (index, c) in input.char_indices() {
+        if raw {
+            break;
+        }
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (c, quote) {
+            // Backslashes are literal within single quotes
+            ('\\', Some('\'')) => {}
+            ('\\', _) => escaped = true,
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (';', None) => {
+                commands.push(&input[start..index]);
+                start = index + 1;
+                raw = raw_from(start);
+            }
+            _ => {}
+        }
+    }
+    commands.push(&input[start..]);
+    commands.retain(|command| !command.trim().is_empty());
+    commands
+}
+
+/// Length of the first word of `input` as the shell splits it, up to unquoted whitespace.
+fn first_word_len(input: &str) -> usize {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in input.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (c, quote) {
+            ('\\', Some('\'')) => {}
+            ('\\', _) => escaped = true,
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (c, None) if c.is_whitespace() => return index,
+            _ => {}
+        }
+    }
+    input.len()
+}
+
+/// Splits the console variables set for the duration of a command off its front, like
+/// `gravity=0` in `gravity=0 spawn_level`, returning their names and values and the command.
+///
+/// Words before the command are only taken as variables if a variable of that name exists, and
+/// their values are checked against its type.
+fn split_overrides<'a, M: ConsoleMarker>(
+    input: &'a str,
+    lookup: &CommandLookup<M>,
+) -> Result<(CvarOverrides, &'a str), String> {
+    let CommandLookup { config, cvars, .. } = *lookup;
+    let mut overrides = Vec::new();
+    let mut rest = input.trim_start();
+    loop {
+        let word = &rest[..first_word_len(rest)];
+        let Some((name, _)) = word.split_once('=') else {
+            break;
+        };
+        if !cvars.contains(name) {
+            break;
+        }
+        let has_variables = word.contains('$');
+        let word = if has_variables {
+            escape_quoted_dollars(word)
+        } else {
+            word.to_owned()
+        };
+        let mut arg = split_args(&word, &config.localization)?.concat();
+        if has_variables {
+            arg = cvars.interpolate(&arg, &config.localization)?;
+        }
+        let value = arg[name.len() + 1..].to_owned();
+        cvars.check(name, &value).map_err(|err| {
+            let error = err.describe(&config.localization);
+            fill(&config.localization.variable_error, &[("error", &error)])
+        })?;
+        overrides.push((name.to_owned(), value));
+        rest = rest[first_word_len(rest)..].trim_start();
+    }
+
+    if !overrides.is_empty() && rest.is_empty() {
+        let variables = overrides
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        return Err(fill(
+            &config.localization.variables_without_command,
+            &[("variables", &variables)],
+        ));
+    }
+    Ok((overrides, rest))
+}
+
+/// Doubles the `$` within single quotes, so they stay literal when console variables are
+/// interpolated, like in the shell.
+fn escape_quoted_dollars(line: &str) -> String {
+    let mut escaped = String::with_capacity(line.len());
+    let mut quote = None;
+    let mut backslash = false;
+    for c in line.chars() {
+        if backslash {
+            backslash = false;
+        } else {
+            match (c, quote) {
+                // Backslashes are literal within single quotes
+                ('\\', Some('\'')) => {}
+                ('\\', _) => backslash = true,
+                ('\'' | '"', None) => quote = Some(c),
+                (c, Some(open)) if c == open => quote = None,
+                ('$', Some('\'')) => escaped.push('$'),
+                _ => {}
+            }
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Splits a command into its arguments like the shell does.
+///
+/// Fails on an unterminated quote or a trailing backslash, instead of guessing the arguments.
+pub(crate) fn split_args(
+    command: &str,
+    localization: &ConsoleLocalization,
+) -> Result<Vec<String>, String> {
+    let mut lexer = Shlex::new(command);
+    let args = lexer.by_ref().collect::<Vec<_>>();
+    if !lexer.had_error {
+        return Ok(args);
+    }
+
+    let mut quote = None;
+    let mut escaped = false;
+    for c in command.chars() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (c, quote) {
+            ('\\', Some('\'')) => {}
+            ('\\', _) => escaped = true,
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            _ => {}
+        }
+    }
+    let error = if quote.is_some() {
+        &localization.unterminated_quote
+    } else {
+        &localization.trailing_backslash
+    };
+    Err(error.clone())
+}
+
+/// Resources used to resolve the command an entered line runs.
+#[derive(SystemParam)]
+pub(crate) struct CommandResources<'w> {
+    aliases: Res<'w, ConsoleAliases>,
+    pub(crate) cvars: Res<'w, ConsoleVariables>,
+    gates: Res<'w, ConsoleGates>,
+}
+
+/// When the resources used to resolve a line last changed, see [`CommandResources::generation`].
+pub(crate) type LookupGeneration = [Tick; 4];
+
+impl CommandResources<'_> {
+    /// Changes when any resource used to resolve a line changes, `config_changed` being when the
+    /// [`ConsoleConfiguration`] did, so a line resolved earlier may now resolve differently.
+    pub(crate) fn generation(&self, config_changed: Tick) -> LookupGeneration {
+        [
+            config_changed,
+            self.aliases.last_changed(),
+            self.cvars.last_changed(),
+            self.gates.last_changed(),
+        ]
+    }
+
+    pub(crate) fn lookup<'a, M>(
+        &'a self,
+        config: &'a ConsoleConfiguration<M>,
+    ) -> CommandLookup<'a, M> {
+        CommandLookup {
+            config,
+            aliases: &self.aliases,
+            cvars: &self.cvars,
+            gates: &self.gates,
+        }
+    }
+}
+
+/// Everything resolving the command an entered line runs depends on.
+pub(crate) struct CommandLookup<'a, M = DefaultConsole> {
+    pub(crate) config: &'a ConsoleConfiguration<M>,
+    pub(crate) aliases: &'a ConsoleAliases,
+    pub(crate) cvars: &'a ConsoleVariables,
+    pub(crate) gates: &'a ConsoleGates,
+}
+
+/// Why an entered command line can't be run.
+#[derive(Debug, PartialEq)]
+pub(crate) enum CommandLineError<M = DefaultConsole> {
+    /// No command has the entered name, with the error suggesting similar commands
+    Unknown(UnknownConsoleCommand<M>, String),
+    /// The line can't be run, e.g. because it refers to a variable which doesn't exist
+    Invalid(String),
+}
+
+impl<M> From<String> for CommandLineError<M> {
+    fn from(error: String) -> Self {
+        CommandLineError::Invalid(error)
+    }
+}
+
+impl<M> fmt::Display for CommandLineError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandLineError::Unknown(_, error) | CommandLineError::Invalid(error) => {
+                f.write_str(error)
+            }
+        }
+    }
+}
+
+/// Expands aliases in a command line and resolves the command it runs.
+///
+/// Returns `None` if the line is empty, or an error if the command is unknown or unavailable.
+pub(crate) fn parse_command_line<M: ConsoleMarker>(
+    input: &str,
+    lookup: &CommandLookup<M>,
+) -> Result<Option<ConsoleCommandEntered<M>>, CommandLineError<M>> {
+    let CommandLookup {
+        config,
+        aliases,
+        cvars,
+        ..
+    } = *lookup;
+
+    let raw = input.trim();
+    let (overrides, input) = split_overrides(input, lookup)?;
+
+    // Raw commands get the rest of the line verbatim, without quoting, variables or aliases
+    let trimmed = input.trim();
+    let (first, rest) = trimmed
+        .split_once(char::is_whitespace)
+        .unwrap_or((trimmed, ""));
+    if let Some(name) = config
+        .resolve_command(first)
+        .filter(|name| config.has_raw_args(name))
+    {
+        check_gate(lookup, name, first)?;
+        let rest = rest.trim();
+        return Ok(Some(ConsoleCommandEntered {
+            command_name: name.to_owned(),
+            args: if rest.is_empty() {
+                vec![]
+            } else {
+                vec![rest.to_owned()]
+            },
+            raw: raw.to_owned(),
+            confirmed: false,
+            origin: CommandOrigin::Local,
+            overrides,
+            marker: PhantomData,
+        }));
+    }
+
+    let expanded = aliases.expand(config, input);
+    let has_variables = expanded.contains('$');
+    let expanded = if has_variables {
+        escape_quoted_dollars(&expanded)
+    } else {
+        expanded
+    };
+    let mut args = split_args(&expanded, &config.localization)?;
+    if args.is_empty() {
+        return Ok(None);
+    }
+
+    // Console variables are substituted after splitting, so values with spaces stay one argument
+    if has_variables {
+        args = args
+            .iter()
+            .map(|arg| cvars.interpolate(arg, &config.localization))
+            .collect::<Result<_, _>>()?;
+        debug!("Console variables interpolated: `{args:?}`");
+    }
+
+    let command_name = args.remove(0);
+    debug!("Command entered: `{command_name}`, with args: `{args:?}`");
+
+    // Console variables are printed and set by a command system of their own
+    let name = config.resolve_command(&command_name).or_else(|| {
+        cvars
+            .handles(&command_name)
+            .then_some(command_name.as_str())
+    });
+    let name = match name {
+        None if config.allow_prefix_matching => {
+            match *config.commands_with_prefix(&command_name).as_slice() {
+                [] => None,
+                [name] => Some(name),
+                ref candidates => {
+                    let localization = &config.localization;
+                    return Err(fill(
+                        &localization.ambiguous_command,
+                        &[
+                            ("name", &command_name),
+                            ("names", &quoted_list(candidates, &localization.or)),
+                        ],
+                    )
+                    .into());
+                }
+            }
+        }
+        name => name,
+    };
+    if let Some(name) = name {
+        check_gate(lookup, name, &command_name)?;
+    }
+
+    match name {
+        Some(name) => Ok(Some(ConsoleCommandEntered {
+            command_name: name.to_owned(),
+            args,
+            raw: raw.to_owned(),
+            confirmed: false,
+            origin: CommandOrigin::Local,
+            overrides,
+            marker: PhantomData,
+        })),
+        None => {
+            debug!(
+                "Command not recognized, recognized commands: `{:?}`",
+                config.commands.keys().collect::<Vec<_>>()
+            );
+            let error = unknown_command_error(config, &command_name);
+            let unknown = UnknownConsoleCommand {
+                name: command_name,
+                args,
+                raw: raw.to_owned(),
+                marker: PhantomData,
+            };
+            Err(CommandLineError::Unknown(unknown, error))
+        }
+    }
+}
+
+/// Gated commands are known, but can't be run while their gate is closed
+fn check_gate<M: ConsoleMarker>(
+    lookup: &CommandLookup<M>,
+    name: &str,
+    entered: &str,
+) -> Result<(), String> {
+    let gate = lookup
+        .config
+        .commands
+        .get(name)
+        .and_then(|registered| registered.meta.requires.as_deref());
+    match gate.filter(|gate| !lookup.gates.is_open(gate, lookup.cvars)) {
+        Some(gate) => Err(fill(
+            &lookup.config.localization.unavailable_command,
+            &[("name", &entered), ("gate", &gate)],
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Runs the command lines queued by scripts and key bindings, echoing them into the scrollback.
+///
+/// Lines run until one enters commands, the next line runs once they ran.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_queued_commands<M: ConsoleMarker>(
+    mut queue: ResMut<CommandQueue<M>>,
+    mut state: ResMut<ConsoleState<M>>,
+    config: Res<ConsoleConfiguration<M>>,
+    resources: CommandResources,
+    mut command_entered: EventWriter<ConsoleCommandEntered<M>>,
+    mut unknown_command: EventWriter<UnknownConsoleCommand<M>>,
+    mut failures: EventReader<CommandFailed<M>>,
+    mut parse_failures: EventReader<CommandParseFailed<M>>,
+    time: Res<Time<Real>>,
+) {
+    let now = time.elapsed();
+    let lookup = resources.lookup(&config);
+
+    // The commands of the script line run last frame failed if any command failed since
+    let failed = failures.read().count() + parse_failures.read().count() > 0;
+    if let Some(line) = queue.script.take().filter(|_| failed) {
+        stop_script(&line, &mut state, &config, &mut queue, now);
+    }
+
+    if let Some(wait) = &mut queue.wait {
+        let over = match wait {
+            Wait::Frames(frames) => {
+                *frames = frames.saturating_sub(1);
+                *frames == 0
+            }
+            Wait::Until(until) => now >= *until,
+        };
+        if !over {
+            return;
+        }
+        queue.wait = None;
+    }
+
+    // A queued `wait` stops running the lines after it
+    while queue.wait.is_none() {
+        let Some(line) = queue.lines.pop_front() else {
+            break;
+        };
+        let run = run_command_line(
+            &line,
+            &mut state,
+            &lookup,
+            &mut queue,
+            &mut command_entered,
+            &mut unknown_command,
+            now,
+        );
+        let stopped = run.failed
+            && line
+                .script
+                .as_ref()
+                .is_some_and(|script| stop_script(script, &mut state, &config, &mut queue, now));
+        if run.entered {
+            queue.script = line.script.filter(|_| !stopped);
+            break;
+        }
+    }
+}
+
+/// Stops the script of a failed line if it's strict, printing why, and returns whether it did.
+fn stop_script<M: ConsoleMarker>(
+    line: &ScriptLine,
+    state: &mut ConsoleState<M>,
+    config: &ConsoleConfiguration<M>,
+    queue: &mut CommandQueue<M>,
+    now: Duration,
+) -> bool {
+    let Some(error) = stop_strict_script(line, &config.localization, queue) else {
+        return false;
+    };
+    let error = colored(AnsiColor::Red, &error);
+    state.push_line(ScrollbackLine::new(error).at(now));
+    true
+}
+
+/// Error for an unknown command, suggesting up to three registered commands with similar names.
+fn unknown_command_error<M: ConsoleMarker>(config: &ConsoleConfiguration<M>, name: &str) -> String {
+    // Allow one typo per three characters, so short names don't match everything
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut similar = config
+        .visible_commands()
+        .map(|command| (edit_distance(name, command), command))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect::<Vec<_>>();
+    similar.sort();
+
+    let suggestions = similar
+        .iter()
+        .take(3)
+        .map(|(_, command)| *command)
+        .collect::<Vec<_>>();
+    let localization = &config.localization;
+    let error = fill(&localization.unknown_command, &[("name", &name)]);
+    if suggestions.is_empty() {
+        error
+    } else {
+        let names = quoted_list(&suggestions, &localization.or);
+        fill(
+            &localization.did_you_mean,
+            &[("error", &error), ("names", &names)],
+        )
+    }
+}
+
+/// Quotes names and joins them like `'a', 'b' or 'c'`, with `or` in the given language.
+fn quoted_list(names: &[&str], or: &str) -> String {
+    let quoted = names
+        .iter()
+        .map(|name| format!("'{name}'"))
+        .collect::<Vec<_>>();
+    match quoted.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} {or} {last}", rest.join(", ")),
+    }
+}
+
+/// Number of single character insertions, deletions, substitutions and swaps of adjacent
+/// characters turning `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+
+    // Rows of the distance matrix for the previous two and the current prefix of `a`
+    let mut before_previous = vec![0; b.len() + 1];
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let substitution = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + substitution);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+        mem::swap(&mut before_previous, &mut previous);
+        mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::event::ManualEventReader;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{CommandMeta, ConsoleCommandEntered, ConsoleConfiguration, UnknownConsoleCommand};
+
+    #[test]
+    fn test_validate_input() {
+        let mut config = ConsoleConfiguration::default();
+        let spawn = clap::Command::new("spawn").arg(
+            clap::Arg::new("count")
+                .required(true)
+                .value_parser(clap::value_parser!(u32)),
+        );
+        config.register_raw_console_command("spawn", spawn);
+        let aliases = ConsoleAliases::default();
+        let cvars = ConsoleVariables::default();
+        let gates = ConsoleGates::default();
+        let lookup = CommandLookup {
+            config: &config,
+            aliases: &aliases,
+            cvars: &cvars,
+            gates: &gates,
+        };
+        let validate = |buf: &str| validate_input(buf, &lookup);
+
+        assert_eq!(validate("  "), None);
+        assert_eq!(validate("spawn 3"), Some(InputValidation::Valid));
+        assert_eq!(validate("spawn --help"), Some(InputValidation::Valid));
+        assert_eq!(
+            validate("spawn 3; wait 2; spawn 1"),
+            Some(InputValidation::Valid)
+        );
+        assert_eq!(validate("lua run"), Some(InputValidation::Unknown));
+        assert_eq!(
+            validate("spawn three"),
+            Some(InputValidation::Invalid(
+                "error: invalid value 'three' for '<count>': invalid digit found in string"
+                    .to_owned()
+            ))
+        );
+        let Some(InputValidation::Invalid(missing)) = validate("spawn") else {
+            panic!("spawn without a count should be invalid");
+        };
+        assert!(missing.starts_with("error: the following required arguments"));
+        // The first invalid command wins over unknown ones
+        assert!(matches!(
+            validate("lua; spawn x; wait forever"),
+            Some(InputValidation::Invalid(_))
+        ));
+        assert!(matches!(
+            validate("spawn 1; wait forever"),
+            Some(InputValidation::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("spawn", "spawn"), 0);
+        assert_eq!(edit_distance("spwan", "spawn"), 1);
+        assert_eq!(edit_distance("spwn", "spawn"), 1);
+        assert_eq!(edit_distance("spawnn", "spawn"), 1);
+        assert_eq!(edit_distance("", "help"), 4);
+    }
+
+    #[test]
+    fn test_wait_delays_chained_commands() {
+        let mut app = App::new();
+        app.init_resource::<ConsoleState>()
+            .init_resource::<ConsoleConfiguration>()
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<CommandQueue>()
+            .init_resource::<Time<Real>>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<UnknownConsoleCommand>()
+            .add_event::<CommandFailed>()
+            .add_event::<CommandParseFailed>()
+            .add_systems(Update, run_queued_commands::<DefaultConsole>);
+        app.world
+            .resource_mut::<ConsoleConfiguration>()
+            .register_raw_console_command("spawn", clap::Command::new("spawn"));
+        app.world
+            .resource_mut::<CommandQueue>()
+            .lines
+            .push_back("spawn cube; wait 2; spawn sphere".into());
+
+        let mut reader = ManualEventReader::<ConsoleCommandEntered>::default();
+        let mut entered = |app: &mut App| {
+            app.update();
+            let events = app.world.resource::<Events<ConsoleCommandEntered>>();
+            reader
+                .read(events)
+                .map(|event| event.raw.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(entered(&mut app), ["spawn cube"]);
+        assert!(entered(&mut app).is_empty());
+        assert_eq!(entered(&mut app), ["spawn sphere"]);
+        assert!(app.world.resource::<CommandQueue>().wait.is_none());
+    }
+
+    #[test]
+    fn test_input_filter() {
+        fn submit(
+            mut state: ResMut<ConsoleState>,
+            config: Res<ConsoleConfiguration>,
+            resources: CommandResources,
+            mut queue: ResMut<CommandQueue>,
+            mut command_entered: EventWriter<ConsoleCommandEntered>,
+            mut unknown_command: EventWriter<UnknownConsoleCommand>,
+        ) {
+            for input in ["/spawn cube", "hello", "#"] {
+                submit_input(
+                    input,
+                    &mut state,
+                    &resources.lookup(&config),
+                    &mut queue,
+                    &mut command_entered,
+                    &mut unknown_command,
+                    Duration::ZERO,
+                );
+            }
+        }
+
+        let mut app = App::new();
+        app.init_resource::<ConsoleState>()
+            .init_resource::<ConsoleConfiguration>()
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<CommandQueue>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<UnknownConsoleCommand>()
+            .add_systems(Update, submit);
+        let mut config = app.world.resource_mut::<ConsoleConfiguration>();
+        config.register_raw_console_command("spawn", clap::Command::new("spawn"));
+        config.input_filter = Some(Arc::new(|input: &str| match input.strip_prefix('/') {
+            Some(command) => InputAction::Execute(command.to_owned()),
+            None if input == "#" => InputAction::Ignore,
+            None => InputAction::Print(format!("<player> {input}").into()),
+        }));
+        app.update();
+
+        let entered = app.world.resource::<Events<ConsoleCommandEntered>>();
+        let raw = ManualEventReader::<ConsoleCommandEntered>::default()
+            .read(entered)
+            .map(|event| event.raw.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(raw, ["spawn cube"]);
+
+        let state = app.world.resource::<ConsoleState>();
+        let scrollback = state
+            .scrollback
+            .iter()
+            .map(|line| line.text.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(scrollback, ["/spawn cube", "hello", "<player> hello"]);
+        assert_eq!(state.history_entry(1), Some("hello".to_owned()));
+        assert_eq!(state.history_entry(2), Some("spawn cube".to_owned()));
+    }
+
+    #[test]
+    fn test_confirmation() {
+        fn submit(
+            mut state: ResMut<ConsoleState>,
+            config: Res<ConsoleConfiguration>,
+            resources: CommandResources,
+            mut queue: ResMut<CommandQueue>,
+            mut command_entered: EventWriter<ConsoleCommandEntered>,
+            mut unknown_command: EventWriter<UnknownConsoleCommand>,
+        ) {
+            for input in ["wipe", "no", "wipe", "wipe", "wipe all", "y", "save"] {
+                submit_input(
+                    input,
+                    &mut state,
+                    &resources.lookup(&config),
+                    &mut queue,
+                    &mut command_entered,
+                    &mut unknown_command,
+                    Duration::ZERO,
+                );
+            }
+        }
+
+        let mut app = App::new();
+        app.init_resource::<ConsoleState>()
+            .init_resource::<ConsoleConfiguration>()
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<CommandQueue>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<UnknownConsoleCommand>()
+            .add_systems(Update, submit);
+        let mut config = app.world.resource_mut::<ConsoleConfiguration>();
+        config.register_raw_console_command_with(
+            "wipe",
+            clap::Command::new("wipe").arg(clap::Arg::new("what")),
+            CommandMeta {
+                confirm: true,
+                ..default()
+            },
+        );
+        config.register_raw_console_command("save", clap::Command::new("save"));
+        app.update();
+
+        let entered = app.world.resource::<Events<ConsoleCommandEntered>>();
+        let entered = ManualEventReader::<ConsoleCommandEntered>::default()
+            .read(entered)
+            .map(|event| (event.raw.clone(), event.confirmed))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entered,
+            [("wipe all".to_owned(), true), ("save".to_owned(), false)]
+        );
+
+        let state = app.world.resource::<ConsoleState>();
+        let scrollback = state
+            .scrollback
+            .iter()
+            .map(|line| line.text.to_string())
+            .filter(|line| line.contains("cancelled"))
+            .count();
+        assert_eq!(scrollback, 2);
+        assert!(state.pending_confirmation.is_none());
+    }
+
+    #[test]
+    fn test_split_args() {
+        let localization = ConsoleLocalization::default();
+        assert_eq!(
+            split_args(r#"say "hello world" 'it''s' \"quoted\""#, &localization),
+            Ok(vec![
+                "say".to_owned(),
+                "hello world".to_owned(),
+                "its".to_owned(),
+                "\"quoted\"".to_owned()
+            ])
+        );
+        let unterminated = Err("parse error: unterminated quote".to_owned());
+        assert_eq!(split_args(r#"say "hello"#, &localization), unterminated);
+        assert_eq!(split_args("say 'hello", &localization), unterminated);
+        // Backslashes don't escape within single quotes
+        assert_eq!(split_args(r#"say 'it\'s'"#, &localization), unterminated);
+        assert_eq!(
+            split_args(r"say hello\", &localization),
+            Err("parse error: trailing backslash".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        let config = ConsoleConfiguration::default();
+        let aliases = ConsoleAliases::default();
+        let cvars = ConsoleVariables::default();
+        let gates = ConsoleGates::default();
+        let lookup = CommandLookup {
+            config: &config,
+            aliases: &aliases,
+            cvars: &cvars,
+            gates: &gates,
+        };
+        let Err(CommandLineError::Unknown(unknown, error)) =
+            parse_command_line("  lua  run intro ", &lookup)
+        else {
+            panic!("lua should be unknown");
+        };
+        assert_eq!(
+            unknown,
+            UnknownConsoleCommand {
+                name: "lua".to_owned(),
+                args: vec!["run".to_owned(), "intro".to_owned()],
+                raw: "lua  run intro".to_owned(),
+                marker: PhantomData,
+            }
+        );
+        assert!(error.starts_with("error: unknown command 'lua'"));
+    }
+
+    #[test]
+    fn test_raw_args() {
+        let mut config = ConsoleConfiguration::default();
+        config.register_raw_console_command_with(
+            "lua",
+            clap::Command::new("lua").arg(clap::Arg::new("code")),
+            CommandMeta {
+                raw_args: true,
+                ..default()
+            },
+        );
+        let aliases = ConsoleAliases::default();
+        let cvars = ConsoleVariables::default();
+        let gates = ConsoleGates::default();
+        let lookup = CommandLookup {
+            config: &config,
+            aliases: &aliases,
+            cvars: &cvars,
+            gates: &gates,
+        };
+        let args = |input: &str| {
+            parse_command_line(input, &lookup)
+                .unwrap()
+                .map(|command| command.args)
+        };
+
+        assert_eq!(
+            args(r#" lua print("it's $x")  "#),
+            Some(vec![r#"print("it's $x")"#.to_owned()])
+        );
+        assert_eq!(args("lua"), Some(vec![]));
+    }
+
+    #[test]
+    fn test_single_quotes_keep_variables() {
+        let mut config = ConsoleConfiguration::default();
+        config.register_raw_console_command("say", clap::Command::new("say"));
+        let aliases = ConsoleAliases::default();
+        let mut cvars = ConsoleVariables::default();
+        cvars.register_cvar("name", "player".to_owned());
+        let gates = ConsoleGates::default();
+        let lookup = CommandLookup {
+            config: &config,
+            aliases: &aliases,
+            cvars: &cvars,
+            gates: &gates,
+        };
+        let args = |input: &str| {
+            parse_command_line(input, &lookup)
+                .unwrap()
+                .map(|command| command.args)
+        };
+
+        assert_eq!(
+            args(r#"say "$name" '$name' '$$' it\'s"#),
+            Some(vec![
+                "player".to_owned(),
+                "$name".to_owned(),
+                "$$".to_owned(),
+                "it's".to_owned()
+            ])
+        );
+        assert_eq!(
+            args(r#"say "it's $name's""#),
+            Some(vec!["it's player's".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_variable_overrides() {
+        let mut config = ConsoleConfiguration::default();
+        config.register_raw_console_command("spawn", clap::Command::new("spawn"));
+        let aliases = ConsoleAliases::default();
+        let mut cvars = ConsoleVariables::default();
+        cvars
+            .register_cvar("gravity", 9.81_f32)
+            .register_cvar("name", "player".to_owned());
+        let gates = ConsoleGates::default();
+        let lookup = CommandLookup {
+            config: &config,
+            aliases: &aliases,
+            cvars: &cvars,
+            gates: &gates,
+        };
+        let parse = |input: &str| parse_command_line(input, &lookup).map(Option::unwrap);
+
+        let command = parse("gravity=0 name='big $name' spawn cube").unwrap();
+        assert_eq!(command.command_name, "spawn");
+        assert_eq!(command.args, ["cube"]);
+        assert_eq!(command.raw, "gravity=0 name='big $name' spawn cube");
+        assert_eq!(
+            command.overrides,
+            [
+                ("gravity".to_owned(), "0".to_owned()),
+                ("name".to_owned(), "big $name".to_owned())
+            ]
+        );
+        let command = parse("name=$gravity spawn").unwrap();
+        assert_eq!(command.overrides, [("name".to_owned(), "9.81".to_owned())]);
+        // Arguments of the command aren't variables
+        assert_eq!(parse("spawn gravity=0").unwrap().args, ["gravity=0"]);
+
+        assert!(matches!(
+            parse("speed=2 spawn"),
+            Err(CommandLineError::Unknown(unknown, _)) if unknown.name == "speed=2"
+        ));
+        assert!(matches!(
+            parse("gravity=up spawn"),
+            Err(CommandLineError::Invalid(error)) if error.starts_with("error: invalid value 'up'")
+        ));
+        assert!(matches!(
+            parse("gravity=0"),
+            Err(CommandLineError::Invalid(error)) if error.starts_with("error: no command to run")
+        ));
+    }
+
+    #[test]
+    fn test_prefix_matching() {
+        let mut config = ConsoleConfiguration::default();
+        for name in ["help", "teleport", "test"] {
+            config.register_raw_console_command(name, clap::Command::new(name));
+        }
+        let aliases = ConsoleAliases::default();
+        let cvars = ConsoleVariables::default();
+        let gates = ConsoleGates::default();
+        let parse = |config: &ConsoleConfiguration, input: &str| {
+            let lookup = CommandLookup {
+                config,
+                aliases: &aliases,
+                cvars: &cvars,
+                gates: &gates,
+            };
+            parse_command_line(input, &lookup)
+                .map(|command| command.map(|command| command.command_name))
+                .map_err(|err| err.to_string())
+        };
+
+        assert!(parse(&config, "hel").is_err());
+        config.allow_prefix_matching = true;
+        assert_eq!(parse(&config, "hel"), Ok(Some("help".to_owned())));
+        assert_eq!(
+            parse(&config, "te"),
+            Err("error: ambiguous command 'te', could be 'teleport' or 'test'".to_owned())
+        );
+        assert_eq!(parse(&config, "test"), Ok(Some("test".to_owned())));
+    }
+
+    #[test]
+    fn test_split_commands() {
+        assert_eq!(
+            split_commands("god; noclip;give rocketlauncher", &|_| false),
+            vec!["god", " noclip", "give rocketlauncher"]
+        );
+        assert_eq!(
+            split_commands(r#"say "a;b" 'c;d' e\;f; ;;quit"#, &|_| false),
+            vec![r#"say "a;b" 'c;d' e\;f"#, "quit"]
+        );
+        assert_eq!(
+            split_commands(r"say 'back\'; quit", &|_| false),
+            vec![r"say 'back\'", " quit"]
+        );
+        assert!(split_commands(" ; ", &|_| false).is_empty());
+        assert_eq!(
+            split_commands("god; lua a = 1; b = 'x", &|name| name == "lua"),
+            vec!["god", " lua a = 1; b = 'x"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_command_suggestions() {
+        let mut config = ConsoleConfiguration::default();
+        for name in ["clear", "exit", "help", "same", "save", "spawn"] {
+            config.register_raw_console_command(name, clap::Command::new(name));
+        }
+
+        let error = |name| unknown_command_error(&config, name);
+        assert_eq!(
+            error("spwan"),
+            "error: unknown command 'spwan', did you mean 'spawn'?"
+        );
+        assert_eq!(
+            error("hlep"),
+            "error: unknown command 'hlep', did you mean 'help'?"
+        );
+        assert_eq!(
+            error("spwn"),
+            "error: unknown command 'spwn', did you mean 'spawn'?"
+        );
+        assert_eq!(
+            error("sale"),
+            "error: unknown command 'sale', did you mean 'same' or 'save'?"
+        );
+        assert_eq!(error("x"), "error: unknown command 'x'");
+        assert_eq!(error("teleport"), "error: unknown command 'teleport'");
+    }
+}
@@ -0,0 +1,100 @@
+//! Consoles besides the default one, each with its own configuration, scrollback and commands.
+//!
+//! A console is told apart by a marker type, added with `ConsolePlugin::<Marker>::default()`.
+//! The types of this module take the marker as their last type parameter, which defaults to
+//! [`DefaultConsole`], the console added by [`ConsolePlugin::new`](crate::ConsolePlugin::new).
+//! The types of the same name at the crate root are aliases for the default console.
+//!
+//! Commands are added to a console with
+//! [`AddConsoleCommand::add_console_command_for`](crate::AddConsoleCommand::add_console_command_for)
+//! and are only run from that console. Console variables, aliases and gates are shared by all
+//! consoles. Key bindings, scripts, the standard input, the remote console, captured logs and
+//! panics, the overlay and the built-in commands only use the default console.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use bevy::prelude::*;
+//! use bevy_console::instance::{ConsoleConfiguration, PrintConsoleLine};
+//! use bevy_console::{AddConsoleCommand, ConsoleCommand, ConsolePlugin, ToggleConsoleKey};
+//! # use clap::Parser;
+//!
+//! #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+//! struct AssetConsole;
+//!
+//! App::new()
+//!     .add_plugins((ConsolePlugin::new(), ConsolePlugin::<AssetConsole>::default()))
+//!     .insert_resource(ConsoleConfiguration::<AssetConsole> {
+//!         keys: vec![ToggleConsoleKey::KeyCode(KeyCode::F2)],
+//!         ..default()
+//!     })
+//!     .add_console_command_for::<AssetConsole, ReloadCommand, _>(reload_command);
+//!
+//! /// Reloads the assets
+//! #[derive(Parser, ConsoleCommand)]
+//! #[command(name = "reload")]
+//! struct ReloadCommand;
+//!
+//! fn reload_command(mut reload: ConsoleCommand<ReloadCommand, AssetConsole>) {
+//!     while let Some(Ok(ReloadCommand)) = reload.take() {
+//!         reload.ok();
+//!     }
+//! }
+//!
+//! fn report_import(mut console_line: EventWriter<PrintConsoleLine<AssetConsole>>) {
+//!     console_line.send(PrintConsoleLine::new("imported level.gltf".into()));
+//! }
+//! ```
+
+use std::any::TypeId;
+use std::fmt::Debug;
+
+use bevy::prelude::*;
+
+pub use crate::completion::CompletionIndex;
+pub use crate::console::{
+    CommandFinished, ConsoleClosed, ConsoleCommandEntered, ConsoleConfiguration, ConsoleInputFocus,
+    ConsoleOpen, ConsoleOpened, PrintConsoleLine, UnknownConsoleCommand,
+};
+
+/// Marker type telling a console apart from the others.
+///
+/// Implemented for every type with the derives below, e.g.
+/// `#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)] struct GameConsole;`.
+pub trait ConsoleMarker:
+    Clone + Copy + Debug + Default + PartialEq + Eq + Send + Sync + 'static
+{
+}
+
+impl<M: Clone + Copy + Debug + Default + PartialEq + Eq + Send + Sync + 'static> ConsoleMarker
+    for M
+{
+}
+
+/// Marker of the console added by [`ConsolePlugin::new`](crate::ConsolePlugin::new), which the
+/// types at the crate root refer to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DefaultConsole;
+
+/// Whether `M` is the marker of the default console.
+pub(crate) fn is_default_console<M: ConsoleMarker>() -> bool {
+    TypeId::of::<M>() == TypeId::of::<DefaultConsole>()
+}
+
+/// Run condition which is true while the console `M` is open.
+pub fn console_open<M: ConsoleMarker>(console_open: Res<ConsoleOpen<M>>) -> bool {
+    console_open.open
+}
+
+/// Run condition which is true while the console `M` is closed.
+pub fn console_closed<M: ConsoleMarker>(console_open: Res<ConsoleOpen<M>>) -> bool {
+    !console_open.open
+}
+
+/// Run condition which is true unless the console `M` is open with its input line focused.
+pub fn not_typing_in_console<M: ConsoleMarker>(
+    console_open: Res<ConsoleOpen<M>>,
+    input_focus: Res<ConsoleInputFocus<M>>,
+) -> bool {
+    !console_open.open || !input_focus.focused
+}
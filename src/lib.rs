@@ -1,80 +1,525 @@
 #![doc = include_str ! ("../README.md")]
 #![deny(missing_docs)]
+// State and helpers only the UI reads are unused without it
+#![cfg_attr(not(feature = "egui"), allow(dead_code))]
 
 use bevy::prelude::*;
 pub use bevy_console_derive::ConsoleCommand;
-use bevy_egui::EguiPlugin;
+#[cfg(feature = "egui")]
+use bevy_egui::{EguiPlugin, EguiSet};
 
-use crate::commands::clear::{clear_command, ClearCommand};
-use crate::commands::exit::{exit_command, ExitCommand};
-use crate::commands::help::{help_command, HelpCommand};
+pub use crate::bind::{ConsoleBindings, ConsoleShortcut, ConsoleShortcuts};
+#[cfg(feature = "egui")]
+pub use crate::console::UiStyle;
 pub use crate::console::{
-    AddConsoleCommand, Command, ConsoleCommand, ConsoleCommandEntered, ConsoleConfiguration,
-    ConsoleOpen, NamedCommand, PrintConsoleLine, ToggleConsoleKey,
+    AddConsoleCommand, ClearConsole, Command, CommandMeta, CommandOrigin, CommandUsage,
+    Confirmation, Console, ConsoleAliases, ConsoleAnchor, ConsoleCommand, ConsoleCommandStats,
+    ConsoleGates, ConsoleLayout, ConsoleOverlay, ConsoleRunner, ConsoleToggleSource,
+    ConsoleWindowTarget, FontChoice, InputAction, InputFilter, NamedCommand, Progress,
+    RegisteredCommand, ResetConsole, TextDirection, ToggleConsoleKey, DEFAULT_TABLE_CELL_WIDTH,
 };
+pub use crate::cvar::{ConsoleVariables, CvarChanged, RegisterCvarResource};
+pub use crate::localization::{ConsoleLocalization, Plural};
+#[cfg(feature = "log_capture")]
+pub use crate::log_capture::{ConsoleLogFilter, ConsoleLogPlugin};
+pub use crate::mirror::ConsoleMirror;
+#[cfg(feature = "remote")]
+pub use crate::remote::RemoteConsolePlugin;
+#[cfg(feature = "egui")]
+pub use crate::theme::ConsoleTheme;
 
-use crate::console::{receive_console_line, ConsoleState};
-use crate::ui::console_ui;
+use crate::bind::run_key_bindings;
+#[cfg(feature = "egui")]
+use crate::completion::update_completion_index;
+use crate::console::{
+    clear_console, pause_time, print_command_timings, receive_console_line, record_command_stats,
+    release_cursor, report_duplicate_commands, request_confirmations, reset_console,
+    send_console_toggles, start_command_timer, update_status_labels, CommandFailed, CommandOwners,
+    CommandParseFailed, CommandQueue, CommandTimer, ConsoleLineReader, ConsoleState,
+    RequestConfirmation, StatusLabels,
+};
+use crate::cvar::{
+    apply_cvar_overrides, cvar_commands, cvar_resource_commands, restore_cvar_overrides,
+    snapshot_cvar_resources,
+};
+use crate::dispatch::run_queued_commands;
+use crate::instance::{is_default_console, ConsoleMarker, DefaultConsole};
+use crate::mirror::{write_mirrors, MirrorFiles};
+use crate::panic_capture::{install_panic_hook, print_captured_panics};
+use crate::script::run_autoexec;
+use crate::stdin::{run_stdin_lines, spawn_stdin_reader, stop_stdin_reader};
+#[cfg(feature = "egui")]
+use crate::ui::{console_overlay_ui, console_ui, scale_console_scroll};
+use std::marker::PhantomData;
 
+#[cfg(feature = "egui")]
+mod ansi;
+mod bind;
+#[cfg(feature = "default_commands")]
 mod commands;
+mod completion;
 mod console;
-mod ui;
+mod cvar;
+mod diff;
+mod dispatch;
+pub mod instance;
+mod localization;
+#[cfg(feature = "log_capture")]
+mod log_capture;
 mod macros;
+mod mirror;
+mod panic_capture;
+#[cfg(feature = "remote")]
+mod remote;
+mod script;
+mod stdin;
+mod table;
+#[cfg(feature = "egui")]
+mod theme;
+#[cfg(feature = "egui")]
+mod ui;
+
+/// Event sent for each command entered in the default console, see
+/// [`instance::ConsoleCommandEntered`].
+pub type ConsoleCommandEntered = console::ConsoleCommandEntered<DefaultConsole>;
+
+/// Event sent when a command of the default console is done, see
+/// [`instance::CommandFinished`].
+pub type CommandFinished = console::CommandFinished<DefaultConsole>;
+
+/// Completion index of the default console, see [`instance::CompletionIndex`].
+pub type CompletionIndex = completion::CompletionIndex<DefaultConsole>;
+
+/// Configuration of the default console, see [`instance::ConsoleConfiguration`].
+pub type ConsoleConfiguration = console::ConsoleConfiguration<DefaultConsole>;
+
+/// Whether the default console is open, see [`instance::ConsoleOpen`].
+pub type ConsoleOpen = console::ConsoleOpen<DefaultConsole>;
+
+/// Whether the input line of the default console has the keyboard focus, see
+/// [`instance::ConsoleInputFocus`].
+pub type ConsoleInputFocus = console::ConsoleInputFocus<DefaultConsole>;
+
+/// Event sent when the default console opens, see [`instance::ConsoleOpened`].
+pub type ConsoleOpened = console::ConsoleOpened<DefaultConsole>;
+
+/// Event sent when the default console closes, see [`instance::ConsoleClosed`].
+pub type ConsoleClosed = console::ConsoleClosed<DefaultConsole>;
+
+/// Event printing a line to the default console, see [`instance::PrintConsoleLine`].
+pub type PrintConsoleLine = console::PrintConsoleLine<DefaultConsole>;
+
+/// Event sent for each unknown command entered in the default console, see
+/// [`instance::UnknownConsoleCommand`].
+pub type UnknownConsoleCommand = console::UnknownConsoleCommand<DefaultConsole>;
+
+/// Console plugin, adding [`ConsoleCorePlugin`] and, with the `egui` feature, [`ConsoleUiPlugin`]
+///
+/// Game input handling can be skipped while the console is used with the run conditions
+/// [`console_open`], [`console_closed`] and [`not_typing_in_console`], e.g.
+/// `.add_systems(Update, move_player.run_if(console_closed))`.
+///
+/// More consoles are added with a marker type, e.g. `ConsolePlugin::<AssetConsole>::default()`,
+/// see the [`instance`] module.
+pub struct ConsolePlugin<M = DefaultConsole> {
+    add_egui_plugin: bool,
+    marker: PhantomData<M>,
+}
+
+impl ConsolePlugin {
+    /// Creates the console plugin, adding [`EguiPlugin`](bevy_egui::EguiPlugin) unless the app
+    /// already has it
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<M> ConsolePlugin<M> {
+    /// Whether to add `EguiPlugin` when the app doesn't have it yet.
+    ///
+    /// Disable it when the app adds and configures `EguiPlugin` itself after this plugin.
+    pub fn with_egui_plugin(mut self, add_egui_plugin: bool) -> Self {
+        self.add_egui_plugin = add_egui_plugin;
+        self
+    }
+}
+
+impl<M> Default for ConsolePlugin<M> {
+    fn default() -> Self {
+        Self {
+            add_egui_plugin: true,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Commands, console variables and the scrollback, without any UI.
+///
+/// Enough for apps which enter commands with [`ConsoleRunner`], the standard input or a remote
+/// console, e.g. dedicated servers. Lines are still kept in the scrollback and mirrored.
+pub struct ConsoleCorePlugin;
+
+/// The commands and scrollback of a console besides the default one.
+struct ConsoleInstancePlugin<M>(PhantomData<M>);
 
-/// Console plugin
-pub struct ConsolePlugin;
+/// Resources and system sets shared by all consoles.
+struct ConsoleSharedPlugin;
+
+/// The console window and overlay, drawn with egui. Adds [`ConsoleCorePlugin`] if it isn't
+/// added yet.
+///
+/// The overlay is only drawn for the default console.
+#[cfg(feature = "egui")]
+pub struct ConsoleUiPlugin<M = DefaultConsole> {
+    add_egui_plugin: bool,
+    marker: PhantomData<M>,
+}
+
+#[cfg(feature = "egui")]
+impl ConsoleUiPlugin {
+    /// Creates the console UI plugin, adding [`EguiPlugin`] unless the app already has it
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "egui")]
+impl<M> ConsoleUiPlugin<M> {
+    /// Whether to add [`EguiPlugin`] when the app doesn't have it yet.
+    ///
+    /// Disable it when the app adds and configures `EguiPlugin` itself after this plugin.
+    pub fn with_egui_plugin(mut self, add_egui_plugin: bool) -> Self {
+        self.add_egui_plugin = add_egui_plugin;
+        self
+    }
+}
+
+#[cfg(feature = "egui")]
+impl<M> Default for ConsoleUiPlugin<M> {
+    fn default() -> Self {
+        Self {
+            add_egui_plugin: true,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// The systems drawing the console window and overlay, in [`ConsoleSet::ConsoleUI`].
+///
+/// Other egui systems can be ordered relative to it, e.g. to draw windows above the console
+/// with `.after(ConsoleUiSet)`.
+#[cfg(feature = "egui")]
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub struct ConsoleUiSet;
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
-/// The SystemSet for console/command related systems
+/// The SystemSet for console/command related systems, in `Update`.
+///
+/// The sets run one after the other in the order below, every frame. Lines printed before
+/// [`ConsoleSet::ConsoleUI`] are added to the scrollback before the input of the frame is echoed,
+/// and lines printed until [`ConsoleSet::ReceiveLines`] right after, so the echo of a command, its
+/// replies and its `[ok]` marker stay together in that order. Systems printing lines outside of
+/// these sets should run before [`ConsoleSet::ConsoleUI`] or before [`ConsoleSet::ReceiveLines`],
+/// otherwise their lines show up the next frame.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_console::{ConsolePlugin, ConsoleSet};
+/// # fn print_score() {}
+/// # fn react_to_commands() {}
+/// App::new().add_plugins(ConsolePlugin::new()).add_systems(
+///     Update,
+///     (
+///         print_score.before(ConsoleSet::ConsoleUI),
+///         react_to_commands.in_set(ConsoleSet::PostCommands),
+///     ),
+/// );
+/// ```
 pub enum ConsoleSet {
-    /// Systems operating the console UI (the input layer)
+    /// Systems reading the input, from the console UI, key bindings, scripts and the like, and
+    /// sending the entered commands (the input layer)
     ConsoleUI,
 
     /// Systems executing console commands (the functionality layer).
-    /// All command handler systems are added to this set
+    /// All command handler systems are added to this set, which only runs on frames where
+    /// commands were entered in any console
     Commands,
 
     /// Systems running after command systems, which depend on the fact commands have executed beforehand (the output layer).
     /// For example a system which makes use of [`PrintConsoleLine`] events should be placed in this set to be able to receive
     /// New lines to print in the same frame
     PostCommands,
+
+    /// Systems adding the lines printed this frame to the scrollback and writing them to the
+    /// mirrors and remote clients (the display layer). Lines printed later in the frame are
+    /// received the next frame
+    ReceiveLines,
+}
+
+/// Checks of each console added for commands entered this frame.
+#[derive(Default, Resource)]
+struct ConsoleInstances {
+    have_commands: Vec<fn(&World) -> bool>,
+}
+
+/// Whether commands were entered in the console `M`.
+fn have_console_commands<M: ConsoleMarker>(world: &World) -> bool {
+    !world
+        .resource::<Events<console::ConsoleCommandEntered<M>>>()
+        .is_empty()
+}
+
+/// Run condition which does not run any command systems if no command was entered in any console
+fn have_commands(world: &World) -> bool {
+    let instances = world.resource::<ConsoleInstances>();
+    instances.have_commands.iter().any(|check| check(world))
 }
 
-/// Run condition which does not run any command systems if no command was entered
-fn have_commands(commands: EventReader<ConsoleCommandEntered>) -> bool {
-    !commands.is_empty()
+/// Run condition which is true while the console is open.
+pub fn console_open(console_open: Res<ConsoleOpen>) -> bool {
+    console_open.open
 }
 
-impl Plugin for ConsolePlugin {
+/// Run condition which is true while the console is closed.
+pub fn console_closed(console_open: Res<ConsoleOpen>) -> bool {
+    !console_open.open
+}
+
+/// Run condition which is true unless the console is open with its input line focused.
+///
+/// Unlike [`console_closed`], game input keeps working while the console is left open after
+/// clicking outside of it.
+pub fn not_typing_in_console(
+    console_open: Res<ConsoleOpen>,
+    input_focus: Res<ConsoleInputFocus>,
+) -> bool {
+    !console_open.open || !input_focus.focused
+}
+
+impl<M: ConsoleMarker> Plugin for ConsolePlugin<M> {
     fn build(&self, app: &mut App) {
-        app.init_resource::<ConsoleConfiguration>()
-            .init_resource::<ConsoleState>()
-            .init_resource::<ConsoleOpen>()
-            .add_event::<ConsoleCommandEntered>()
-            .add_event::<PrintConsoleLine>()
-            .add_console_command::<ClearCommand, _>(clear_command)
-            .add_console_command::<ExitCommand, _>(exit_command)
-            .add_console_command::<HelpCommand, _>(help_command)
+        add_core_plugin::<M>(app);
+        #[cfg(feature = "egui")]
+        app.add_plugins(ConsoleUiPlugin::<M>::default().with_egui_plugin(self.add_egui_plugin));
+    }
+}
+
+/// Adds the commands and scrollback of the console `M` unless they are added already.
+fn add_core_plugin<M: ConsoleMarker>(app: &mut App) {
+    if is_default_console::<M>() {
+        if !app.is_plugin_added::<ConsoleCorePlugin>() {
+            app.add_plugins(ConsoleCorePlugin);
+        }
+    } else if !app.is_plugin_added::<ConsoleInstancePlugin<M>>() {
+        app.add_plugins(ConsoleInstancePlugin::<M>(PhantomData));
+    }
+}
+
+/// Adds the resources and events of the console `M`.
+fn init_console<M: ConsoleMarker>(app: &mut App) {
+    if !app.is_plugin_added::<ConsoleSharedPlugin>() {
+        app.add_plugins(ConsoleSharedPlugin);
+    }
+    app.world
+        .resource_mut::<ConsoleInstances>()
+        .have_commands
+        .push(have_console_commands::<M>);
+
+    app.init_resource::<console::ConsoleConfiguration<M>>()
+        .init_resource::<ConsoleState<M>>()
+        .init_resource::<console::ConsoleOpen<M>>()
+        .init_resource::<console::ConsoleInputFocus<M>>()
+        .init_resource::<CommandQueue<M>>()
+        .init_resource::<CommandOwners<M>>()
+        .init_resource::<ConsoleLineReader<M>>()
+        .init_resource::<completion::CompletionIndex<M>>()
+        .add_event::<console::ConsoleCommandEntered<M>>()
+        .add_event::<console::UnknownConsoleCommand<M>>()
+        .add_event::<RequestConfirmation<M>>()
+        .add_event::<CommandParseFailed<M>>()
+        .add_event::<CommandFailed<M>>()
+        .add_event::<console::CommandFinished<M>>()
+        .add_event::<console::PrintConsoleLine<M>>()
+        .add_event::<console::ConsoleOpened<M>>()
+        .add_event::<console::ConsoleClosed<M>>();
+}
+
+impl Plugin for ConsoleSharedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsoleInstances>()
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<MirrorFiles>()
+            .init_resource::<ConsoleShortcuts>()
+            .add_event::<CvarChanged>()
+            .configure_sets(
+                Update,
+                (
+                    ConsoleSet::Commands
+                        .after(ConsoleSet::ConsoleUI)
+                        .run_if(have_commands),
+                    ConsoleSet::PostCommands.after(ConsoleSet::Commands),
+                    ConsoleSet::ReceiveLines.after(ConsoleSet::PostCommands),
+                ),
+            )
             .add_systems(
                 Update,
+                snapshot_cvar_resources.before(ConsoleSet::ConsoleUI),
+            );
+    }
+}
+
+impl<M: ConsoleMarker> Plugin for ConsoleInstancePlugin<M> {
+    fn build(&self, app: &mut App) {
+        init_console::<M>(app);
+        app.add_systems(Startup, report_duplicate_commands::<M>)
+            .add_systems(
+                Update,
+                (
+                    (receive_console_line::<M>, run_queued_commands::<M>)
+                        .chain()
+                        .in_set(ConsoleSet::ConsoleUI),
+                    (send_console_toggles::<M>, request_confirmations::<M>)
+                        .chain()
+                        .in_set(ConsoleSet::PostCommands),
+                    apply_cvar_overrides::<M>
+                        .after(ConsoleSet::ConsoleUI)
+                        .before(ConsoleSet::Commands),
+                    restore_cvar_overrides::<M>
+                        .after(ConsoleSet::PostCommands)
+                        .before(ConsoleSet::ReceiveLines),
+                    (receive_console_line::<M>, write_mirrors::<M>)
+                        .chain()
+                        .in_set(ConsoleSet::ReceiveLines),
+                ),
+            );
+    }
+}
+
+impl Plugin for ConsoleCorePlugin {
+    fn build(&self, app: &mut App) {
+        init_console::<DefaultConsole>(app);
+        app.init_resource::<ConsoleOverlay>()
+            .init_resource::<ConsoleBindings>()
+            .init_resource::<CommandTimer>()
+            .init_resource::<StatusLabels>()
+            .init_resource::<ConsoleCommandStats>()
+            .add_event::<ResetConsole>()
+            .add_event::<ClearConsole>()
+            .add_systems(
+                Startup,
                 (
-                    console_ui.in_set(ConsoleSet::ConsoleUI),
-                    receive_console_line.in_set(ConsoleSet::PostCommands),
+                    report_duplicate_commands::<DefaultConsole>,
+                    install_panic_hook,
+                    spawn_stdin_reader,
                 ),
             )
-            .configure_sets(
+            .add_systems(Last, stop_stdin_reader)
+            .add_systems(PostStartup, run_autoexec)
+            .add_systems(
                 Update,
                 (
-                    ConsoleSet::Commands
+                    (
+                        // Lines printed earlier in the frame go before the echo of the input
+                        receive_console_line::<DefaultConsole>,
+                        print_captured_panics,
+                        update_status_labels,
+                        run_key_bindings,
+                        run_stdin_lines,
+                        run_queued_commands::<DefaultConsole>,
+                        start_command_timer,
+                    )
+                        .chain()
+                        .in_set(ConsoleSet::ConsoleUI),
+                    // Variables entered for a command are set while its systems run
+                    apply_cvar_overrides::<DefaultConsole>
                         .after(ConsoleSet::ConsoleUI)
-                        .run_if(have_commands),
-                    ConsoleSet::PostCommands.after(ConsoleSet::Commands),
+                        .before(ConsoleSet::Commands),
+                    (cvar_commands, cvar_resource_commands).in_set(ConsoleSet::Commands),
+                    (
+                        send_console_toggles::<DefaultConsole>,
+                        release_cursor,
+                        pause_time,
+                        print_command_timings,
+                        record_command_stats,
+                        request_confirmations::<DefaultConsole>,
+                        reset_console,
+                        clear_console,
+                    )
+                        .chain()
+                        .in_set(ConsoleSet::PostCommands),
+                    restore_cvar_overrides::<DefaultConsole>
+                        .after(ConsoleSet::PostCommands)
+                        .before(ConsoleSet::ReceiveLines),
+                    (
+                        receive_console_line::<DefaultConsole>,
+                        write_mirrors::<DefaultConsole>,
+                    )
+                        .chain()
+                        .in_set(ConsoleSet::ReceiveLines),
                 ),
             );
 
+        #[cfg(feature = "default_commands")]
+        commands::add_default_commands(app);
+
+        #[cfg(feature = "log_capture")]
+        app.init_resource::<ConsoleLogFilter>().add_systems(
+            Update,
+            (
+                (
+                    log_capture::reset_log_filter,
+                    log_capture::update_log_filter,
+                )
+                    .chain()
+                    .in_set(ConsoleSet::PostCommands),
+                log_capture::print_captured_logs.before(ConsoleSet::ConsoleUI),
+            ),
+        );
+    }
+}
+
+#[cfg(feature = "egui")]
+impl<M: ConsoleMarker> Plugin for ConsoleUiPlugin<M> {
+    fn build(&self, app: &mut App) {
+        add_core_plugin::<M>(app);
+
+        app.add_systems(
+            PreUpdate,
+            scale_console_scroll::<M>
+                .run_if(instance::console_open::<M>)
+                .after(EguiSet::ProcessInput)
+                .before(EguiSet::BeginFrame),
+        );
+        if is_default_console::<M>() {
+            app.add_systems(
+                Update,
+                // Input is read after queued commands ran, and before commands are timed
+                (
+                    update_completion_index::<DefaultConsole>,
+                    console_ui::<DefaultConsole>,
+                    console_overlay_ui,
+                )
+                    .chain()
+                    .after(run_queued_commands::<DefaultConsole>)
+                    .before(start_command_timer)
+                    .in_set(ConsoleUiSet),
+            );
+        } else {
+            app.add_systems(
+                Update,
+                (update_completion_index::<M>, console_ui::<M>)
+                    .chain()
+                    .after(run_queued_commands::<M>)
+                    .in_set(ConsoleUiSet),
+            );
+        }
+        app.configure_sets(Update, ConsoleUiSet.in_set(ConsoleSet::ConsoleUI));
+
         // Don't initialize an egui plugin if one already exists.
         // This can happen if another plugin is using egui and was installed before us.
-        if !app.is_plugin_added::<EguiPlugin>() {
+        if self.add_egui_plugin && !app.is_plugin_added::<EguiPlugin>() {
             app.add_plugins(EguiPlugin);
         }
     }
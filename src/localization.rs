@@ -0,0 +1,470 @@
+use std::fmt::Display;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Messages printed by the console and its built-in commands, to translate them.
+///
+/// Names in braces like `{name}` are replaced with the values they stand for. Help texts of
+/// commands come from their clap definitions instead. Changes take effect on the next frame.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct ConsoleLocalization {
+    /// Printed by [`ConsoleCommand::ok`](crate::ConsoleCommand::ok)
+    pub ok: String,
+    /// Printed by [`ConsoleCommand::failed`](crate::ConsoleCommand::failed)
+    pub failed: String,
+    /// Entered command which isn't registered, `{name}`
+    pub unknown_command: String,
+    /// Follows an unknown command error with similar command names, `{error}` and `{names}`
+    pub did_you_mean: String,
+    /// Entered name which several commands start with, `{name}` and `{names}`
+    pub ambiguous_command: String,
+    /// Entered command whose gate is closed, `{name}` and `{gate}`
+    pub unavailable_command: String,
+    /// Joins the last two names of a list, e.g. in `'same' or 'save'`
+    pub or: String,
+    /// Entered line with a quote which isn't closed
+    pub unterminated_quote: String,
+    /// Entered line ending with a backslash
+    pub trailing_backslash: String,
+    /// Commands waiting after a `wait` which were cancelled by a new line, `{count}`
+    pub cancelled_waiting_commands: Plural,
+    /// Line of a strict script which failed, stopping the script, `{path}` and `{line}`
+    pub script_stopped: String,
+    /// Script which can't be read, `{path}` and `{error}`
+    pub script_unreadable: String,
+    /// Script run by more scripts nested in each other than allowed, `{path}` and `{depth}`
+    pub script_nested_too_deep: String,
+    /// Script run on the web, which has no file system to read it from, `{path}`
+    pub script_on_web: String,
+    /// Entered alias which is deprecated, `{name}` and `{command}`
+    pub deprecated_alias: String,
+    /// Asks to confirm a command registered with
+    /// [`CommandMeta::confirm`](crate::CommandMeta::confirm), `{command}`
+    pub confirm_command: String,
+    /// Follows confirmation prompts
+    pub confirm_answers: String,
+    /// Answers confirming a command, compared ignoring case
+    pub confirm_words: Vec<String>,
+    /// Command which wasn't confirmed, `{command}`
+    pub confirmation_cancelled: String,
+    /// Command which wasn't confirmed in time, `{command}`
+    pub confirmation_timed_out: String,
+    /// Command names added more than once, `{names}`
+    pub duplicate_commands: String,
+    /// Entry of [`ConsoleLocalization::duplicate_commands`], `{name}` and `{count}`
+    pub duplicate_command: String,
+    /// Time taken by a single command, `{millis}`
+    pub command_timing: String,
+    /// Time taken by several commands entered in the same frame, `{count}` and `{millis}`
+    pub commands_timing: String,
+    /// Header of the commands listed by `help`
+    pub help_commands: String,
+    /// Header of the console variables listed by `help`
+    pub help_console_variables: String,
+    /// Header of the deprecated aliases listed by `help`
+    pub help_deprecated_aliases: String,
+    /// Console variable described by `help`, `{value}`
+    pub help_console_variable: String,
+    /// Resource whose fields are console variables, listed by `help`, `{prefix}` and `{type}`
+    pub help_resource_fields: String,
+    /// Command `help` was asked about which doesn't exist, `{name}`
+    pub help_no_such_command: String,
+    /// Subcommand `help` was asked about which doesn't exist, `{name}` and `{command}`
+    pub help_no_such_subcommand: String,
+    /// Asks to confirm the `exit` command entered without `--force`, `{seconds}`
+    pub exit_confirm: String,
+    /// Pattern of `find` which isn't a valid regular expression, `{pattern}` and `{error}`
+    pub find_invalid_pattern: String,
+    /// Pattern of `find` which nothing matches, `{pattern}`
+    pub find_no_match: String,
+    /// Variable reference opened with `${` which isn't closed, `{token}`
+    pub unclosed_variable: String,
+    /// Variable referenced in a command line which doesn't exist, `{name}` and `{token}`
+    pub unknown_variable: String,
+    /// Console variable entered with more than one value, `{name}`
+    pub variable_usage: String,
+    /// Console variable described by `help`, after its value, `{name}`
+    pub help_variable_usage: String,
+    /// Follows the echo of a command entered with console variables set for it, `{variables}`
+    pub temporary_variables: String,
+    /// Console variables entered to be set for a command without one, `{variables}`
+    pub variables_without_command: String,
+    /// Printed by `stats` before any command was entered
+    pub stats_empty: String,
+    /// Column headers of the table printed by `stats`
+    pub stats_headers: [String; 4],
+    /// Time since a command was last used, printed by `stats`, `{seconds}`
+    pub stats_last_used: String,
+    /// Printed by `timing` once timings are turned on
+    pub timings_on: String,
+    /// Printed by `timing` once timings are turned off
+    pub timings_off: String,
+    /// History entry `history` was asked to run which doesn't exist, `{entry}`
+    pub history_no_entry: String,
+    /// Key without a binding, `{key}`
+    pub key_not_bound: String,
+    /// Printed by `bindlist` when no key is bound
+    pub no_bindings: String,
+    /// `bind` of a key a console shortcut works with while the console is closed, `{key}`,
+    /// `{action}`
+    pub bind_conflict: String,
+    /// Column headers of the table printed by `shortcuts`
+    pub shortcuts_headers: [String; 3],
+    /// Source of console shortcuts in the table printed by `shortcuts`
+    pub shortcut_console: String,
+    /// Key given by its scan code in the table printed by `shortcuts`, `{code}`
+    pub shortcut_scan_code: String,
+    /// Action of the keys toggling the console, listed by `shortcuts`
+    pub shortcut_toggle: String,
+    /// Action of [`ConsoleShortcuts::submit`](crate::ConsoleShortcuts::submit)
+    pub shortcut_submit: String,
+    /// Action of [`ConsoleShortcuts::complete`](crate::ConsoleShortcuts::complete)
+    pub shortcut_complete: String,
+    /// Action of [`ConsoleShortcuts::hide_suggestions`](crate::ConsoleShortcuts::hide_suggestions)
+    pub shortcut_hide_suggestions: String,
+    /// Action of [`ConsoleShortcuts::previous`](crate::ConsoleShortcuts::previous) and
+    /// [`ConsoleShortcuts::next`](crate::ConsoleShortcuts::next)
+    pub shortcut_browse: String,
+    /// Action of [`ConsoleShortcuts::accept_hint`](crate::ConsoleShortcuts::accept_hint)
+    pub shortcut_accept_hint: String,
+    /// Action of [`ConsoleShortcuts::page_up`](crate::ConsoleShortcuts::page_up) and
+    /// [`ConsoleShortcuts::page_down`](crate::ConsoleShortcuts::page_down)
+    pub shortcut_scroll: String,
+    /// Action of [`ConsoleShortcuts::clear`](crate::ConsoleShortcuts::clear)
+    pub shortcut_clear: String,
+    /// Action of Ctrl and a digit, recalling a history entry
+    pub shortcut_recall: String,
+    /// Source of key bindings in the table printed by `shortcuts`
+    pub shortcut_bind: String,
+    /// Source of key bindings clashing with a console shortcut in the table printed by `shortcuts`
+    pub shortcut_conflict: String,
+    /// Level of the captured log events, printed by `loglevel`, `{level}`
+    pub log_level: String,
+    /// Level set by `loglevel`, `{level}`
+    pub log_level_set: String,
+    /// Level of a target set by `loglevel`, `{target}` and `{level}`
+    pub log_target_set: String,
+    /// Target given to `loglevel` without its level, `{target}`
+    pub log_target_without_level: String,
+    /// Level given to `loglevel` after directives which aren't a single target
+    pub log_level_without_target: String,
+    /// Printed by `fps` when frame times aren't measured
+    pub fps_unmeasured: String,
+    /// Frame rate printed by `fps`, `{summary}`
+    pub fps_rate: String,
+    /// Frame time printed by `fps`, `{summary}`
+    pub fps_frame_time: String,
+    /// Number of entities printed by `fps`, `{count}`
+    pub fps_entities: String,
+    /// Value of a diagnostic printed by `fps`, `{value}`, `{average}`, `{min}` and `{max}`
+    pub fps_summary: String,
+    /// Line updated by `fps watch`, `{fps}` and `{millis}`
+    pub fps_watch: String,
+    /// Follows [`ConsoleLocalization::fps_watch`] with the number of entities, `{count}`
+    pub fps_watch_entities: Plural,
+    /// Scrollback written to a file by `dump`, `{count}` and `{path}`
+    pub dump_written: Plural,
+    /// File `dump` can't write the scrollback to, `{path}` and `{error}`
+    pub dump_unwritable: String,
+    /// Printed by `fps stop` when `fps watch` isn't running
+    pub fps_not_watching: String,
+    /// Printed by `version` when [`ConsoleConfiguration::app_version`] isn't set
+    ///
+    /// [`ConsoleConfiguration::app_version`]: crate::ConsoleConfiguration::app_version
+    pub unknown_version: String,
+    /// Captured log events which were dropped because the console was behind, `{count}`
+    pub dropped_logs: Plural,
+    /// Panics which were dropped because the console was behind, `{count}`
+    pub dropped_panics: Plural,
+    /// Font size printed by `fontsize`, `{size}`
+    pub font_size: String,
+    /// Font size set by `fontsize`, `{size}`
+    pub font_size_set: String,
+    /// Font size given to `fontsize` which isn't a positive number
+    pub font_size_invalid: String,
+    /// Alias `alias` was asked to define with the name of a command, `{name}`
+    pub alias_is_command: String,
+    /// Alias which doesn't exist, `{name}`
+    pub no_alias: String,
+    /// Printed by `alias` when no alias is defined
+    pub no_aliases: String,
+    /// Printed by `autoexec` when [`ConsoleConfiguration::autoexec`] isn't set
+    ///
+    /// [`ConsoleConfiguration::autoexec`]: crate::ConsoleConfiguration::autoexec
+    pub no_autoexec: String,
+    /// Configuration `console_config dump` can't write, `{error}`
+    pub config_unwritable: String,
+    /// Lines copied to the clipboard by `copy`, `{count}`
+    pub copied_lines: Plural,
+    /// Unchanged lines collapsed in a diff, `{count}`
+    pub unchanged_lines: Plural,
+    /// Title of the console window when [`ConsoleConfiguration::title`] isn't set
+    ///
+    /// [`ConsoleConfiguration::title`]: crate::ConsoleConfiguration::title
+    pub window_title: String,
+    /// Context menu entry copying the scrollback
+    pub copy_scrollback: String,
+    /// Button jumping to lines printed while scrolled up, `{count}`
+    pub new_lines: Plural,
+    /// Number of commands shown in the status bar, `{count}`
+    pub status_commands: Plural,
+    /// Scrollback length shown in the status bar, `{lines}` and `{max}`
+    pub status_lines: String,
+    /// History entry shown in the status bar while browsing the history, `{entry}` and `{count}`
+    pub status_history: String,
+    /// Frame rate shown in the status bar, `{fps}`
+    pub status_fps: String,
+    /// `wait` entered with an argument which isn't a duration
+    pub wait_usage: String,
+    /// Key name which isn't known, `{name}` and `{keys}`
+    pub unknown_key: String,
+    /// Error of a console variable, `{error}`
+    pub variable_error: String,
+    /// Console variable which doesn't exist, `{name}`
+    pub no_variable: String,
+    /// Console variable toggled which isn't a bool, `{name}`
+    pub variable_not_bool: String,
+    /// Console variable changed by an amount which isn't a number, `{name}`
+    pub variable_not_number: String,
+    /// Value which can't be parsed for a console variable, `{value}`, `{name}`, `{type}` and
+    /// `{error}`
+    pub invalid_variable_value: String,
+    /// Resource of console variable fields which doesn't exist, `{type}`
+    pub no_resource: String,
+    /// Field of a resource which doesn't exist, `{path}`, `{type}` and `{error}`
+    pub no_resource_field: String,
+    /// Resource set as a whole instead of one of its fields
+    pub resource_not_field: String,
+    /// Value which can't be parsed for a resource field, `{value}`, `{type}` and `{error}`
+    pub invalid_field_value: String,
+    /// Resource field of a type which can't be parsed, `{type}`
+    pub unsupported_field: String,
+}
+
+impl Default for ConsoleLocalization {
+    fn default() -> Self {
+        Self {
+            ok: "[ok]".to_owned(),
+            failed: "[failed]".to_owned(),
+            unknown_command: "error: unknown command '{name}'".to_owned(),
+            did_you_mean: "{error}, did you mean {names}?".to_owned(),
+            ambiguous_command: "error: ambiguous command '{name}', could be {names}".to_owned(),
+            unavailable_command: "error: command '{name}' is unavailable, it requires {gate}"
+                .to_owned(),
+            or: "or".to_owned(),
+            unterminated_quote: "parse error: unterminated quote".to_owned(),
+            trailing_backslash: "parse error: trailing backslash".to_owned(),
+            cancelled_waiting_commands: Plural::new(
+                "Cancelled {count} waiting command",
+                "Cancelled {count} waiting commands",
+            ),
+            script_stopped: "error: {path}:{line} failed, stopping the script".to_owned(),
+            script_unreadable: "error: can't read {path}: {error}".to_owned(),
+            script_nested_too_deep: "error: can't run {path}, scripts are nested more than \
+                                     {depth} deep"
+                .to_owned(),
+            script_on_web: "error: scripts can't be run on the web, there is no file system to \
+                            read {path} from"
+                .to_owned(),
+            deprecated_alias: "'{name}' is deprecated, use '{command}'".to_owned(),
+            confirm_command: "Run `{command}`?".to_owned(),
+            confirm_answers: "[y/N]".to_owned(),
+            confirm_words: vec!["y".to_owned(), "yes".to_owned()],
+            confirmation_cancelled: "`{command}` was cancelled".to_owned(),
+            confirmation_timed_out: "`{command}` timed out and was cancelled".to_owned(),
+            duplicate_commands:
+                "console commands added more than once, only the last one added runs: {names}"
+                    .to_owned(),
+            duplicate_command: "'{name}' ({count} times)".to_owned(),
+            command_timing: "(took {millis} ms)".to_owned(),
+            commands_timing: "({count} commands took {millis} ms)".to_owned(),
+            help_commands: "Available commands:".to_owned(),
+            help_console_variables: "Console variables:".to_owned(),
+            help_deprecated_aliases: "Deprecated aliases:".to_owned(),
+            help_console_variable: "Console variable, currently {value}".to_owned(),
+            help_resource_fields: "fields of {type}".to_owned(),
+            help_no_such_command: "No such command '{name}', try `help`".to_owned(),
+            help_no_such_subcommand: "No such subcommand '{name}', try `help {command}`".to_owned(),
+            exit_confirm: "Exit the app? Type `exit --force` or press Enter again within \
+                           {seconds} seconds"
+                .to_owned(),
+            find_invalid_pattern: "Invalid pattern '{pattern}': {error}".to_owned(),
+            find_no_match: "Nothing matches '{pattern}'".to_owned(),
+            unclosed_variable: "error: missing '}' after '${' in '{token}'".to_owned(),
+            unknown_variable: "error: unknown console variable '{name}' in '{token}'".to_owned(),
+            variable_usage: "error: usage: {name} [value]".to_owned(),
+            help_variable_usage: "Usage: {name} [value]".to_owned(),
+            temporary_variables: "{variables} until the command finishes".to_owned(),
+            variables_without_command: "error: no command to run with {variables}, leave out \
+                                        the '=' to set a variable"
+                .to_owned(),
+            stats_empty: "No commands entered yet".to_owned(),
+            stats_headers: [
+                "command".to_owned(),
+                "runs".to_owned(),
+                "invalid".to_owned(),
+                "last used".to_owned(),
+            ],
+            stats_last_used: "{seconds}s ago".to_owned(),
+            timings_on: "Command timings on".to_owned(),
+            timings_off: "Command timings off".to_owned(),
+            history_no_entry: "No history entry '{entry}'".to_owned(),
+            key_not_bound: "{key} is not bound".to_owned(),
+            no_bindings: "No keys bound".to_owned(),
+            bind_conflict: "{key} is the console shortcut to {action}, add --force to bind it \
+                            anyway"
+                .to_owned(),
+            shortcuts_headers: ["keys".to_owned(), "action".to_owned(), "source".to_owned()],
+            shortcut_console: "console".to_owned(),
+            shortcut_scan_code: "scan code {code}".to_owned(),
+            shortcut_toggle: "open or close the console".to_owned(),
+            shortcut_submit: "run the input line or complete the suggestion".to_owned(),
+            shortcut_complete: "complete the selected suggestion".to_owned(),
+            shortcut_hide_suggestions: "hide the suggestions".to_owned(),
+            shortcut_browse: "select a suggestion or browse the history".to_owned(),
+            shortcut_accept_hint: "accept the completion hint".to_owned(),
+            shortcut_scroll: "scroll by a page".to_owned(),
+            shortcut_clear: "clear the scrollback".to_owned(),
+            shortcut_recall: "recall the n-th most recent history entry".to_owned(),
+            shortcut_bind: "bind".to_owned(),
+            shortcut_conflict: "bind, clashes with the console".to_owned(),
+            log_level: "Log level is {level}".to_owned(),
+            log_level_set: "Log level set to {level}".to_owned(),
+            log_target_set: "{target} set to {level}".to_owned(),
+            log_target_without_level: "missing the level of '{target}'".to_owned(),
+            log_level_without_target: "only a target is followed by a level".to_owned(),
+            fps_unmeasured:
+                "Frame times aren't measured, add `FrameTimeDiagnosticsPlugin` to the app"
+                    .to_owned(),
+            fps_rate: "FPS:        {summary}".to_owned(),
+            fps_frame_time: "Frame time: {summary}".to_owned(),
+            fps_entities: "Entities:   {count}".to_owned(),
+            fps_summary: "{value} (avg {average}, min {min}, max {max})".to_owned(),
+            fps_watch: "{fps} FPS, {millis} ms".to_owned(),
+            fps_watch_entities: Plural::new(", {count} entity", ", {count} entities"),
+            fps_not_watching: "`fps watch` isn't running".to_owned(),
+            dump_written: Plural::new(
+                "Wrote {count} line to {path}",
+                "Wrote {count} lines to {path}",
+            ),
+            dump_unwritable: "error: can't write {path}: {error}".to_owned(),
+            unknown_version: "unknown".to_owned(),
+            dropped_logs: Plural::new(
+                "{count} log event was dropped",
+                "{count} log events were dropped",
+            ),
+            dropped_panics: Plural::new(
+                "{count} more panic was dropped",
+                "{count} more panics were dropped",
+            ),
+            font_size: "Font size is {size}".to_owned(),
+            font_size_set: "Font size set to {size}".to_owned(),
+            font_size_invalid: "Font size must be a positive number".to_owned(),
+            alias_is_command: "'{name}' is a command and can't be an alias".to_owned(),
+            no_alias: "No alias named '{name}'".to_owned(),
+            no_aliases: "No aliases defined".to_owned(),
+            no_autoexec: "No autoexec script configured".to_owned(),
+            config_unwritable: "The configuration can't be written: {error}".to_owned(),
+            copied_lines: Plural::new(
+                "Copied {count} line to the clipboard",
+                "Copied {count} lines to the clipboard",
+            ),
+            unchanged_lines: Plural::new("… {count} unchanged line", "… {count} unchanged lines"),
+            window_title: "Console".to_owned(),
+            copy_scrollback: "Copy scrollback".to_owned(),
+            new_lines: Plural::new("{count} new line ↓", "{count} new lines ↓"),
+            status_commands: Plural::new("{count} command", "{count} commands"),
+            status_lines: "{lines}/{max} lines".to_owned(),
+            status_history: "history {entry}/{count}".to_owned(),
+            status_fps: "{fps} FPS".to_owned(),
+            wait_usage: "error: usage: wait [frames | <seconds>s | <milliseconds>ms]".to_owned(),
+            unknown_key: "unknown key '{name}', valid keys are: {keys}".to_owned(),
+            variable_error: "error: {error}".to_owned(),
+            no_variable: "no console variable named '{name}'".to_owned(),
+            variable_not_bool: "'{name}' is not a bool".to_owned(),
+            variable_not_number: "'{name}' is not a number".to_owned(),
+            invalid_variable_value: "invalid value '{value}' for '{name}' of type {type}: {error}"
+                .to_owned(),
+            no_resource: "resource {type} doesn't exist".to_owned(),
+            no_resource_field: "no field '{path}' in {type}: {error}".to_owned(),
+            resource_not_field: "only fields of a resource can be set".to_owned(),
+            invalid_field_value: "invalid value '{value}' of type {type}: {error}".to_owned(),
+            unsupported_field: "fields of type {type} can't be set from the console".to_owned(),
+        }
+    }
+}
+
+impl ConsoleLocalization {
+    /// Whether `answer` confirms a command.
+    pub(crate) fn confirms(&self, answer: &str) -> bool {
+        let answer = answer.trim();
+        self.confirm_words
+            .iter()
+            .any(|word| word.to_lowercase() == answer.to_lowercase())
+    }
+}
+
+/// Message in one form for a `{count}` of one and another for any other count.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Plural {
+    /// Form for a count of one
+    pub one: String,
+    /// Form for any other count
+    pub other: String,
+}
+
+impl Plural {
+    /// Creates a message from its forms for one and for any other count.
+    pub fn new(one: impl Into<String>, other: impl Into<String>) -> Self {
+        Self {
+            one: one.into(),
+            other: other.into(),
+        }
+    }
+
+    /// Picks the form for `count` and replaces its `{count}` and other placeholders.
+    pub(crate) fn fill(&self, count: usize, values: &[(&str, &dyn Display)]) -> String {
+        let template = if count == 1 { &self.one } else { &self.other };
+        fill(&fill(template, &[("count", &count)]), values)
+    }
+}
+
+/// Replaces the `{name}` placeholders of `template` with their values.
+pub(crate) fn fill(template: &str, values: &[(&str, &dyn Display)]) -> String {
+    values
+        .iter()
+        .fold(template.to_owned(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill() {
+        let localization = ConsoleLocalization::default();
+        assert_eq!(
+            fill(
+                &localization.unavailable_command,
+                &[("name", &"noclip"), ("gate", &"cheats")]
+            ),
+            "error: command 'noclip' is unavailable, it requires cheats"
+        );
+        assert_eq!(fill("{count} of {count}", &[("count", &2)]), "2 of 2");
+        assert_eq!(
+            localization.copied_lines.fill(1, &[]),
+            "Copied 1 line to the clipboard"
+        );
+        assert_eq!(
+            localization.dump_written.fill(3, &[("path", &"log.txt")]),
+            "Wrote 3 lines to log.txt"
+        );
+        assert!(localization.confirms(" YES "));
+        assert!(!localization.confirms("no"));
+    }
+}
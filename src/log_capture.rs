@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex, RwLock};
+
+use bevy::prelude::*;
+use bevy::utils::tracing::field::{Field, Visit};
+use bevy::utils::tracing::level_filters::LevelFilter;
+use bevy::utils::tracing::{self, Level, Subscriber};
+use clap::builder::styling::AnsiColor;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+use crate::console::colored;
+use crate::{ConsoleConfiguration, PrintConsoleLine, ResetConsole};
+
+/// Maximum number of log events waiting to be printed, later ones are dropped
+const LOG_CAPACITY: usize = 1024;
+
+/// Sets up logging like bevy's [`LogPlugin`], and also prints log events to the console.
+///
+/// Formatted events are written to stderr, filtered by the `RUST_LOG` environment variable or
+/// else by [`ConsoleLogPlugin::level`] and [`ConsoleLogPlugin::filter`]. Events are printed in
+/// the console from the next frame on, as filtered by the [`ConsoleLogFilter`] resource after
+/// the filter of this plugin. It replaces the [`LogPlugin`] of the [`DefaultPlugins`], which
+/// must be disabled since only one of them can set up logging.
+///
+/// # Example
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// use bevy::log::LogPlugin;
+/// use bevy_console::{ConsoleLogPlugin, ConsolePlugin};
+///
+/// App::new().add_plugins((
+///     DefaultPlugins.build().disable::<LogPlugin>(),
+///     ConsoleLogPlugin::default(),
+///     ConsolePlugin::new(),
+/// ));
+/// ```
+///
+/// [`LogPlugin`]: bevy::log::LogPlugin
+pub struct ConsoleLogPlugin {
+    /// Filters logs using the [`EnvFilter`] format, like [`LogPlugin::filter`]
+    ///
+    /// [`LogPlugin::filter`]: bevy::log::LogPlugin::filter
+    pub filter: String,
+    /// Filters out logs that are "less than" the given level, like [`LogPlugin::level`]
+    ///
+    /// [`LogPlugin::level`]: bevy::log::LogPlugin::level
+    pub level: Level,
+}
+
+impl Default for ConsoleLogPlugin {
+    fn default() -> Self {
+        Self {
+            filter: "wgpu=error,naga=warn".to_owned(),
+            level: Level::INFO,
+        }
+    }
+}
+
+impl Plugin for ConsoleLogPlugin {
+    fn build(&self, app: &mut App) {
+        let (layer, captured) = capture_logs();
+        app.insert_resource(captured);
+
+        let default_filter = format!("{},{}", self.level, self.filter);
+        let filter = EnvFilter::try_from_default_env()
+            .or_else(|_| EnvFilter::try_new(&default_filter))
+            .unwrap();
+        let fmt = tracing_subscriber::fmt::Layer::default().with_writer(std::io::stderr);
+        let subscriber = Registry::default().with(filter).with(fmt).with(layer);
+        if subscriber.try_init().is_err() {
+            warn!("Could not set up logging for the console, was LogPlugin added too?");
+        }
+    }
+}
+
+/// Creates the layer sending log events to the console, with the resource they're printed from.
+fn capture_logs() -> (ConsoleLogLayer, CapturedLogs) {
+    let (sender, receiver) = sync_channel(LOG_CAPACITY);
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let filter = Arc::new(RwLock::new(ConsoleLogFilter::default()));
+    let layer = ConsoleLogLayer {
+        sender,
+        dropped: dropped.clone(),
+        filter: filter.clone(),
+    };
+    let captured = CapturedLogs {
+        receiver: Mutex::new(receiver),
+        dropped,
+        filter,
+    };
+    (layer, captured)
+}
+
+/// Events captured by the layer of [`ConsoleLogPlugin`], until the console prints them.
+#[derive(Resource)]
+pub(crate) struct CapturedLogs {
+    receiver: Mutex<Receiver<CapturedLog>>,
+    /// Events which didn't fit in the channel since the last frame
+    dropped: Arc<AtomicUsize>,
+    /// Copy of the [`ConsoleLogFilter`] resource, which the layer can't access
+    filter: Arc<RwLock<ConsoleLogFilter>>,
+}
+
+/// Which log events captured with [`ConsoleLogPlugin`] are printed to the console.
+///
+/// Changes take effect for events logged from the next frame on.
+#[derive(Clone, Debug, PartialEq, Resource)]
+pub struct ConsoleLogFilter {
+    /// Most verbose level printed
+    pub level: LevelFilter,
+    /// Most verbose level printed by targets starting with a module path like `my_game::ai`,
+    /// overriding [`ConsoleLogFilter::level`]. The longest matching path is used
+    pub modules: BTreeMap<String, LevelFilter>,
+}
+
+impl Default for ConsoleLogFilter {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::INFO,
+            modules: BTreeMap::new(),
+        }
+    }
+}
+
+impl ConsoleLogFilter {
+    /// Whether events of `level` from `target` are printed.
+    pub fn enabled(&self, target: &str, level: &Level) -> bool {
+        let module = self
+            .modules
+            .iter()
+            .filter(|(module, _)| {
+                target
+                    .strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len());
+        let filter = module.map_or(self.level, |(_, filter)| *filter);
+        *level <= filter
+    }
+}
+
+/// A log event waiting to be printed.
+struct CapturedLog {
+    level: Level,
+    target: String,
+    message: String,
+}
+
+/// Sends log events to the console through a bounded channel.
+struct ConsoleLogLayer {
+    sender: SyncSender<CapturedLog>,
+    dropped: Arc<AtomicUsize>,
+    filter: Arc<RwLock<ConsoleLogFilter>>,
+}
+
+impl<S: Subscriber> Layer<S> for ConsoleLogLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let (target, level) = (metadata.target(), metadata.level());
+        let enabled = self
+            .filter
+            .read()
+            .is_ok_and(|filter| filter.enabled(target, level));
+        if !enabled {
+            return;
+        }
+
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+        let log = CapturedLog {
+            level: *level,
+            target: target.to_owned(),
+            message: message.0,
+        };
+        if self.sender.try_send(log).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Formats the message of an event followed by its other fields, like `message key=value`.
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl MessageVisitor {
+    fn separate(&mut self) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.separate();
+        if field.name() == "message" {
+            self.0.push_str(value);
+        } else {
+            let _ = write!(self.0, "{}={value}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.separate();
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, "{}={value:?}", field.name());
+        }
+    }
+}
+
+/// Shares the [`ConsoleLogFilter`] with the layer when it changed.
+pub(crate) fn update_log_filter(
+    captured: Option<Res<CapturedLogs>>,
+    filter: Res<ConsoleLogFilter>,
+) {
+    let Some(captured) = captured else {
+        return;
+    };
+    if filter.is_changed() {
+        if let Ok(mut shared) = captured.filter.write() {
+            *shared = filter.clone();
+        }
+    }
+}
+
+/// Restores the default [`ConsoleLogFilter`] when the console is reset.
+pub(crate) fn reset_log_filter(
+    mut resets: EventReader<ResetConsole>,
+    mut filter: ResMut<ConsoleLogFilter>,
+) {
+    if resets.read().count() > 0 {
+        filter.set_if_neq(ConsoleLogFilter::default());
+    }
+}
+
+/// Prints the log events captured since the last frame.
+pub(crate) fn print_captured_logs(
+    captured: Option<Res<CapturedLogs>>,
+    config: Res<ConsoleConfiguration>,
+    mut console_line: EventWriter<PrintConsoleLine>,
+) {
+    let Some(captured) = captured else {
+        return;
+    };
+    let Ok(receiver) = captured.receiver.lock() else {
+        return;
+    };
+    for log in receiver.try_iter() {
+        let color = match log.level {
+            Level::ERROR => AnsiColor::Red,
+            Level::WARN => AnsiColor::Yellow,
+            Level::INFO => AnsiColor::Green,
+            Level::DEBUG => AnsiColor::Blue,
+            _ => AnsiColor::Magenta,
+        };
+        let mut line = colored(color, &format!("{:>5}", log.level.as_str()));
+        line.push_str(&format!(" {}", log.message));
+        console_line.send(PrintConsoleLine::new(line).with_source(log.target));
+    }
+    let dropped = captured.dropped.swap(0, Ordering::Relaxed);
+    if dropped > 0 {
+        let warning = config.localization.dropped_logs.fill(dropped, &[]);
+        console_line.send(PrintConsoleLine::new(colored(AnsiColor::Yellow, &warning)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captured_logs_are_printed() {
+        let (layer, captured) = capture_logs();
+        let mut app = App::new();
+        app.insert_resource(captured)
+            .init_resource::<ConsoleConfiguration>()
+            .insert_resource(ConsoleLogFilter {
+                level: LevelFilter::WARN,
+                ..default()
+            })
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Update, (update_log_filter, print_captured_logs).chain());
+        app.update();
+
+        let subscriber = Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            warn!(target: "my_game::net", peer = 3, "connection lost");
+            info!("filtered out");
+        });
+        app.update();
+
+        let events = app.world.resource::<Events<PrintConsoleLine>>();
+        let lines = events
+            .get_reader()
+            .read(events)
+            .map(|line| (line.line.to_string(), line.source.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![(
+                " WARN connection lost peer=3".to_owned(),
+                Some("my_game::net".to_owned())
+            )]
+        );
+    }
+
+    #[test]
+    fn test_log_filter() {
+        let filter = ConsoleLogFilter {
+            level: LevelFilter::WARN,
+            modules: BTreeMap::from([
+                ("my_game".to_owned(), LevelFilter::DEBUG),
+                ("my_game::ai".to_owned(), LevelFilter::OFF),
+            ]),
+        };
+
+        assert!(filter.enabled("wgpu", &Level::ERROR));
+        assert!(!filter.enabled("wgpu", &Level::INFO));
+        assert!(filter.enabled("my_game", &Level::DEBUG));
+        assert!(filter.enabled("my_game::physics", &Level::DEBUG));
+        assert!(!filter.enabled("my_game::physics", &Level::TRACE));
+        assert!(!filter.enabled("my_game::ai::path", &Level::ERROR));
+        // Only whole module names match
+        assert!(!filter.enabled("my_game_server", &Level::INFO));
+    }
+}
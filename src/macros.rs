@@ -48,3 +48,42 @@ macro_rules! reply_failed {
         }
     };
 }
+
+/// Reply with the value of a [`Result`] followed by `[ok]`, or its error followed by `[failed]`.
+///
+/// The value can be formatted with the [`format!`] syntax, it's the last argument.
+///
+/// # Example
+///
+/// ```ignore
+/// reply_result!(cmd, spawn(kind));
+/// reply_result!(cmd, spawn(kind), "Spawned entity {:?}");
+/// ```
+#[macro_export]
+macro_rules! reply_result {
+    ($cmd: ident, $result: expr) => {
+        $cmd.reply_result($result)
+    };
+    ($cmd: ident, $result: expr, $fmt: literal) => {
+        $cmd.reply_result($result.map(|value| format!($fmt, value)))
+    };
+}
+
+/// The name and version of the crate this is used in, from its `Cargo.toml`.
+///
+/// # Example
+///
+/// ```
+/// use bevy_console::{app_version, ConsoleConfiguration};
+///
+/// let config = ConsoleConfiguration {
+///     app_version: Some(app_version!().to_owned()),
+///     ..Default::default()
+/// };
+/// ```
+#[macro_export]
+macro_rules! app_version {
+    () => {
+        concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"))
+    };
+}
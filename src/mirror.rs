@@ -0,0 +1,196 @@
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::console::{ConsoleConfiguration, ConsoleState};
+use crate::instance::ConsoleMarker;
+
+/// Where lines added to the scrollback are also written, for [`ConsoleConfiguration::mirror`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConsoleMirror {
+    /// The standard output
+    Stdout,
+    /// The standard error
+    Stderr,
+    /// A file, opened on the first line written to it.
+    ///
+    /// If it can't be opened or written to, a warning is logged and nothing more is written to it.
+    File(PathBuf),
+}
+
+/// Files mirroring the scrollback, opened on first use.
+#[derive(Default, Resource)]
+pub(crate) struct MirrorFiles {
+    files: BTreeMap<PathBuf, MirrorFile>,
+    /// Files which failed, and which are no longer written to
+    failed: BTreeSet<PathBuf>,
+}
+
+struct MirrorFile {
+    file: File,
+    /// Size of the file
+    bytes: u64,
+}
+
+impl MirrorFiles {
+    /// Writes a line to a file mirror, rotating it once it would grow past `max_bytes`.
+    fn write_line<M>(&mut self, path: &Path, line: &str, config: &ConsoleConfiguration<M>) {
+        if self.failed.contains(path) {
+            return;
+        }
+        if let Err(err) = self.try_write_line(path, line, config) {
+            warn!(
+                "console output isn't mirrored to '{}' anymore: {err}",
+                path.display()
+            );
+            self.files.remove(path);
+            self.failed.insert(path.to_owned());
+        }
+    }
+
+    fn try_write_line<M>(
+        &mut self,
+        path: &Path,
+        line: &str,
+        config: &ConsoleConfiguration<M>,
+    ) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        let full = |mirror: &MirrorFile| {
+            config
+                .mirror_max_bytes
+                .is_some_and(|max_bytes| mirror.bytes > 0 && mirror.bytes + len > max_bytes)
+        };
+        if self.files.get(path).is_some_and(full) {
+            // The previous file is kept beside it, replacing the one kept before
+            self.files.remove(path);
+            fs::rename(path, rotated_path(path))?;
+            self.files.insert(path.to_owned(), open_file(path, false)?);
+        }
+        let mirror = match self.files.entry(path.to_owned()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(open_file(path, config.mirror_append)?),
+        };
+        writeln!(mirror.file, "{line}")?;
+        mirror.bytes += len;
+        Ok(())
+    }
+}
+
+fn open_file(path: &Path, append: bool) -> io::Result<MirrorFile> {
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)?;
+    let bytes = file.metadata()?.len();
+    Ok(MirrorFile { file, bytes })
+}
+
+/// Path the previous contents of a full mirror file are moved to, like `console.log.1`.
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    rotated.into()
+}
+
+/// Time of day in UTC, like `[14:03:27.512]`.
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs() % (24 * 60 * 60);
+    format!(
+        "[{:02}:{:02}:{:02}.{:03}]",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Writes the lines added to the scrollback of a console this frame to its mirrors.
+pub(crate) fn write_mirrors<M: ConsoleMarker>(
+    config: Res<ConsoleConfiguration<M>>,
+    mut state: ResMut<ConsoleState<M>>,
+    mut files: ResMut<MirrorFiles>,
+) {
+    if state.mirror_lines.is_empty() {
+        return;
+    }
+    let lines = std::mem::take(&mut state.mirror_lines);
+    // Replies to lines read from the standard input go to the standard output
+    let stdout = config.read_stdin && !config.mirror.contains(&ConsoleMirror::Stdout);
+    if config.mirror.is_empty() && !stdout {
+        return;
+    }
+    let now = SystemTime::now();
+    for line in lines {
+        let line = if config.mirror_timestamps {
+            format!("{} {line}", timestamp(now))
+        } else {
+            line
+        };
+        let stdout = stdout.then_some(&ConsoleMirror::Stdout);
+        for mirror in config.mirror.iter().chain(stdout) {
+            // Nothing can be reported if the standard streams are gone
+            let _ = match mirror {
+                ConsoleMirror::Stdout => writeln!(io::stdout().lock(), "{line}"),
+                ConsoleMirror::Stderr => writeln!(io::stderr().lock(), "{line}"),
+                ConsoleMirror::File(path) => {
+                    files.write_line(path, &line, &config);
+                    Ok(())
+                }
+            };
+        }
+    }
+    let _ = io::stdout().flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConsoleConfiguration;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timestamp() {
+        let time = UNIX_EPOCH + Duration::from_millis(((14 * 60 + 3) * 60 + 27) * 1000 + 512);
+        assert_eq!(timestamp(time), "[14:03:27.512]");
+    }
+
+    #[test]
+    fn test_file_mirror_rotates() {
+        let path = std::env::temp_dir().join("bevy_console_mirror.log");
+        let config = ConsoleConfiguration {
+            mirror_append: false,
+            mirror_max_bytes: Some(13),
+            ..default()
+        };
+        let mut files = MirrorFiles::default();
+        for line in ["first", "second", "third"] {
+            files.write_line(&path, line, &config);
+        }
+        files.files.clear();
+
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path)).unwrap(),
+            "first\nsecond\n"
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third\n");
+        let _ = fs::remove_file(rotated_path(&path));
+        let _ = fs::remove_file(&path);
+
+        let missing = std::env::temp_dir()
+            .join("bevy_console_missing")
+            .join("mirror.log");
+        files.write_line(&missing, "line", &config);
+        assert!(files.failed.contains(&missing));
+    }
+}
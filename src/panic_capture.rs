@@ -0,0 +1,149 @@
+use std::any::Any;
+use std::panic::{self, Location};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use bevy::prelude::*;
+use clap::builder::styling::AnsiColor;
+
+use crate::console::colored;
+use crate::{ConsoleConfiguration, ConsoleOpen, PrintConsoleLine};
+
+/// Maximum number of panics waiting to be printed, later ones are dropped
+const PANIC_CAPACITY: usize = 64;
+
+/// Panics caught by the hook of this app, until the console prints them. Each app installs its
+/// own hook, which calls the hook installed before it, so every app sees every panic.
+#[derive(Resource)]
+pub(crate) struct CapturedPanics {
+    receiver: Mutex<Receiver<String>>,
+    /// Panics which didn't fit in the channel since the last frame
+    dropped: Arc<AtomicUsize>,
+}
+
+/// Installs a panic hook printing panics in the console, for
+/// [`ConsoleConfiguration::capture_panics`]. The previous hook still runs after it.
+pub(crate) fn install_panic_hook(mut commands: Commands, config: Res<ConsoleConfiguration>) {
+    if !config.capture_panics {
+        return;
+    }
+    let (sender, receiver) = sync_channel(PANIC_CAPACITY);
+    let dropped = Arc::new(AtomicUsize::new(0));
+    commands.insert_resource(CapturedPanics {
+        receiver: Mutex::new(receiver),
+        dropped: dropped.clone(),
+    });
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let message = panic_message(thread::current().name(), info.payload(), info.location());
+        if sender.try_send(message).is_err() {
+            dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        previous(info);
+    }));
+}
+
+/// Formats a panic like the default hook does.
+fn panic_message(
+    thread: Option<&str>,
+    payload: &(dyn Any + Send),
+    location: Option<&Location>,
+) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let thread = thread.unwrap_or("<unnamed>");
+    match location {
+        Some(location) => format!("thread '{thread}' panicked at {location}:\n{message}"),
+        None => format!("thread '{thread}' panicked:\n{message}"),
+    }
+}
+
+/// Prints the panics caught since the last frame, opening the console for
+/// [`ConsoleConfiguration::open_on_panic`].
+pub(crate) fn print_captured_panics(
+    captured: Option<Res<CapturedPanics>>,
+    config: Res<ConsoleConfiguration>,
+    mut console_open: ResMut<ConsoleOpen>,
+    mut console_line: EventWriter<PrintConsoleLine>,
+) {
+    let Some(captured) = captured else {
+        return;
+    };
+    let Ok(receiver) = captured.receiver.lock() else {
+        return;
+    };
+    let mut caught = false;
+    for message in receiver.try_iter() {
+        for line in message.lines() {
+            console_line.send(PrintConsoleLine::new(colored(AnsiColor::Red, line)));
+        }
+        caught = true;
+    }
+    let dropped = captured.dropped.swap(0, Ordering::Relaxed);
+    if dropped > 0 {
+        let warning = config.localization.dropped_panics.fill(dropped, &[]);
+        console_line.send(PrintConsoleLine::new(colored(AnsiColor::Red, &warning)));
+    }
+    if (caught || dropped > 0) && config.open_on_panic && !console_open.open {
+        console_open.open = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_app_captures_panics() {
+        let mut apps = [(); 2].map(|_| {
+            let mut app = App::new();
+            app.insert_resource(ConsoleConfiguration {
+                capture_panics: true,
+                ..default()
+            })
+            .init_resource::<ConsoleOpen>()
+            .add_event::<PrintConsoleLine>()
+            .add_systems(Startup, install_panic_hook)
+            .add_systems(Update, print_captured_panics);
+            app.update();
+            app
+        });
+
+        let _ = thread::Builder::new()
+            .name("captured".to_owned())
+            .spawn(|| panic!("captured panic"))
+            .unwrap()
+            .join();
+
+        for app in &mut apps {
+            app.update();
+            let events = app.world.resource::<Events<PrintConsoleLine>>();
+            let lines = events
+                .get_reader()
+                .read(events)
+                .map(|line| line.line.to_string())
+                .collect::<Vec<_>>();
+            assert!(lines.iter().any(|line| line.contains("captured panic")));
+        }
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let location = Location::caller();
+        let payload: Box<dyn Any + Send> = Box::new(format!("index {} out of bounds", 3));
+        assert_eq!(
+            panic_message(Some("main"), payload.as_ref(), Some(location)),
+            format!("thread 'main' panicked at {location}:\nindex 3 out of bounds")
+        );
+        let payload: Box<dyn Any + Send> = Box::new("boom");
+        assert_eq!(
+            panic_message(None, payload.as_ref(), None),
+            "thread '<unnamed>' panicked:\nboom"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::console::{receive_console_line, ConsoleState};
+use crate::instance::DefaultConsole;
+use crate::{CommandOrigin, ConsoleRunner, ConsoleSet};
+
+/// How long a client has to send the token after connecting
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest token line read before the client is turned away, in bytes
+const MAX_TOKEN_LINE: u64 = 1024;
+/// Longest command line read before the client is disconnected, newline included, in bytes
+const MAX_LINE: u64 = 64 * 1024;
+/// Most connections handled at once, authenticated or not
+const MAX_CONNECTIONS: usize = 16;
+
+/// Runs console commands sent over TCP, e.g. on dedicated servers without a window.
+///
+/// Clients connect to `address` and send lines of text. The first line must be the `token`,
+/// after which each line is run like [`ConsoleRunner::run`]. The lines replying to the commands
+/// of a client are sent back to it without styling, also when they're printed in later frames,
+/// like after a `wait`. A confirmation prompt is answered by the next line the client sends.
+///
+/// Connections are handled on background threads, at most 16 at once, and a client sending a
+/// line longer than 64 KiB is disconnected. The token is sent in clear text, so the address
+/// should only be reachable from trusted networks or through a tunnel.
+///
+/// # Example
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// use bevy_console::{ConsolePlugin, RemoteConsolePlugin};
+///
+/// App::new().add_plugins((
+///     MinimalPlugins,
+///     ConsolePlugin::new(),
+///     RemoteConsolePlugin {
+///         address: "127.0.0.1:7878".to_owned(),
+///         token: std::env::var("CONSOLE_TOKEN").unwrap(),
+///     },
+/// ));
+/// ```
+pub struct RemoteConsolePlugin {
+    /// Address to listen on, like `127.0.0.1:7878`
+    pub address: String,
+    /// Shared secret clients send as their first line
+    pub token: String,
+}
+
+impl Plugin for RemoteConsolePlugin {
+    fn build(&self, app: &mut App) {
+        if self.token.is_empty() {
+            error!("remote console disabled, its token is empty");
+            return;
+        }
+        let listener = match TcpListener::bind(&self.address) {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!("remote console can't listen on {}: {err}", self.address);
+                return;
+            }
+        };
+        let (sender, receiver) = channel();
+        let token = self.token.clone();
+        let spawned = thread::Builder::new()
+            .name("remote console".to_owned())
+            .spawn(move || accept_clients(listener, token, sender));
+        if let Err(err) = spawned {
+            error!("remote console can't start: {err}");
+            return;
+        }
+
+        app.insert_resource(RemoteClients {
+            receiver: Mutex::new(receiver),
+            clients: BTreeMap::new(),
+        })
+        .add_systems(
+            Update,
+            (
+                run_remote_lines.in_set(ConsoleSet::ConsoleUI),
+                send_remote_output
+                    .after(receive_console_line::<DefaultConsole>)
+                    .in_set(ConsoleSet::ReceiveLines),
+            ),
+        );
+    }
+}
+
+/// What happened on a remote connection.
+enum RemoteEvent {
+    /// A client sent the token, and receives the lines sent on this channel
+    Connected(u64, Sender<String>),
+    /// An authenticated client sent a line
+    Line(u64, String),
+    /// A client disconnected
+    Disconnected(u64),
+}
+
+/// Authenticated remote clients, by the id of their connection.
+#[derive(Resource)]
+struct RemoteClients {
+    receiver: Mutex<Receiver<RemoteEvent>>,
+    clients: BTreeMap<u64, Sender<String>>,
+}
+
+/// One of the [`MAX_CONNECTIONS`] connections handled at once, freed when dropped.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    /// Takes a free slot, if any.
+    fn take(connections: &Arc<AtomicUsize>) -> Option<Self> {
+        connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < MAX_CONNECTIONS).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| Self(connections.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn accept_clients(listener: TcpListener, token: String, events: Sender<RemoteEvent>) {
+    let connections = Arc::new(AtomicUsize::new(0));
+    for (id, stream) in (0..).zip(listener.incoming()) {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("remote console connection failed: {err}");
+                continue;
+            }
+        };
+        let Some(slot) = ConnectionSlot::take(&connections) else {
+            let _ = writeln!(stream, "error: too many connections");
+            continue;
+        };
+        let token = token.clone();
+        let events = events.clone();
+        let spawned = thread::Builder::new()
+            .name(format!("remote console client {id}"))
+            .spawn(move || {
+                handle_client(id, stream, &token, events);
+                drop(slot);
+            });
+        if let Err(err) = spawned {
+            warn!("remote console can't handle a connection: {err}");
+        }
+    }
+}
+
+fn handle_client(id: u64, stream: TcpStream, token: &str, events: Sender<RemoteEvent>) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    // Clients which don't send the token in time or send too much of it are turned away
+    if stream.set_read_timeout(Some(AUTH_TIMEOUT)).is_err() {
+        return;
+    }
+    let mut reader = BufReader::new(stream);
+    let mut token_line = String::new();
+    let read = (&mut reader)
+        .take(MAX_TOKEN_LINE)
+        .read_line(&mut token_line);
+    let authenticated =
+        read.is_ok() && constant_time_eq(token_line.trim_end_matches(['\r', '\n']), token);
+    if !authenticated {
+        let _ = writeln!(writer, "error: invalid token");
+        return;
+    }
+    if reader.get_ref().set_read_timeout(None).is_err() {
+        return;
+    }
+
+    // Output is written on its own thread, so a slow client doesn't hold up the app
+    let (output, received) = channel::<String>();
+    let spawned = thread::Builder::new()
+        .name(format!("remote console output {id}"))
+        .spawn(move || {
+            for line in received {
+                if writeln!(writer, "{line}").is_err() {
+                    break;
+                }
+            }
+        });
+    if spawned.is_err()
+        || events
+            .send(RemoteEvent::Connected(id, output.clone()))
+            .is_err()
+    {
+        return;
+    }
+
+    // Lines are read up to a limit, so a client never sending a newline can't exhaust memory
+    loop {
+        let mut line = String::new();
+        let read = (&mut reader).take(MAX_LINE).read_line(&mut line);
+        let Ok(len) = read else {
+            break;
+        };
+        if len == 0 {
+            break;
+        }
+        if len as u64 == MAX_LINE && !line.ends_with('\n') {
+            let _ = output.send("error: line too long".to_owned());
+            break;
+        }
+        let line = line.trim_end_matches(['\r', '\n']).to_owned();
+        if events.send(RemoteEvent::Line(id, line)).is_err() {
+            return;
+        }
+    }
+    let _ = events.send(RemoteEvent::Disconnected(id));
+}
+
+/// Compares a received token without leaking how much of it matched through timing.
+fn constant_time_eq(received: &str, token: &str) -> bool {
+    let different = received
+        .bytes()
+        .zip(token.bytes())
+        .fold(0, |different, (a, b)| different | (a ^ b));
+    different == 0 && received.len() == token.len()
+}
+
+/// Runs the lines remote clients sent since the last frame.
+fn run_remote_lines(mut remote: ResMut<RemoteClients>, mut runner: ConsoleRunner) {
+    let remote = &mut *remote;
+    let Ok(receiver) = remote.receiver.lock() else {
+        return;
+    };
+    for event in receiver.try_iter() {
+        match event {
+            RemoteEvent::Connected(id, output) => {
+                info!("remote console client {id} connected");
+                remote.clients.insert(id, output);
+            }
+            RemoteEvent::Line(id, line) => {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if let Err(error) = runner.run_from(&line, CommandOrigin::Remote(id)) {
+                    if let Some(output) = remote.clients.get(&id) {
+                        let _ = output.send(error);
+                    }
+                }
+            }
+            RemoteEvent::Disconnected(id) => {
+                info!("remote console client {id} disconnected");
+                remote.clients.remove(&id);
+            }
+        }
+    }
+}
+
+/// Sends the lines added to the scrollback since the last frame to the clients they reply to.
+fn send_remote_output(remote: Res<RemoteClients>, mut state: ResMut<ConsoleState>) {
+    for (id, line) in std::mem::take(&mut state.remote_lines) {
+        // Lines for clients which disconnected since are dropped
+        if let Some(output) = remote.clients.get(&id) {
+            let _ = output.send(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secreT", "secret"));
+        assert!(!constant_time_eq("secret!", "secret"));
+        assert!(!constant_time_eq("", "secret"));
+    }
+
+    #[test]
+    fn test_too_long_line_disconnects() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let (events, received) = channel();
+        let handler = thread::spawn(move || handle_client(1, stream, "secret", events));
+
+        writeln!(client, "secret\nstatus").unwrap();
+        // Written in chunks, like a client streaming a line which never ends
+        let chunk = vec![b'a'; 1024];
+        for _ in 0..=MAX_LINE / 1024 {
+            if client.write_all(&chunk).is_err() {
+                break;
+            }
+        }
+        handler.join().unwrap();
+
+        // Read while the client output thread still has the connection open
+        let mut reply = String::new();
+        BufReader::new(client).read_line(&mut reply).unwrap();
+        assert_eq!(reply, "error: line too long\n");
+        let lines = received
+            .try_iter()
+            .filter_map(|event| match event {
+                RemoteEvent::Line(_, line) => Some(line),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, ["status"]);
+    }
+}
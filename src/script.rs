@@ -0,0 +1,299 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use clap::builder::styling::AnsiColor;
+use clap::Parser;
+
+use crate as bevy_console;
+use crate::console::{colored, CommandOrigin, CommandQueue, QueuedLine};
+use crate::instance::ConsoleMarker;
+use crate::localization::fill;
+use crate::{ConsoleCommand, ConsoleConfiguration, ConsoleLocalization, PrintConsoleLine};
+
+/// Maximum number of scripts an `exec` may run nested within each other
+const MAX_EXEC_DEPTH: usize = 8;
+
+/// Runs the console commands in a file, one per line, skipping blank lines and `#` comments
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "exec")]
+pub(crate) struct ExecCommand {
+    /// Path of the file
+    pub(crate) path: PathBuf,
+    /// Stop at the first line which fails
+    #[arg(long)]
+    pub(crate) strict: bool,
+}
+
+/// Script whose lines are run from the [`CommandQueue`].
+#[derive(Debug)]
+pub(crate) struct Script {
+    path: PathBuf,
+    /// Stop at the first line which fails, set for the scripts a strict script runs too
+    strict: bool,
+    /// Script whose line ran this one
+    parent: Option<Arc<Script>>,
+}
+
+impl Script {
+    /// Number of scripts this one is nested in.
+    fn depth(&self) -> usize {
+        iter_scripts(self).count() - 1
+    }
+}
+
+/// `script` followed by the scripts it's nested in.
+fn iter_scripts(script: &Script) -> impl Iterator<Item = &Script> {
+    std::iter::successors(Some(script), |script| script.parent.as_deref())
+}
+
+/// Line of a [`Script`], numbered from 1.
+#[derive(Clone, Debug)]
+pub(crate) struct ScriptLine {
+    pub(crate) script: Arc<Script>,
+    pub(crate) number: usize,
+}
+
+impl PartialEq for ScriptLine {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.script, &other.script) && self.number == other.number
+    }
+}
+
+/// Queues the lines of a script to run as if they were entered.
+///
+/// The lines are resolved when they run, so an alias or a console variable defined by a line can
+/// be used in the lines after it. A script run by a line of another script runs in its place,
+/// before the rest of that script. The lines are entered at `origin`.
+pub(crate) fn queue_script<M: ConsoleMarker>(
+    path: &Path,
+    strict: bool,
+    origin: CommandOrigin,
+    localization: &ConsoleLocalization,
+    queue: &mut CommandQueue<M>,
+) -> Result<(), String> {
+    if cfg!(target_arch = "wasm32") {
+        return Err(fill(
+            &localization.script_on_web,
+            &[("path", &path.display())],
+        ));
+    }
+
+    let parent = queue.script.as_ref().map(|line| line.script.clone());
+    if parent
+        .as_ref()
+        .is_some_and(|parent| parent.depth() + 1 >= MAX_EXEC_DEPTH)
+    {
+        return Err(fill(
+            &localization.script_nested_too_deep,
+            &[("path", &path.display()), ("depth", &MAX_EXEC_DEPTH)],
+        ));
+    }
+    let text = fs::read_to_string(path).map_err(|err| {
+        fill(
+            &localization.script_unreadable,
+            &[("path", &path.display()), ("error", &err)],
+        )
+    })?;
+
+    let script = Arc::new(Script {
+        path: path.to_owned(),
+        strict: strict || parent.as_ref().is_some_and(|parent| parent.strict),
+        parent,
+    });
+    let lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| QueuedLine {
+            line: line.to_owned(),
+            script: Some(ScriptLine {
+                script: script.clone(),
+                number,
+            }),
+            chained: false,
+            origin,
+        })
+        .collect::<Vec<_>>();
+    if script.parent.is_some() {
+        for line in lines.into_iter().rev() {
+            queue.lines.push_front(line);
+        }
+    } else {
+        queue.lines.extend(lines);
+    }
+    Ok(())
+}
+
+/// Stops the script of a failed line if it's strict, along with the strict scripts it's nested
+/// in, returning the error to print.
+pub(crate) fn stop_strict_script<M: ConsoleMarker>(
+    line: &ScriptLine,
+    localization: &ConsoleLocalization,
+    queue: &mut CommandQueue<M>,
+) -> Option<String> {
+    // Strict scripts only run strict scripts, the outermost one is stopped with all it runs
+    let stopped = iter_scripts(&line.script)
+        .take_while(|script| script.strict)
+        .last()?;
+    queue.lines.retain(|queued| {
+        !queued.script.as_ref().is_some_and(|queued| {
+            iter_scripts(&queued.script).any(|script| std::ptr::eq(script, stopped))
+        })
+    });
+    Some(fill(
+        &localization.script_stopped,
+        &[
+            ("path", &line.script.path.display()),
+            ("line", &line.number),
+        ],
+    ))
+}
+
+/// Runs the [`ConsoleConfiguration::autoexec`] script once all commands are registered.
+pub(crate) fn run_autoexec(
+    config: Res<ConsoleConfiguration>,
+    mut queue: ResMut<CommandQueue>,
+    mut console_line: EventWriter<PrintConsoleLine>,
+) {
+    let Some(path) = &config.autoexec else {
+        return;
+    };
+    if !path.is_file() {
+        warn!("autoexec script {} doesn't exist", path.display());
+        return;
+    }
+
+    let origin = CommandOrigin::Startup;
+    if let Err(error) = queue_script(path, false, origin, &config.localization, &mut queue) {
+        console_line
+            .send(PrintConsoleLine::new(colored(AnsiColor::Red, &error)).with_source("autoexec"));
+    }
+}
+
+#[cfg(all(test, feature = "default_commands"))]
+mod tests {
+    use bevy::ecs::event::ManualEventReader;
+
+    use super::*;
+    use crate::console::{CommandFailed, CommandParseFailed, ConsoleState};
+    use crate::dispatch::run_queued_commands;
+    use crate::instance::DefaultConsole;
+    use crate::{
+        ConsoleAliases, ConsoleCommandEntered, ConsoleGates, ConsoleVariables,
+        UnknownConsoleCommand,
+    };
+
+    fn write_script(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("bevy_console_{name}.cfg"));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn script_app() -> App {
+        use crate::commands::alias::{alias_command, AliasCommand};
+        use crate::commands::exec::exec_command;
+
+        let mut config = ConsoleConfiguration::default();
+        config.register_console_command::<ExecCommand>();
+        config.register_console_command::<AliasCommand>();
+        config.register_raw_console_command("spawn", clap::Command::new("spawn"));
+        let mut app = App::new();
+        app.insert_resource(config)
+            .init_resource::<ConsoleState>()
+            .init_resource::<ConsoleAliases>()
+            .init_resource::<ConsoleVariables>()
+            .init_resource::<ConsoleGates>()
+            .init_resource::<CommandQueue>()
+            .init_resource::<Time<Real>>()
+            .add_event::<ConsoleCommandEntered>()
+            .add_event::<UnknownConsoleCommand>()
+            .add_event::<PrintConsoleLine>()
+            .add_event::<CommandFailed>()
+            .add_event::<CommandParseFailed>()
+            .add_systems(
+                Update,
+                (
+                    run_queued_commands::<DefaultConsole>,
+                    (exec_command, alias_command),
+                )
+                    .chain(),
+            );
+        app
+    }
+
+    /// Runs a script until its lines ran, returning what the `spawn` commands it entered spawned.
+    fn run_script(app: &mut App, path: &Path, strict: bool) -> Vec<String> {
+        let localization = ConsoleLocalization::default();
+        let mut queue = app.world.resource_mut::<CommandQueue>();
+        let origin = CommandOrigin::Script;
+        queue_script(path, strict, origin, &localization, &mut queue).unwrap();
+
+        let mut reader = ManualEventReader::<ConsoleCommandEntered>::default();
+        let mut spawned = Vec::new();
+        for _ in 0..20 {
+            app.update();
+            let events = app.world.resource::<Events<ConsoleCommandEntered>>();
+            spawned.extend(
+                reader
+                    .read(events)
+                    .filter(|command| command.command_name == "spawn")
+                    .flat_map(|command| command.args.clone()),
+            );
+        }
+        spawned
+    }
+
+    #[test]
+    fn test_script_lines_resolve_when_run() {
+        let nested = write_script("nested", "spawn sphere\n");
+        let path = write_script(
+            "script",
+            &format!(
+                "# setup\nalias both \"spawn cube; spawn cone\"\n\nboth\nunknown\nexec {}\n  spawn a; wait 2; spawn b  \n",
+                nested.display()
+            ),
+        );
+
+        let mut app = script_app();
+        assert_eq!(
+            run_script(&mut app, &path, false),
+            ["cube", "cone", "sphere", "a", "b"]
+        );
+        assert!(app.world.resource::<CommandQueue>().lines.is_empty());
+
+        let mut app = script_app();
+        assert_eq!(run_script(&mut app, &path, true), ["cube", "cone"]);
+        let state = app.world.resource::<ConsoleState>();
+        let stopped = format!("{}:5 failed", path.display());
+        assert!(state
+            .scrollback
+            .iter()
+            .any(|line| line.text.to_string().contains(&stopped)));
+    }
+
+    #[test]
+    fn test_strict_script_stops_on_failing_command() {
+        let missing = std::env::temp_dir().join("bevy_console_missing.cfg");
+        let path = write_script(
+            "failing",
+            &format!("spawn cube\nexec {}\nspawn sphere\n", missing.display()),
+        );
+
+        let mut app = script_app();
+        assert_eq!(run_script(&mut app, &path, false), ["cube", "sphere"]);
+        let mut app = script_app();
+        assert_eq!(run_script(&mut app, &path, true), ["cube"]);
+    }
+
+    #[test]
+    fn test_script_nesting_is_limited() {
+        let path = std::env::temp_dir().join("bevy_console_recursive.cfg");
+        fs::write(&path, format!("spawn cube\nexec {}\n", path.display())).unwrap();
+
+        let mut app = script_app();
+        assert_eq!(run_script(&mut app, &path, false).len(), MAX_EXEC_DEPTH);
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::console::{CommandQueue, ConsoleState};
+use crate::dispatch::{submit_input, CommandResources};
+use crate::{ConsoleCommandEntered, ConsoleConfiguration, UnknownConsoleCommand};
+
+/// Lines read from the standard input, for [`ConsoleConfiguration::read_stdin`].
+#[derive(Resource)]
+pub(crate) struct StdinLines {
+    receiver: Mutex<Receiver<String>>,
+    /// Tells the reader thread to stop after the line it's waiting for
+    stop: Arc<AtomicBool>,
+}
+
+/// Starts reading lines from the standard input on a background thread.
+pub(crate) fn spawn_stdin_reader(config: Res<ConsoleConfiguration>, mut commands: Commands) {
+    if !config.read_stdin {
+        return;
+    }
+    let (sender, receiver) = channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let spawned = thread::Builder::new()
+        .name("console stdin".to_owned())
+        .spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if stopped.load(Ordering::Relaxed) || sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+    match spawned {
+        Ok(_) => commands.insert_resource(StdinLines {
+            receiver: Mutex::new(receiver),
+            stop,
+        }),
+        Err(err) => error!("console can't read the standard input: {err}"),
+    }
+}
+
+/// Runs the lines read from the standard input as if they were entered in the console.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_stdin_lines(
+    stdin: Option<Res<StdinLines>>,
+    mut state: ResMut<ConsoleState>,
+    config: Res<ConsoleConfiguration>,
+    resources: CommandResources,
+    mut queue: ResMut<CommandQueue>,
+    mut command_entered: EventWriter<ConsoleCommandEntered>,
+    mut unknown_command: EventWriter<UnknownConsoleCommand>,
+    time: Res<Time<Real>>,
+) {
+    let Some(stdin) = stdin else {
+        return;
+    };
+    let Ok(receiver) = stdin.receiver.lock() else {
+        return;
+    };
+    for line in receiver.try_iter() {
+        submit_input(
+            &line,
+            &mut state,
+            &resources.lookup(&config),
+            &mut queue,
+            &mut command_entered,
+            &mut unknown_command,
+            time.elapsed(),
+        );
+    }
+}
+
+/// Stops the reader thread when the app exits.
+///
+/// The thread can't be woken up while it waits for a line, so it isn't joined. It doesn't keep
+/// the process alive either.
+pub(crate) fn stop_stdin_reader(
+    stdin: Option<Res<StdinLines>>,
+    mut exit: EventReader<AppExit>,
+    mut commands: Commands,
+) {
+    if exit.read().count() == 0 {
+        return;
+    }
+    if let Some(stdin) = stdin {
+        stdin.stop.store(true, Ordering::Relaxed);
+        commands.remove_resource::<StdinLines>();
+    }
+}
@@ -0,0 +1,112 @@
+use clap::builder::styling::Style;
+use clap::builder::StyledStr;
+
+/// Spaces between two columns
+const COLUMN_GAP: usize = 2;
+
+/// Renders `rows` as columns aligned under a bold `headers` row.
+///
+/// Cells longer than `max_width` characters are truncated with a trailing `…`.
+/// Rows with fewer cells than there are headers are padded with empty cells.
+pub(crate) fn render_table(
+    headers: &[String],
+    rows: &[Vec<String>],
+    max_width: usize,
+) -> Vec<StyledStr> {
+    let max_width = max_width.max(1);
+    let columns = rows.iter().map(Vec::len).fold(headers.len(), usize::max);
+
+    let headers = truncate_row(headers, columns, max_width);
+    let rows = rows
+        .iter()
+        .map(|row| truncate_row(row, columns, max_width))
+        .collect::<Vec<_>>();
+
+    let mut widths = vec![0; columns];
+    for row in std::iter::once(&headers).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    if !headers.iter().all(String::is_empty) {
+        let style = Style::new().bold();
+        let header = align_row(&headers, &widths);
+        lines.push(format!("{}{header}{}", style.render(), style.render_reset()).into());
+    }
+    lines.extend(rows.iter().map(|row| align_row(row, &widths).into()));
+    lines
+}
+
+fn truncate_row(row: &[String], columns: usize, max_width: usize) -> Vec<String> {
+    (0..columns)
+        .map(|column| {
+            row.get(column)
+                .map_or_else(String::new, |cell| truncate(cell, max_width))
+        })
+        .collect()
+}
+
+fn truncate(cell: &str, max_width: usize) -> String {
+    // Cells are laid out on a single row
+    let cell = cell.lines().next().unwrap_or_default();
+    if cell.chars().count() <= max_width {
+        cell.to_owned()
+    } else {
+        let mut truncated = cell.chars().take(max_width - 1).collect::<String>();
+        truncated.push('…');
+        truncated
+    }
+}
+
+fn align_row(row: &[String], widths: &[usize]) -> String {
+    let mut line = String::new();
+    for (cell, width) in row.iter().zip(widths) {
+        line.push_str(cell);
+        let padding = width - cell.chars().count() + COLUMN_GAP;
+        line.push_str(&" ".repeat(padding));
+    }
+    line.trim_end().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|cell| cell.to_string()).collect()
+    }
+
+    #[test]
+    fn test_render_table_aligns_columns() {
+        let rendered = render_table(
+            &strings(&["id", "name"]),
+            &[
+                strings(&["1", "camera"]),
+                strings(&["42", "player"]),
+                strings(&["7"]),
+            ],
+            40,
+        )
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>();
+
+        assert_eq!(rendered, vec!["id  name", "1   camera", "42  player", "7"]);
+    }
+
+    #[test]
+    fn test_render_table_truncates_long_cells() {
+        let rendered = render_table(
+            &strings(&["name", "über"]),
+            &[strings(&["a very long entity name", "ü"])],
+            8,
+        )
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>();
+
+        assert_eq!(rendered, vec!["name      über", "a very …  ü"]);
+    }
+}
@@ -0,0 +1,126 @@
+use bevy_egui::egui::Color32;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Colors used by the console.
+///
+/// Changes take effect on the next frame. With the `serde` feature, colors are serialized as
+/// unmultiplied `[r, g, b, a]` arrays.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct ConsoleTheme {
+    /// Background fill of the console window, translucent colors show the scene behind it
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub background: Color32,
+    /// Border of the console window, see [`ConsoleConfiguration::border_width`](crate::ConsoleConfiguration::border_width)
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub border: Color32,
+    /// Default color of scrollback text
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub text: Color32,
+    /// Color of echoed command lines
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub echo: Color32,
+    /// Highlight behind echoed command lines, setting them apart from the output following them
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub echo_background: Color32,
+    /// Color of errors and `[failed]` markers, also used for red ANSI text
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub error: Color32,
+    /// Color of warnings, also used for yellow ANSI text
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub warning: Color32,
+    /// Color of `[ok]` markers, also used for green ANSI text
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub success: Color32,
+    /// Color of the separator between the scrollback and the input line
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub separator: Color32,
+    /// Color of the text in the input line
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub input: Color32,
+    /// Background fill behind the input line, usually more opaque than `background`
+    #[cfg_attr(feature = "serde", serde(with = "rgba"))]
+    pub input_background: Color32,
+}
+
+impl ConsoleTheme {
+    /// Light text on a translucent black background
+    pub fn dark() -> Self {
+        Self {
+            background: Color32::from_black_alpha(240),
+            border: Color32::from_gray(70),
+            text: Color32::from_gray(220),
+            echo: Color32::from_rgb(130, 170, 255),
+            echo_background: Color32::from_rgba_unmultiplied(130, 170, 255, 24),
+            error: Color32::from_rgb(241, 76, 76),
+            warning: Color32::from_rgb(245, 200, 67),
+            success: Color32::from_rgb(35, 209, 139),
+            separator: Color32::from_gray(60),
+            input: Color32::WHITE,
+            input_background: Color32::from_gray(12),
+        }
+    }
+
+    /// Dark text on a translucent white background
+    pub fn light() -> Self {
+        Self {
+            background: Color32::from_rgba_unmultiplied(245, 245, 245, 240),
+            border: Color32::from_gray(170),
+            text: Color32::from_gray(30),
+            echo: Color32::from_rgb(20, 80, 200),
+            echo_background: Color32::from_rgba_unmultiplied(20, 80, 200, 20),
+            error: Color32::from_rgb(200, 30, 30),
+            warning: Color32::from_rgb(170, 110, 0),
+            success: Color32::from_rgb(20, 140, 60),
+            separator: Color32::from_gray(190),
+            input: Color32::BLACK,
+            input_background: Color32::WHITE,
+        }
+    }
+
+    /// Saturated colors on an opaque black background
+    pub fn high_contrast() -> Self {
+        Self {
+            background: Color32::BLACK,
+            border: Color32::WHITE,
+            text: Color32::WHITE,
+            echo: Color32::from_rgb(255, 255, 0),
+            echo_background: Color32::from_gray(40),
+            error: Color32::from_rgb(255, 80, 80),
+            warning: Color32::from_rgb(255, 200, 0),
+            success: Color32::from_rgb(0, 255, 0),
+            separator: Color32::WHITE,
+            input: Color32::WHITE,
+            input_background: Color32::BLACK,
+        }
+    }
+}
+
+impl Default for ConsoleTheme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+/// Serializes colors as unmultiplied `[r, g, b, a]`, which are easier to edit than the
+/// premultiplied values egui stores.
+#[cfg(feature = "serde")]
+mod rgba {
+    use bevy_egui::egui::Color32;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        color: &Color32,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        color.to_srgba_unmultiplied().serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Color32, D::Error> {
+        let [r, g, b, a] = <[u8; 4]>::deserialize(deserializer)?;
+        Ok(Color32::from_rgba_unmultiplied(r, g, b, a))
+    }
+}
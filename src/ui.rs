@@ -1,29 +1,87 @@
+use crate::ansi::{append_ansi, truncate_ansi};
+use crate::completion::{Candidates, CompletionIndex};
+use crate::console::{
+    CommandQueue, ConsoleCommandEntered, ConsoleConfiguration, ConsoleInputFocus, ConsoleOpen,
+    ConsoleState, LineKind, ScrollbackLine, UnknownConsoleCommand,
+};
+use crate::dispatch::{submit_input, validate_input, CommandResources, InputValidation};
+use crate::instance::ConsoleMarker;
+use crate::localization::fill;
 use crate::{
-    ConsoleCommandEntered, ConsoleConfiguration, ConsoleOpen, ConsoleState, ToggleConsoleKey,
+    ConsoleAnchor, ConsoleLayout, ConsoleOverlay, ConsoleShortcuts, ConsoleTheme,
+    ConsoleToggleSource, ConsoleVariables, ConsoleWindowTarget, FontChoice, TextDirection,
+    ToggleConsoleKey,
 };
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::ecs::system::SystemParam;
 use bevy::input::keyboard::KeyboardInput;
 use bevy::prelude::*;
-use bevy_egui::egui::{self, Align, ScrollArea, TextEdit};
+use bevy::window::PrimaryWindow;
+use bevy_egui::egui::{
+    self, pos2, vec2, Align, Color32, Galley, Hyperlink, Label, Margin, Pos2, Rect, Response,
+    ScrollArea, Sense, Stroke, TextEdit, Vec2,
+};
 use bevy_egui::egui::{text::LayoutJob, text_edit::CCursorRange};
 use bevy_egui::egui::{Context, Id};
 use bevy_egui::{
-    egui::{epaint::text::cursor::CCursor, Color32, FontId, TextFormat},
-    EguiContexts,
+    egui::{epaint::text::cursor::CCursor, FontFamily, FontId, TextFormat},
+    EguiContexts, EguiInput,
 };
 use clap::builder::StyledStr;
-use shlex::Shlex;
+use std::any::type_name;
+use std::collections::VecDeque;
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Settings affecting the cached layouts of scrollback lines.
+#[derive(PartialEq)]
+pub(crate) struct LayoutStyle {
+    font_id: FontId,
+    theme: ConsoleTheme,
+    text_direction: TextDirection,
+    show_sources: bool,
+    max_line_length: usize,
+    /// Address of egui's font atlas, which is built again when the scale changes or it fills up,
+    /// leaving the glyphs of cached galleys pointing into the old one
+    font_atlas: usize,
+}
 
-pub(crate) fn console_ui(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn console_ui<M: ConsoleMarker>(
     mut egui_context: EguiContexts,
-    config: Res<ConsoleConfiguration>,
+    mut config: ResMut<ConsoleConfiguration<M>>,
     mut keyboard_input_events: EventReader<KeyboardInput>,
     keys: Res<Input<KeyCode>>,
-    mut state: ResMut<ConsoleState>,
-    mut command_entered: EventWriter<ConsoleCommandEntered>,
-    mut console_open: ResMut<ConsoleOpen>,
+    mut state: ResMut<ConsoleState<M>>,
+    mut command_entered: EventWriter<ConsoleCommandEntered<M>>,
+    mut unknown_command: EventWriter<UnknownConsoleCommand<M>>,
+    mut queue: ResMut<CommandQueue<M>>,
+    input: InputResources<M>,
+    mut console_open: ResMut<ConsoleOpen<M>>,
+    mut input_focus: ResMut<ConsoleInputFocus<M>>,
+    time: Res<Time<Real>>,
+    windows: ConsoleWindows,
+    diagnostics: Option<Res<DiagnosticsStore>>,
+    mut layout_style: Local<Option<LayoutStyle>>,
+    mut last_ui_scale: Local<Option<f32>>,
 ) {
-    let keyboard_input_events = keyboard_input_events.read().collect::<Vec<_>>();
-    let ctx = egui_context.ctx_mut();
+    let now = time.elapsed();
+    let resources = &input.commands;
+    let shortcuts = &*input.shortcuts;
+    state.input_recorded = false;
+    let Some(window) = console_window(&config, &windows) else {
+        return;
+    };
+    let Some(ctx) = egui_context.try_ctx_for_window_mut(window) else {
+        return;
+    };
+
+    // Only keys pressed in the console's window control it
+    let keyboard_input_events = keyboard_input_events
+        .read()
+        .filter(|event| event.window == window)
+        .collect::<Vec<_>>();
 
     let pressed = keyboard_input_events
         .iter()
@@ -33,138 +91,1698 @@ pub(crate) fn console_ui(
     // Avoid opening console if typing in another text input
     if pressed && (console_open.open || !ctx.wants_keyboard_input()) {
         console_open.open = !console_open.open;
+        state.toggle_source = Some(ConsoleToggleSource::Key);
     }
 
-    if !console_open.open {
+    let font_id = console_font(ctx, &config);
+
+    // Cached layouts are only valid for the style they were built with, checked even while
+    // closed since the overlay shares them
+    let style = LayoutStyle {
+        font_id: font_id.clone(),
+        theme: config.theme.clone(),
+        text_direction: config.text_direction,
+        show_sources: config.show_sources,
+        max_line_length: config.max_line_length,
+        font_atlas: ctx.fonts(|fonts| Arc::as_ptr(&fonts.texture_atlas()) as usize),
+    };
+    if layout_style.as_ref() != Some(&style) {
+        for line in state.scrollback.iter_mut() {
+            line.layout = None;
+        }
+        *layout_style = Some(style);
+    }
+
+    // The console keeps showing while it slides out after closing
+    let window_id = console_id::<M>("console");
+    let shown = if config.slide_duration > 0.0 {
+        ctx.animate_bool_with_time(window_id, console_open.open, config.slide_duration)
+    } else {
+        if console_open.open {
+            1.0
+        } else {
+            0.0
+        }
+    };
+    if shown <= 0.0 {
         return;
     }
+    let closing = !console_open.open;
 
-    egui::Window::new("console")
-        .fixed_pos([config.left_pos, config.top_pos])
-        .default_size([config.width, config.height])
-        .collapsible(config.collapsible)
-        .resizable(config.resizable)
-        .title_bar(config.title_bar)
-        .frame(egui::Frame::none().fill(egui::Color32::from_black_alpha(240)))
-        .show(ctx, |ui| {
-            ui.vertical(|ui| {
-                let scroll_height = ui.available_height() - 30.0;
+    // Anchored positions and relative sizes follow the screen size
+    let (anchored_pos, relative_size) = window_layout(&config, ctx.screen_rect());
+    let floating = config.layout == ConsoleLayout::Window;
+    let movable = floating && config.movable && anchored_pos.is_none() && shown >= 1.0;
+
+    // Slide in from above the top of the screen
+    let pos = anchored_pos.unwrap_or(pos2(config.left_pos, config.top_pos));
+    let window_bottom = ctx
+        .memory(|memory| memory.area_rect(window_id))
+        .map_or(pos.y + config.height * config.ui_scale, |rect| {
+            pos.y + rect.height()
+        });
+    let pos = pos - vec2(0.0, window_bottom.max(0.0) * (1.0 - shown));
+
+    // The scrollback and input line are the same in every layout
+    let mut content_size = None;
+    let contents = |ui: &mut egui::Ui| {
+        if let Some(ui_style) = &config.ui_style {
+            ui_style(ui.style_mut());
+        }
+        content_size = Some(ui.available_size());
+
+        ui.vertical(|ui| {
+            let status_font_id =
+                FontId::new(font_id.size * STATUS_BAR_SCALE, font_id.family.clone());
+
+            if config.layout == ConsoleLayout::Mini {
+                // Only the most recent line is shown above the input line
+                if let Some(line) = state.scrollback.last_mut() {
+                    let rows = line_layout(line, &font_id, &config).rows(ui, None);
+                    rows_ui(ui, rows, config.text_direction);
+                }
+            } else {
+                // The separator, the input line and the status bar take the height below the
+                // scrollback
+                let mut input_height = ui.fonts(|fonts| fonts.row_height(&font_id))
+                    + INPUT_MARGIN.y * 2.0
+                    + SEPARATOR_SPACING
+                    + ui.spacing().item_spacing.y * 2.0;
+                if !config.quick_commands.is_empty() {
+                    input_height += ui.spacing().interact_size.y + ui.spacing().item_spacing.y;
+                }
+                if config.show_status_bar {
+                    input_height += ui.fonts(|fonts| fonts.row_height(&status_font_id))
+                        + ui.spacing().item_spacing.y;
+                }
+                let scroll_height = ui.available_height() - input_height;
 
                 // Scroll area
-                ScrollArea::vertical()
-                    .auto_shrink([false, false])
-                    .stick_to_bottom(true)
-                    .max_height(scroll_height)
-                    .show(ui, |ui| {
-                        ui.vertical(|ui| {
-                            for line in &state.scrollback {
-                                let mut text = LayoutJob::default();
-
-                                text.append(
-                                    &line.to_string(),
-                                    0f32,
-                                    TextFormat::simple(FontId::monospace(14f32), Color32::WHITE),
-                                );
+                let opened = console_open.is_changed() && !closing;
+                let pages = ui.input(|i| {
+                    key_pressed(i, shortcuts.page_down) as i32
+                        - key_pressed(i, shortcuts.page_up) as i32
+                });
+                scrollback_ui(
+                    ui,
+                    &mut state,
+                    &config,
+                    &font_id,
+                    scroll_height,
+                    opened,
+                    pages,
+                );
 
-                                ui.label(text);
-                            }
-                        });
+                // Separator
+                ui.visuals_mut().widgets.noninteractive.bg_stroke.color = config.theme.separator;
+                ui.add(egui::Separator::default().spacing(SEPARATOR_SPACING));
+            }
 
-                        // Scroll to bottom if console just opened
-                        if console_open.is_changed() {
-                            ui.scroll_to_cursor(Some(Align::BOTTOM));
-                        }
+            // Suggestions take the navigation keys before the input line sees them, the names at
+            // the start of the line come from the prebuilt index
+            let (path, _) = split_completion(&state.buf);
+            let arguments = argument_names(&config, &path);
+            let names = if path.is_empty() {
+                input.completion.candidates()
+            } else {
+                &arguments
+            };
+            let suggestions = if closing || state.suggestions_dismissed {
+                Vec::new()
+            } else {
+                command_suggestions(names, &state.buf, config.suggestion_count)
+            };
+            let mut accepted = false;
+            if !suggestions.is_empty() {
+                let count = suggestions.len();
+                let index = state.suggestion_index.min(count - 1);
+                let selected = suggestions[index];
+                // Enter runs a command whose full name is typed instead of completing it
+                let (_, typed) = split_completion(&state.buf);
+                let complete = typed != selected;
+                let typed = typed.len();
+                let (up, down, accept, dismiss) = ui.input_mut(|i| {
+                    (
+                        consume_key(i, shortcuts.previous),
+                        consume_key(i, shortcuts.next),
+                        consume_key(i, shortcuts.complete)
+                            || complete && consume_key(i, shortcuts.submit),
+                        consume_key(i, shortcuts.hide_suggestions),
+                    )
+                });
+
+                state.suggestion_index = if up {
+                    (index + count - 1) % count
+                } else if down {
+                    (index + 1) % count
+                } else {
+                    index
+                };
+                if accept {
+                    let start = state.buf.len() - typed;
+                    state.buf.truncate(start);
+                    state.buf.push_str(selected);
+                    state.buf.push(' ');
+                    state.suggestion_index = 0;
+                    accepted = true;
+                }
+                state.suggestions_dismissed = dismiss;
+            }
+
+            // Ghost text completing the command name, accepted with the keys that would move
+            // the caret past the end of the input
+            let input_id = window_id.with("input");
+            let ghost = if closing || config.text_direction == TextDirection::RightToLeft {
+                None
+            } else {
+                ghost_completion(names, &state.history, &state.buf)
+            };
+            if let Some(rest) = ghost {
+                let at_end = caret_at_end(TextEdit::load_state(ui.ctx(), input_id), &state.buf);
+                let accept = at_end
+                    && ui.input_mut(|i| {
+                        let keys = &shortcuts.accept_hint;
+                        keys.iter().any(|&key| consume_key(i, key))
                     });
+                if accept {
+                    state.buf.push_str(rest);
+                    accepted = true;
+                }
+            }
 
-                // Separator
-                ui.separator();
-
-                // Input
-                let text_edit = TextEdit::singleline(&mut state.buf)
-                    .desired_width(f32::INFINITY)
-                    .lock_focus(true)
-                    .frame(false)
-                    .font(egui::TextStyle::Monospace);
-
-                // Handle enter
-                let text_edit_response = ui.add(text_edit);
-                if text_edit_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))
-                {
-                    if state.buf.trim().is_empty() {
-                        state.scrollback.push(StyledStr::new());
-                    } else {
-                        let msg = format!("{}{}", config.symbol, state.buf);
-                        state.scrollback.push(msg.into());
-                        let cmd_string = state.buf.clone();
-                        state.history.insert(1, cmd_string.into());
-                        if state.history.len() > config.history_size + 1 {
-                            state.history.pop_back();
-                        }
+            // Validation only colors the input line, and reruns when the line changed or the
+            // commands, aliases, variables or gates it's resolved with did
+            let validation = if config.validate_input {
+                let generation = resources.generation(config.last_changed());
+                let stale = state.validation.as_ref().is_none_or(|(buf, validated, _)| {
+                    *buf != state.buf || *validated != generation
+                });
+                if stale {
+                    let validation = validate_input(&state.buf, &resources.lookup(&config));
+                    state.validation = Some((state.buf.clone(), generation, validation));
+                }
+                state
+                    .validation
+                    .as_ref()
+                    .and_then(|(_, _, validation)| validation.clone())
+            } else {
+                None
+            };
+            let input_color = match validation {
+                Some(InputValidation::Invalid(_)) => config.theme.error,
+                Some(InputValidation::Unknown) => {
+                    config.theme.input.gamma_multiply(SECONDARY_TEXT_OPACITY)
+                }
+                Some(InputValidation::Valid) | None => config.theme.input,
+            };
 
-                        let mut args = Shlex::new(&state.buf).collect::<Vec<_>>();
+            // Input
+            let text_edit = TextEdit::singleline(&mut state.buf)
+                .id(input_id)
+                .desired_width(f32::INFINITY)
+                .lock_focus(true)
+                .frame(false)
+                .font(font_id.clone())
+                .text_color(input_color)
+                .horizontal_align(match config.text_direction {
+                    TextDirection::LeftToRight => Align::LEFT,
+                    TextDirection::RightToLeft => Align::RIGHT,
+                });
 
-                        if !args.is_empty() {
-                            let command_name = args.remove(0);
-                            debug!("Command entered: `{command_name}`, with args: `{args:?}`");
+            let input = egui::Frame::none()
+                .fill(config.theme.input_background)
+                .rounding(config.rounding)
+                .inner_margin(Margin::symmetric(INPUT_MARGIN.x, INPUT_MARGIN.y))
+                .show(ui, |ui| text_edit.show(ui))
+                .inner;
+            let text_edit_response = &input.response;
+            if text_edit_response.changed() {
+                state.suggestion_index = 0;
+                state.suggestions_dismissed = false;
+            }
+            if accepted {
+                set_cursor_to_end(ui.ctx(), text_edit_response.id, &state.buf);
+            } else if !suggestions.is_empty() && !state.suggestions_dismissed {
+                let popup = SuggestionPopup {
+                    id: window_id.with("suggestions"),
+                    input_rect: text_edit_response.rect,
+                    suggestions: &suggestions,
+                    selected: state.suggestion_index,
+                };
+                suggestions_ui(ui.ctx(), &popup, &config, &resources.cvars, &font_id);
+            }
 
-                            let command = config.commands.get(command_name.as_str());
+            // Without a completion, the arguments still expected are hinted instead, or else why
+            // the line can't run
+            let ghost_color = config.theme.input.gamma_multiply(SECONDARY_TEXT_OPACITY);
+            let hint = || {
+                let hidden =
+                    accepted || closing || config.text_direction == TextDirection::RightToLeft;
+                if hidden {
+                    return None;
+                }
+                if let Some(hint) = argument_hint(&config, &state.buf) {
+                    return Some((hint, ghost_color));
+                }
+                let Some(InputValidation::Invalid(error)) = &validation else {
+                    return None;
+                };
+                let error_color = config.theme.error.gamma_multiply(SECONDARY_TEXT_OPACITY);
+                Some((format!("  {error}"), error_color))
+            };
+            // TextEdit can't mix opacities, the ghost text is painted right after the input
+            let ghost = ghost
+                .filter(|_| !accepted)
+                .map(|rest| (rest.to_owned(), ghost_color))
+                .or_else(hint);
+            if let Some((rest, color)) = ghost {
+                if caret_at_end(Some(input.state.clone()), &state.buf) {
+                    let galley =
+                        ui.fonts(|fonts| fonts.layout_no_wrap(rest, font_id.clone(), color));
+                    let pos = input.text_draw_pos + vec2(input.galley.rect.right(), 0.0);
+                    ui.painter()
+                        .with_clip_rect(input.text_clip_rect)
+                        .galley(pos, galley);
+                }
+            }
 
-                            if command.is_some() {
-                                command_entered.send(ConsoleCommandEntered { command_name, args });
-                            } else {
-                                // TODO: IF COMMAND IS NOT RECOGNIZED, CHECK IF IT'S SETTING A VARIABLE
-                                debug!(
-                                    "Command not recognized, recognized commands: `{:?}`",
-                                    config.commands.keys().collect::<Vec<_>>()
-                                );
+            // Handle enter
+            // A single line text edit gives up the focus on enter, other keys leave it focused
+            let submit = ui.input(|i| key_pressed(i, shortcuts.submit));
+            if submit && (text_edit_response.lost_focus() || text_edit_response.has_focus()) {
+                let input = mem::take(&mut state.buf);
+                submit_input(
+                    &input,
+                    &mut state,
+                    &resources.lookup(&config),
+                    &mut queue,
+                    &mut command_entered,
+                    &mut unknown_command,
+                    now,
+                );
+            }
 
-                                state.scrollback.push("error: Invalid command".into());
-                            }
+            // Quick commands run as if typed
+            if !config.quick_commands.is_empty() {
+                let mut clicked = None;
+                ui.horizontal_wrapped(|ui| {
+                    for (label, command) in &config.quick_commands {
+                        if ui.small_button(label).on_hover_text(command).clicked() {
+                            clicked = Some(command.clone());
                         }
-
-                        state.buf.clear();
                     }
+                });
+                if let Some(command) = clicked {
+                    submit_input(
+                        &command,
+                        &mut state,
+                        &resources.lookup(&config),
+                        &mut queue,
+                        &mut command_entered,
+                        &mut unknown_command,
+                        now,
+                    );
                 }
+            }
 
-                // Clear on ctrl+l
-                if keyboard_input_events
-                    .iter()
-                    .any(|&k| k.state.is_pressed() && k.key_code == Some(KeyCode::L))
-                    && (keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]))
-                {
-                    state.scrollback.clear();
+            // Clear on ctrl+l
+            if keyboard_input_events
+                .iter()
+                .any(|&k| k.state.is_pressed() && k.key_code == Some(shortcuts.clear))
+                && (keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]))
+            {
+                state.clear_scrollback();
+            }
+
+            // Recall the n-th most recent history entry on ctrl+number, numbered like `!n`
+            let recalled = keyboard_input_events
+                .iter()
+                .filter(|k| k.state.is_pressed())
+                .find_map(|k| k.key_code.and_then(digit_key))
+                .filter(|_| keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]))
+                .and_then(|n| state.history_entry(n));
+            if let Some(entry) = recalled {
+                state.buf = entry;
+                set_cursor_to_end(ui.ctx(), text_edit_response.id, &state.buf);
+            }
+
+            input_focus.focused = text_edit_response.has_focus();
+
+            // Handle up and down through history
+            let browsed = text_edit_response.has_focus()
+                && if ui.input(|i| key_pressed(i, shortcuts.previous)) {
+                    state.history_previous()
+                } else if ui.input(|i| key_pressed(i, shortcuts.next)) {
+                    state.history_next()
+                } else {
+                    false
+                };
+            if browsed {
+                set_cursor_to_end(ui.ctx(), text_edit_response.id, &state.buf);
+            }
+
+            // Status bar
+            if config.show_status_bar {
+                let fps = diagnostics
+                    .as_ref()
+                    .and_then(|diagnostics| diagnostics.get(FrameTimeDiagnosticsPlugin::FPS))
+                    .and_then(|fps| fps.smoothed());
+                ui.add(
+                    Label::new(
+                        egui::RichText::new(status_text(&config, &state, fps))
+                            .font(status_font_id)
+                            .color(config.theme.text.gamma_multiply(SECONDARY_TEXT_OPACITY)),
+                    )
+                    .wrap(false),
+                );
+            }
+
+            // Focus on input right away when opening, give it back when closing
+            if closing {
+                ui.memory_mut(|m| m.surrender_focus(text_edit_response.id));
+            } else {
+                ui.memory_mut(|m| m.request_focus(text_edit_response.id));
+            }
+        });
+    };
+
+    // egui remembers the size the console was last shown at, which would keep the old scale,
+    // so the scaled size is forced for the frame the scale changes on
+    let rescaled = last_ui_scale
+        .replace(config.ui_scale)
+        .is_some_and(|s| s != config.ui_scale);
+
+    let frame = egui::Frame::none()
+        .fill(config.theme.background)
+        .stroke(Stroke::new(config.border_width, config.theme.border))
+        .inner_margin(config.inner_margin * config.ui_scale);
+
+    match config.layout {
+        ConsoleLayout::Window => {
+            let title = config
+                .title
+                .as_ref()
+                .unwrap_or(&config.localization.window_title);
+            let window = egui::Window::new(title)
+                .id(window_id)
+                .movable(movable)
+                .interactable(!closing)
+                .resizable(config.resizable);
+            let window = if movable {
+                window.current_pos(pos)
+            } else {
+                window.fixed_pos(pos)
+            };
+            let size = vec2(config.width, config.height) * config.ui_scale;
+            let window = match relative_size {
+                Some(size) => window.fixed_size(size),
+                None if rescaled => window.fixed_size(size),
+                None => window.default_size(size),
+            };
+
+            let mut open = true;
+            let window = if config.show_close_button {
+                window.open(&mut open)
+            } else {
+                window
+            };
+
+            window
+                .collapsible(config.collapsible)
+                .title_bar(config.title_bar || config.title.is_some())
+                .frame(frame.rounding(config.rounding))
+                .show(ctx, contents);
+            if !open {
+                console_open.open = false;
+                state.toggle_source = Some(ConsoleToggleSource::CloseButton);
+            }
+
+            // Raised every frame so clicking other windows doesn't bury the console
+            if config.always_on_top {
+                ctx.move_to_top(egui::LayerId::new(egui::Order::Middle, window_id));
+            }
+        }
+        ConsoleLayout::TopPanel | ConsoleLayout::BottomPanel | ConsoleLayout::Mini => {
+            // The mini console keeps its own panel state, so the height it fits to doesn't
+            // carry over to the docked layouts and back
+            let panel_id = if config.layout == ConsoleLayout::Mini {
+                window_id.with("mini")
+            } else {
+                window_id
+            };
+            let panel = if config.layout == ConsoleLayout::TopPanel {
+                egui::TopBottomPanel::top(panel_id)
+            } else {
+                egui::TopBottomPanel::bottom(panel_id)
+            };
+            // The mini console fits its contents
+            let panel = if config.layout == ConsoleLayout::Mini {
+                panel.resizable(false)
+            } else {
+                let panel = panel.resizable(config.resizable);
+                if rescaled {
+                    panel.exact_height(config.height * config.ui_scale)
+                } else {
+                    panel.default_height(config.height * config.ui_scale)
                 }
+            };
+            let panel = panel.frame(frame);
 
-                // Handle up and down through history
-                if text_edit_response.has_focus()
-                    && ui.input(|i| i.key_pressed(egui::Key::ArrowUp))
-                    && state.history.len() > 1
-                    && state.history_index < state.history.len() - 1
-                {
-                    if state.history_index == 0 && !state.buf.trim().is_empty() {
-                        *state.history.get_mut(0).unwrap() = state.buf.clone().into();
-                    }
+            // Docked panels slide with egui's own panel animation, which takes the style's
+            // animation time
+            if config.slide_duration > 0.0 {
+                let style = ctx.style();
+                ctx.style_mut(|style| style.animation_time = config.slide_duration);
+                panel.show_animated(ctx, !closing, contents);
+                ctx.set_style(style);
+            } else {
+                panel.show(ctx, contents);
+            }
+        }
+    }
+
+    // Keep the geometry set by the user across close/open cycles
+    if movable {
+        if let Some(rect) = ctx.memory(|memory| memory.area_rect(window_id)) {
+            if config.left_pos != rect.min.x || config.top_pos != rect.min.y {
+                config.left_pos = rect.min.x;
+                config.top_pos = rect.min.y;
+            }
+        }
+    }
+    let resized = config.resizable
+        && config.layout != ConsoleLayout::Mini
+        && relative_size.is_none()
+        && shown >= 1.0;
+    if let Some(size) = content_size.filter(|_| resized) {
+        let size = size / config.ui_scale;
+        if floating && config.width != size.x {
+            config.width = size.x;
+        }
+        if config.height != size.y {
+            config.height = size.y;
+        }
+    }
+}
+
+/// Splits the input line into the words before the one being typed, and the word being typed.
+///
+/// The word being typed is empty if the input ends with a space. The value of a `--flag=value`
+/// word is completed on its own, with the flag as the last word before it.
+fn split_completion(buf: &str) -> (Vec<&str>, &str) {
+    let before = buf.trim_end_matches(|c: char| !c.is_whitespace());
+    let mut path = before.split_whitespace().collect::<Vec<_>>();
+    let word = &buf[before.len()..];
+    match word.split_once('=') {
+        Some((flag, value)) if !path.is_empty() && flag.starts_with("--") => {
+            path.push(flag);
+            (path, value)
+        }
+        _ => (path, word),
+    }
+}
+
+/// Names starting with the word being typed, or fuzzily matching it if none do.
+///
+/// `names` are those which can follow the words before it, see [`argument_names`].
+fn command_suggestions<'a>(names: &'a Candidates, buf: &str, max: usize) -> Vec<&'a str> {
+    let (_, typed) = split_completion(buf);
+    names.suggestions(typed, max)
+}
 
-                    state.history_index += 1;
-                    let previous_item = state.history.get(state.history_index).unwrap().clone();
-                    state.buf = previous_item.to_string();
+/// Names which can be completed after the command and arguments `path` in the input line, those
+/// known to clap, see [`argument_completions`].
+///
+/// The names at the start of the line are in the [`CompletionIndex`] instead.
+fn argument_names<M: ConsoleMarker>(config: &ConsoleConfiguration<M>, path: &[&str]) -> Candidates {
+    let Some((name, args)) = path.split_first() else {
+        return Candidates::default();
+    };
+    let names = config
+        .resolve_command(name)
+        .and_then(|name| config.commands.get(name))
+        .map(|registered| argument_completions(&registered.command, args))
+        .unwrap_or_default();
+    Candidates::new(names)
+}
 
-                    set_cursor_pos(ui.ctx(), text_edit_response.id, state.buf.len());
-                } else if text_edit_response.has_focus()
-                    && ui.input(|i| i.key_pressed(egui::Key::ArrowDown))
-                    && state.history_index > 0
-                {
-                    state.history_index -= 1;
-                    let next_item = state.history.get(state.history_index).unwrap().clone();
-                    state.buf = next_item.to_string();
+/// Subcommands, flags and possible values of `command` which can follow the arguments `args`.
+///
+/// After a flag taking a value only its possible values are completed, like those of a
+/// [`clap::ValueEnum`].
+fn argument_completions(command: &clap::Command, args: &[&str]) -> Vec<String> {
+    let (command, positionals, value_of) = walk_arguments(command, args);
+    if let Some(flag) = value_of {
+        return possible_values(flag);
+    }
+    let mut names = Vec::new();
+    if positionals == 0 {
+        let subcommands = command
+            .get_subcommands()
+            .filter(|subcommand| !subcommand.is_hide_set())
+            .map(|subcommand| subcommand.get_name().to_owned());
+        names.extend(subcommands);
+    }
+    // Arguments taking several values complete all of them
+    let positional = command.get_positionals().nth(positionals).or_else(|| {
+        command
+            .get_positionals()
+            .last()
+            .filter(|arg| takes_many(arg))
+    });
+    names.extend(positional.map(possible_values).unwrap_or_default());
+    for flag in command.get_arguments() {
+        if flag.is_positional() || flag.is_hide_set() {
+            continue;
+        }
+        match (flag.get_long(), flag.get_short()) {
+            (Some(long), _) => names.push(format!("--{long}")),
+            (None, Some(short)) => names.push(format!("-{short}")),
+            (None, None) => {}
+        }
+    }
+    names
+}
+
+/// Follows the arguments `args` through the subcommands of `command`.
+///
+/// Returns the innermost subcommand, the number of positional arguments given to it, and the
+/// flag the next argument is a value of, if any.
+fn walk_arguments<'a>(
+    command: &'a clap::Command,
+    args: &[&str],
+) -> (&'a clap::Command, usize, Option<&'a clap::Arg>) {
+    let mut command = command;
+    let mut positionals = 0;
+    let mut value_of = None;
+    for arg in args {
+        if value_of.take().is_some() {
+            continue;
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            value_of = command
+                .get_arguments()
+                .find(|flag| flag.get_long() == Some(long));
+        } else if let Some(short) = arg.strip_prefix('-').filter(|short| !short.is_empty()) {
+            // The value of short flags can follow them directly, like `-n5`
+            let mut shorts = short.chars();
+            let flag = shorts.next().and_then(|short| {
+                command
+                    .get_arguments()
+                    .find(|flag| flag.get_short() == Some(short))
+            });
+            value_of = flag.filter(|_| shorts.next().is_none());
+        } else if let Some(subcommand) = command.find_subcommand(arg).filter(|_| positionals == 0) {
+            command = subcommand;
+        } else {
+            positionals += 1;
+        }
+        value_of = value_of.filter(|flag| flag.get_action().takes_values());
+    }
+    (command, positionals, value_of)
+}
 
-                    set_cursor_pos(ui.ctx(), text_edit_response.id, state.buf.len());
+/// Placeholders of the arguments still expected after the input line, like `<x> <y> [z]`.
+///
+/// Hidden once all required positional arguments are typed. While a flag is typed, the flags
+/// starting with it are shown instead, with placeholders of their values.
+fn argument_hint<M: ConsoleMarker>(config: &ConsoleConfiguration<M>, buf: &str) -> Option<String> {
+    let (path, typed) = split_completion(buf);
+    let (name, args) = path.split_first()?;
+    let registered = config
+        .resolve_command(name)
+        .and_then(|name| config.commands.get(name))?;
+    let hint = command_argument_hint(&registered.command, args, typed)?;
+    let separator = if buf.ends_with(char::is_whitespace) {
+        ""
+    } else {
+        " "
+    };
+    Some(format!("{separator}{hint}"))
+}
+
+fn command_argument_hint(command: &clap::Command, args: &[&str], typed: &str) -> Option<String> {
+    let (command, positionals, value_of) = walk_arguments(command, args);
+    if value_of.is_some() {
+        return None;
+    }
+
+    let placeholders = if typed.starts_with('-') {
+        command
+            .get_arguments()
+            .filter(|flag| !flag.is_positional() && !flag.is_hide_set())
+            .filter_map(|flag| {
+                let name = match (flag.get_long(), flag.get_short()) {
+                    (Some(long), _) => format!("--{long}"),
+                    (None, Some(short)) => format!("-{short}"),
+                    (None, None) => return None,
+                };
+                if !name.starts_with(typed) || name == typed {
+                    return None;
                 }
+                Some(if flag.get_action().takes_values() {
+                    format!("{name} <{}>", flag.get_id())
+                } else {
+                    name
+                })
+            })
+            .collect::<Vec<_>>()
+    } else {
+        // The word being typed fills a positional argument
+        let filled = positionals + usize::from(!typed.is_empty());
+        let remaining = command.get_positionals().skip(filled).collect::<Vec<_>>();
+        if !remaining.iter().any(|arg| arg.is_required_set()) {
+            return None;
+        }
+        remaining.into_iter().map(placeholder).collect()
+    };
+    (!placeholders.is_empty()).then(|| placeholders.join(" "))
+}
 
-                // Focus on input
-                ui.memory_mut(|m| m.request_focus(text_edit_response.id));
+/// Placeholder of an argument value, `<name>` if it's required and `[name]` if it's optional.
+fn placeholder(arg: &clap::Arg) -> String {
+    let name = arg.get_id().as_str();
+    let many = if takes_many(arg) { "..." } else { "" };
+    if arg.is_required_set() {
+        format!("<{name}>{many}")
+    } else {
+        format!("[{name}]{many}")
+    }
+}
+
+/// Whether an argument can be given several values, e.g. a `Vec` field of a derived command.
+fn takes_many(arg: &clap::Arg) -> bool {
+    matches!(arg.get_action(), clap::ArgAction::Append)
+        || arg
+            .get_num_args()
+            .is_some_and(|range| range.max_values() > 1)
+}
+
+/// Possible values of an argument which aren't hidden.
+fn possible_values(arg: &clap::Arg) -> Vec<String> {
+    arg.get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_owned())
+        .collect()
+}
+
+/// Rest of the word being typed, preferring the most recently used matching command.
+fn ghost_completion<'a>(
+    names: &'a Candidates,
+    history: &VecDeque<StyledStr>,
+    buf: &str,
+) -> Option<&'a str> {
+    let (path, typed) = split_completion(buf);
+    if typed.is_empty() {
+        return None;
+    }
+
+    let completes = |name: &&str| name.len() > typed.len() && name.starts_with(typed);
+    // The first history entry is the input line itself, only commands are looked up in it
+    let recent = history.iter().skip(1).find_map(|entry| {
+        if !path.is_empty() {
+            return None;
+        }
+        let entry = entry.to_string();
+        let name = entry.split_whitespace().next()?;
+        names.get(name).filter(completes)
+    });
+
+    recent
+        .or_else(|| names.starting_with(typed).find(completes))
+        .map(|name| &name[typed.len()..])
+}
+
+/// Whether the caret of a text edit is at the end of `text` without a selection.
+fn caret_at_end(state: Option<egui::text_edit::TextEditState>, text: &str) -> bool {
+    state
+        .and_then(|state| state.ccursor_range())
+        .is_none_or(|range| {
+            range.primary == range.secondary && range.primary.index == text.chars().count()
+        })
+}
+
+/// Command suggestions shown above the input line.
+struct SuggestionPopup<'a> {
+    id: Id,
+    /// Where the input line is, the popup is placed right above it
+    input_rect: Rect,
+    suggestions: &'a [&'a str],
+    /// Index of the highlighted suggestion
+    selected: usize,
+}
+
+/// Shows the command suggestions in a popup above the input line, without taking focus.
+fn suggestions_ui<M: ConsoleMarker>(
+    ctx: &Context,
+    popup: &SuggestionPopup,
+    config: &ConsoleConfiguration<M>,
+    cvars: &ConsoleVariables,
+    font_id: &FontId,
+) {
+    egui::Area::new(popup.id)
+        .order(egui::Order::Foreground)
+        .fixed_pos(popup.input_rect.left_top())
+        .pivot(egui::Align2::LEFT_BOTTOM)
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style())
+                .fill(config.theme.background)
+                .stroke(Stroke::new(config.border_width, config.theme.border))
+                .rounding(config.rounding)
+                .show(ui, |ui| {
+                    for (i, name) in popup.suggestions.iter().enumerate() {
+                        let fill = if i == popup.selected {
+                            config.theme.echo_background
+                        } else {
+                            Color32::TRANSPARENT
+                        };
+                        egui::Frame::none().fill(fill).show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    egui::RichText::new(*name)
+                                        .font(font_id.clone())
+                                        .color(config.theme.input),
+                                );
+                                // Variables show their value instead of a description
+                                let about = match config.commands.get(*name) {
+                                    Some(registered) => {
+                                        registered.command.get_about().map(ToString::to_string)
+                                    }
+                                    None => cvars.display(name).map(|value| format!("= {value}")),
+                                };
+                                if let Some(about) = about {
+                                    ui.label(
+                                        egui::RichText::new(about).font(font_id.clone()).color(
+                                            config
+                                                .theme
+                                                .text
+                                                .gamma_multiply(SECONDARY_TEXT_OPACITY),
+                                        ),
+                                    );
+                                }
+                            });
+                        });
+                    }
+                });
+        });
+}
+
+/// The number of a number key, excluding zero.
+fn digit_key(key: KeyCode) -> Option<usize> {
+    let digit = match key {
+        KeyCode::Key1 | KeyCode::Numpad1 => 1,
+        KeyCode::Key2 | KeyCode::Numpad2 => 2,
+        KeyCode::Key3 | KeyCode::Numpad3 => 3,
+        KeyCode::Key4 | KeyCode::Numpad4 => 4,
+        KeyCode::Key5 | KeyCode::Numpad5 => 5,
+        KeyCode::Key6 | KeyCode::Numpad6 => 6,
+        KeyCode::Key7 | KeyCode::Numpad7 => 7,
+        KeyCode::Key8 | KeyCode::Numpad8 => 8,
+        KeyCode::Key9 | KeyCode::Numpad9 => 9,
+        _ => return None,
+    };
+    Some(digit)
+}
+
+/// Whether `key` was pressed without modifiers, like [`egui::InputState::consume_key`].
+fn consume_key(input: &mut egui::InputState, key: KeyCode) -> bool {
+    egui_key(key).is_some_and(|key| input.consume_key(egui::Modifiers::NONE, key))
+}
+
+/// Whether `key` was pressed, like [`egui::InputState::key_pressed`].
+fn key_pressed(input: &egui::InputState, key: KeyCode) -> bool {
+    egui_key(key).is_some_and(|key| input.key_pressed(key))
+}
+
+/// The egui key of a [`KeyCode`], `None` for keys egui doesn't know.
+fn egui_key(key: KeyCode) -> Option<egui::Key> {
+    let key = match key {
+        KeyCode::Down => egui::Key::ArrowDown,
+        KeyCode::Left => egui::Key::ArrowLeft,
+        KeyCode::Right => egui::Key::ArrowRight,
+        KeyCode::Up => egui::Key::ArrowUp,
+        KeyCode::Escape => egui::Key::Escape,
+        KeyCode::Tab => egui::Key::Tab,
+        KeyCode::Back => egui::Key::Backspace,
+        KeyCode::Return | KeyCode::NumpadEnter => egui::Key::Enter,
+        KeyCode::Space => egui::Key::Space,
+        KeyCode::Insert => egui::Key::Insert,
+        KeyCode::Delete => egui::Key::Delete,
+        KeyCode::Home => egui::Key::Home,
+        KeyCode::End => egui::Key::End,
+        KeyCode::PageUp => egui::Key::PageUp,
+        KeyCode::PageDown => egui::Key::PageDown,
+        KeyCode::Minus | KeyCode::NumpadSubtract => egui::Key::Minus,
+        KeyCode::Equals | KeyCode::NumpadAdd => egui::Key::PlusEquals,
+        KeyCode::Key0 | KeyCode::Numpad0 => egui::Key::Num0,
+        KeyCode::Key1 | KeyCode::Numpad1 => egui::Key::Num1,
+        KeyCode::Key2 | KeyCode::Numpad2 => egui::Key::Num2,
+        KeyCode::Key3 | KeyCode::Numpad3 => egui::Key::Num3,
+        KeyCode::Key4 | KeyCode::Numpad4 => egui::Key::Num4,
+        KeyCode::Key5 | KeyCode::Numpad5 => egui::Key::Num5,
+        KeyCode::Key6 | KeyCode::Numpad6 => egui::Key::Num6,
+        KeyCode::Key7 | KeyCode::Numpad7 => egui::Key::Num7,
+        KeyCode::Key8 | KeyCode::Numpad8 => egui::Key::Num8,
+        KeyCode::Key9 | KeyCode::Numpad9 => egui::Key::Num9,
+        KeyCode::A => egui::Key::A,
+        KeyCode::B => egui::Key::B,
+        KeyCode::C => egui::Key::C,
+        KeyCode::D => egui::Key::D,
+        KeyCode::E => egui::Key::E,
+        KeyCode::F => egui::Key::F,
+        KeyCode::G => egui::Key::G,
+        KeyCode::H => egui::Key::H,
+        KeyCode::I => egui::Key::I,
+        KeyCode::J => egui::Key::J,
+        KeyCode::K => egui::Key::K,
+        KeyCode::L => egui::Key::L,
+        KeyCode::M => egui::Key::M,
+        KeyCode::N => egui::Key::N,
+        KeyCode::O => egui::Key::O,
+        KeyCode::P => egui::Key::P,
+        KeyCode::Q => egui::Key::Q,
+        KeyCode::R => egui::Key::R,
+        KeyCode::S => egui::Key::S,
+        KeyCode::T => egui::Key::T,
+        KeyCode::U => egui::Key::U,
+        KeyCode::V => egui::Key::V,
+        KeyCode::W => egui::Key::W,
+        KeyCode::X => egui::Key::X,
+        KeyCode::Y => egui::Key::Y,
+        KeyCode::Z => egui::Key::Z,
+        KeyCode::F1 => egui::Key::F1,
+        KeyCode::F2 => egui::Key::F2,
+        KeyCode::F3 => egui::Key::F3,
+        KeyCode::F4 => egui::Key::F4,
+        KeyCode::F5 => egui::Key::F5,
+        KeyCode::F6 => egui::Key::F6,
+        KeyCode::F7 => egui::Key::F7,
+        KeyCode::F8 => egui::Key::F8,
+        KeyCode::F9 => egui::Key::F9,
+        KeyCode::F10 => egui::Key::F10,
+        KeyCode::F11 => egui::Key::F11,
+        KeyCode::F12 => egui::Key::F12,
+        KeyCode::F13 => egui::Key::F13,
+        KeyCode::F14 => egui::Key::F14,
+        KeyCode::F15 => egui::Key::F15,
+        KeyCode::F16 => egui::Key::F16,
+        KeyCode::F17 => egui::Key::F17,
+        KeyCode::F18 => egui::Key::F18,
+        KeyCode::F19 => egui::Key::F19,
+        KeyCode::F20 => egui::Key::F20,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Text of the status bar, leaving out the FPS without frame time diagnostics.
+fn status_text<M: ConsoleMarker>(
+    config: &ConsoleConfiguration<M>,
+    state: &ConsoleState<M>,
+    fps: Option<f64>,
+) -> String {
+    let localization = &config.localization;
+    let mut parts = vec![
+        localization
+            .status_commands
+            .fill(config.commands.len(), &[]),
+        fill(
+            &localization.status_lines,
+            &[
+                ("lines", &state.scrollback.len()),
+                ("max", &config.scrollback_size),
+            ],
+        ),
+    ];
+    // The first history entry is the input line itself
+    if state.history_index > 0 {
+        parts.push(fill(
+            &localization.status_history,
+            &[
+                ("entry", &state.history_index),
+                ("count", &(state.history.len() - 1)),
+            ],
+        ));
+    }
+    if let Some(fps) = fps {
+        parts.push(fill(
+            &localization.status_fps,
+            &[("fps", &format!("{fps:.0}"))],
+        ));
+    }
+    parts.join(" | ")
+}
+
+/// Resolves the anchored position and the relative content size of the console window.
+///
+/// Either is `None` if the configuration uses absolute coordinates for it.
+fn window_layout<M: ConsoleMarker>(
+    config: &ConsoleConfiguration<M>,
+    screen: Rect,
+) -> (Option<Pos2>, Option<Vec2>) {
+    let margin = config.inner_margin * config.ui_scale * 2.0;
+    let stretch = matches!(
+        config.anchor,
+        Some(ConsoleAnchor::TopStretch | ConsoleAnchor::BottomStretch)
+    );
+
+    let width = if stretch {
+        Some(screen.width())
+    } else {
+        config
+            .width_percent
+            .map(|percent| screen.width() * percent / 100.0)
+    };
+    let height = config
+        .height_percent
+        .map(|percent| screen.height() * percent / 100.0);
+    let outer_size = vec2(
+        width.unwrap_or(config.width * config.ui_scale + margin),
+        height.unwrap_or(config.height * config.ui_scale + margin),
+    );
+
+    let pos = config.anchor.map(|anchor| match anchor {
+        ConsoleAnchor::TopLeft | ConsoleAnchor::TopStretch => screen.left_top(),
+        ConsoleAnchor::TopRight => screen.right_top() - vec2(outer_size.x, 0.0),
+        ConsoleAnchor::BottomLeft | ConsoleAnchor::BottomStretch => {
+            screen.left_bottom() - vec2(0.0, outer_size.y)
+        }
+        ConsoleAnchor::BottomRight => screen.right_bottom() - outer_size,
+        ConsoleAnchor::Center => screen.center() - outer_size / 2.0,
+    });
+    let size = (width.is_some() || height.is_some()).then(|| outer_size - vec2(margin, margin));
+
+    (pos, size)
+}
+
+/// Shows the most recent scrollback lines in a read-only overlay, fading out old lines.
+pub(crate) fn console_overlay_ui(
+    mut egui_context: EguiContexts,
+    config: Res<ConsoleConfiguration>,
+    overlay: Res<ConsoleOverlay>,
+    mut state: ResMut<ConsoleState>,
+    time: Res<Time<Real>>,
+    windows: ConsoleWindows,
+) {
+    if !overlay.open {
+        return;
+    }
+
+    let now = time.elapsed().as_secs_f32();
+    let opacity = |line: &ScrollbackLine| match overlay.fade_after {
+        Some(fade_after) => {
+            let age = now - line.printed_at.as_secs_f32();
+            (1.0 - (age - fade_after) / overlay.fade_duration.max(f32::EPSILON)).clamp(0.0, 1.0)
+        }
+        None => 1.0,
+    };
+
+    if !state.scrollback.iter().any(|line| opacity(line) > 0.0) {
+        return;
+    }
+
+    let Some(ctx) = console_window(&config, &windows)
+        .and_then(|window| egui_context.try_ctx_for_window_mut(window))
+    else {
+        return;
+    };
+    let font_id = console_font(ctx, &config);
+
+    egui::Area::new(Id::new("console_overlay"))
+        .fixed_pos([overlay.left_pos, overlay.top_pos])
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::none()
+                .fill(config.theme.background)
+                .rounding(config.rounding)
+                .inner_margin(config.inner_margin * config.ui_scale)
+                .show(ui, |ui| {
+                    ui.set_width(overlay.width);
+                    ScrollArea::vertical()
+                        .id_source("console_overlay_scroll")
+                        .max_height(overlay.height)
+                        .stick_to_bottom(true)
+                        .enable_scrolling(false)
+                        .show(ui, |ui| {
+                            for line in state.scrollback.iter_mut() {
+                                let opacity = opacity(line);
+                                if opacity <= 0.0 {
+                                    continue;
+                                }
+
+                                let wrap_width = config.wrap_lines.then(|| wrap_width(ui));
+                                let layout = line_layout(line, &font_id, &config);
+                                if opacity >= 1.0 {
+                                    let rows = layout.rows(ui, wrap_width);
+                                    rows_ui(ui, rows, config.text_direction);
+                                    continue;
+                                }
+
+                                // Fading lines are laid out again each frame in their color
+                                let mut job = layout.job.clone();
+                                for section in &mut job.sections {
+                                    section.format.color =
+                                        section.format.color.gamma_multiply(opacity);
+                                    section.format.background =
+                                        section.format.background.gamma_multiply(opacity);
+                                }
+                                let rows = layout_rows(ui, &job, wrap_width);
+                                rows_ui(ui, &rows, config.text_direction);
+                            }
+                        });
+                });
+        });
+}
+
+/// Space around the text of the input line
+const INPUT_MARGIN: egui::Vec2 = vec2(4.0, 2.0);
+
+/// Height of the separator between the scrollback and the input line
+const SEPARATOR_SPACING: f32 = 6.0;
+
+/// Indent of the continuation rows of a wrapped scrollback line
+const WRAP_INDENT: f32 = 16.0;
+
+/// Font size of the status bar relative to the console font
+const STATUS_BAR_SCALE: f32 = 0.8;
+
+/// Opacity of secondary text, like the status bar and command descriptions, relative to the
+/// console text
+const SECONDARY_TEXT_OPACITY: f32 = 0.6;
+
+/// Seconds a programmatic scroll of the scrollback takes with smooth scrolling
+const SCROLL_ANIMATION_TIME: f32 = 0.15;
+
+/// Scales mouse wheel scrolling over the scrollback by the configured scroll speed.
+///
+/// egui has no per scroll area speed, so the scroll events are scaled before egui sees them.
+pub(crate) fn scale_console_scroll<M: ConsoleMarker>(
+    config: Res<ConsoleConfiguration<M>>,
+    state: Res<ConsoleState<M>>,
+    windows: ConsoleWindows,
+    mut egui_inputs: Query<&mut EguiInput>,
+) {
+    if !state.scrollback_hovered || config.scroll_speed == 1.0 {
+        return;
+    }
+
+    let Some(mut egui_input) =
+        console_window(&config, &windows).and_then(|window| egui_inputs.get_mut(window).ok())
+    else {
+        return;
+    };
+    for event in egui_input.events.iter_mut() {
+        if let egui::Event::Scroll(delta) = event {
+            *delta *= config.scroll_speed;
+        }
+    }
+}
+
+/// Resources the input line is resolved and completed with.
+#[derive(SystemParam)]
+pub(crate) struct InputResources<'w, M: ConsoleMarker> {
+    commands: CommandResources<'w>,
+    completion: Res<'w, CompletionIndex<M>>,
+    shortcuts: Res<'w, ConsoleShortcuts>,
+}
+
+/// Windows the console can be shown in.
+pub(crate) type ConsoleWindows<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Window,
+        Option<&'static Name>,
+        Has<PrimaryWindow>,
+    ),
+>;
+
+/// Finds the window the console is shown in, falling back to the primary window if the
+/// configured one doesn't exist.
+fn console_window<M: ConsoleMarker>(
+    config: &ConsoleConfiguration<M>,
+    windows: &ConsoleWindows,
+) -> Option<Entity> {
+    let primary = || {
+        windows
+            .iter()
+            .find(|(_, _, _, primary)| *primary)
+            .map(|(entity, ..)| entity)
+    };
+
+    let target = match &config.window {
+        ConsoleWindowTarget::Primary => None,
+        ConsoleWindowTarget::Entity(entity) => windows.get(*entity).ok().map(|(entity, ..)| entity),
+        ConsoleWindowTarget::Named(name) => windows
+            .iter()
+            .find(|(_, window, window_name, _)| {
+                window_name.map_or(window.title == *name, |window_name| {
+                    window_name.as_str() == name
+                })
+            })
+            .map(|(entity, ..)| entity),
+    };
+
+    target.or_else(primary)
+}
+
+/// Shows the scrollback, scrolled by `pages` or, if the console was just `opened`, as
+/// [`ConsoleConfiguration::scroll_to_bottom_on_open`] says.
+///
+/// Without line wrapping every line has a known height, so only the visible lines are laid out.
+/// While the user is scrolled away from the bottom new lines don't move the view, instead a
+/// button showing the number of new lines jumps back down.
+fn scrollback_ui<M: ConsoleMarker>(
+    ui: &mut egui::Ui,
+    state: &mut ConsoleState<M>,
+    config: &ConsoleConfiguration<M>,
+    font_id: &FontId,
+    scroll_height: f32,
+    opened: bool,
+    pages: i32,
+) {
+    let jump_to_bottom = opened && config.scroll_to_bottom_on_open;
+    let restore_offset = opened && !config.scroll_to_bottom_on_open;
+    let jump_to_bottom = jump_to_bottom || mem::take(&mut state.scroll_to_bottom);
+    if jump_to_bottom {
+        state.scrolled_up = false;
+        state.scroll_target = Some(f32::INFINITY);
+    }
+
+    // Following the bottom is left to `stick_to_bottom`, so lines printed while closed still show
+    let restore_offset = restore_offset && !jump_to_bottom && state.scrolled_up;
+
+    // Scroll by a page with the keyboard
+    if pages != 0 {
+        let from = state
+            .scroll_target
+            .unwrap_or(state.scroll_offset)
+            .min(state.max_scroll_offset);
+        let target = from + pages as f32 * scroll_height;
+        state.scroll_target = Some(target.clamp(0.0, state.max_scroll_offset));
+        if pages < 0 && !state.scrolled_up {
+            state.scrolled_up = true;
+            state.seen_lines = state.scrollback.len();
+        }
+    }
+
+    // Without wrapping every line has a known height, so the exact bottom is known up front
+    let row_height = ui.fonts(|fonts| fonts.row_height(font_id)) + ui.spacing().item_spacing.y;
+    let content_height = if config.wrap_lines {
+        None
+    } else {
+        let total_rows = state.scrollback.iter().map(|line| line.rows).sum::<usize>();
+        Some(row_height * total_rows as f32)
+    };
+    let bottom_offset = content_height.map_or(state.max_scroll_offset, |content_height| {
+        (content_height - scroll_height).max(0.0)
+    });
+
+    // Programmatic scrolling animates towards its target, ending once the target is reached
+    let target = state.scroll_target.map(|target| target.min(bottom_offset));
+    let animation_time = if target.is_some() && config.smooth_scrolling {
+        SCROLL_ANIMATION_TIME
+    } else {
+        0.0
+    };
+    let animated_offset = ui.ctx().animate_value_with_time(
+        console_id::<M>("console_scroll_animation"),
+        target.unwrap_or(state.scroll_offset),
+        animation_time,
+    );
+    let settled = target.is_some_and(|target| (animated_offset - target).abs() < 0.5);
+    let snap_to_bottom = settled && state.scroll_target.is_some_and(f32::is_infinite);
+
+    // Without wrapping long lines are panned horizontally instead
+    let scroll_area = if config.wrap_lines {
+        ScrollArea::vertical()
+    } else {
+        ScrollArea::both()
+    };
+    let scroll_area = scroll_area
+        .auto_shrink([false, false])
+        .stick_to_bottom(!state.scrolled_up)
+        .max_height(scroll_height);
+    let scroll_area = if target.is_some() {
+        scroll_area.vertical_scroll_offset(animated_offset)
+    } else if restore_offset {
+        scroll_area.vertical_scroll_offset(state.scroll_offset)
+    } else {
+        scroll_area
+    };
+
+    let (offset, inner_rect, content_size) = if config.wrap_lines {
+        let output = scroll_area.show(ui, |ui| {
+            ui.vertical(|ui| {
+                for line in state.scrollback.iter_mut() {
+                    let wrap_width = wrap_width(ui);
+                    let rows = line_layout(line, font_id, config).rows(ui, Some(wrap_width));
+                    let response = rows_ui(ui, rows, config.text_direction);
+                    expand_on_click(line, &response);
+                }
             });
+
+            // The bottom of wrapped lines is only known after laying them out
+            if snap_to_bottom {
+                ui.scroll_to_cursor(Some(Align::BOTTOM));
+            }
+        });
+        (output.state.offset, output.inner_rect, output.content_size)
+    } else {
+        let content_height = content_height.unwrap_or_default();
+        let output = scroll_area.show_viewport(ui, |ui, viewport| {
+            ui.set_height(content_height);
+
+            let first_visible_row = (viewport.min.y / row_height).floor().max(0.0) as usize;
+            let last_visible_row = (viewport.max.y / row_height).ceil().max(0.0) as usize;
+            let origin = ui.max_rect().left_top();
+            let width = ui.max_rect().width();
+
+            let mut row = 0;
+            for line in state.scrollback.iter_mut() {
+                if row > last_visible_row {
+                    break;
+                }
+
+                if row + line.rows > first_visible_row {
+                    let rect = Rect::from_min_size(
+                        origin + vec2(0.0, row as f32 * row_height),
+                        vec2(width, line.rows as f32 * row_height),
+                    );
+                    let response = ui
+                        .allocate_ui_at_rect(rect, |ui| {
+                            let rows = line_layout(line, font_id, config).rows(ui, None);
+                            rows_ui(ui, rows, config.text_direction)
+                        })
+                        .inner;
+                    expand_on_click(line, &response);
+                }
+
+                row += line.rows;
+            }
         });
+        (output.state.offset, output.inner_rect, output.content_size)
+    };
+
+    state.scroll_offset = offset.y;
+    state.max_scroll_offset = (content_size.y - inner_rect.height()).max(0.0);
+    state.scrollback_hovered = ui.rect_contains_pointer(inner_rect);
+    if settled {
+        state.scroll_target = None;
+    }
+
+    ui.interact(
+        inner_rect,
+        console_id::<M>("console_scrollback"),
+        Sense::click(),
+    )
+    .context_menu(|ui| {
+        if ui.button(&config.localization.copy_scrollback).clicked() {
+            let text = state.scrollback_text(state.scrollback.len());
+            ui.ctx().output_mut(|output| output.copied_text = text);
+            ui.close_menu();
+        }
+    });
+
+    let at_bottom = offset.y + inner_rect.height() >= content_size.y - 1.0;
+    if at_bottom {
+        state.scrolled_up = false;
+    } else if !state.scrolled_up && state.scroll_target.is_none() {
+        state.scrolled_up = true;
+        state.seen_lines = state.scrollback.len();
+    }
+
+    let new_lines = state.scrollback.len().saturating_sub(state.seen_lines);
+    if state.scrolled_up && new_lines > 0 {
+        let jump_clicked = egui::Area::new(console_id::<M>("console_new_lines"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(inner_rect.right_bottom() - vec2(8.0, 8.0))
+            .pivot(egui::Align2::RIGHT_BOTTOM)
+            .show(ui.ctx(), |ui| {
+                let text = config.localization.new_lines.fill(new_lines, &[]);
+                ui.button(text).clicked()
+            })
+            .inner;
+
+        if jump_clicked {
+            state.scroll_to_bottom = true;
+        }
+    }
+}
+
+/// Cached layout of a scrollback line.
+///
+/// The text of a line doesn't change once printed, so the job is only built again when the
+/// repeat count or the expansion of the line changes, and its rows when the width they wrap to
+/// does.
+pub(crate) struct LineLayout {
+    /// Repeat count of the line the job was built for
+    count: usize,
+    /// Whether the job was built for the expanded line
+    expanded: bool,
+    job: LayoutJob,
+    rows: Option<LineRows>,
+}
+
+/// Laid out rows of a line, with the width they were wrapped to if any.
+struct LineRows {
+    wrap_width: Option<f32>,
+    rows: Vec<LayoutRow>,
+}
+
+/// A laid out row of a line, split into the text between links and the links.
+struct LayoutRow {
+    indent: f32,
+    /// Laid out text, with the URL it opens if it's a link
+    segments: Vec<(Arc<Galley>, Option<String>)>,
+}
+
+impl LineLayout {
+    /// Returns the rows of the line wrapped to `wrap_width` if set, laying them out on a miss.
+    fn rows(&mut self, ui: &egui::Ui, wrap_width: Option<f32>) -> &[LayoutRow] {
+        if self
+            .rows
+            .as_ref()
+            .is_some_and(|rows| rows.wrap_width != wrap_width)
+        {
+            self.rows = None;
+        }
+        let job = &self.job;
+        &self
+            .rows
+            .get_or_insert_with(|| LineRows {
+                wrap_width,
+                rows: layout_rows(ui, job, wrap_width),
+            })
+            .rows
+    }
+}
+
+impl LayoutRow {
+    fn width(&self) -> f32 {
+        self.segments
+            .iter()
+            .map(|(galley, _)| galley.size().x)
+            .sum()
+    }
+}
+
+/// Width scrollback lines wrap to, leaving room for the indent of continuation rows.
+fn wrap_width(ui: &egui::Ui) -> f32 {
+    (ui.available_width() - WRAP_INDENT).max(WRAP_INDENT)
+}
+
+/// Lays out the rows of a scrollback line, wrapped to `wrap_width` if set.
+///
+/// Continuation rows of a wrapped line are indented to set them apart from new lines. URLs are
+/// found in the whole line, so those wrapping onto the next row stay whole links.
+fn layout_rows(ui: &egui::Ui, job: &LayoutJob, wrap_width: Option<f32>) -> Vec<LayoutRow> {
+    let mut ranges = vec![(0..job.text.len(), 0.0)];
+    if let Some(wrap_width) = wrap_width {
+        let mut wrapped_job = job.clone();
+        wrapped_job.wrap.max_width = wrap_width;
+        let galley = ui.fonts(|fonts| fonts.layout_job(wrapped_job));
+
+        if galley.rows.len() > 1 {
+            ranges.clear();
+            let mut row_start = 0;
+            let mut continuation = false;
+            for row in &galley.rows {
+                let row_end = job.text[row_start..]
+                    .char_indices()
+                    .nth(row.char_count_including_newline())
+                    .map_or(job.text.len(), |(offset, _)| row_start + offset);
+                let content_end = if row.ends_with_newline {
+                    row_end - 1
+                } else {
+                    row_end
+                };
+
+                let indent = if continuation { WRAP_INDENT } else { 0.0 };
+                ranges.push((row_start..content_end, indent));
+
+                continuation = !row.ends_with_newline;
+                row_start = row_end;
+            }
+        }
+    }
+
+    let urls = find_urls(&job.text);
+    let layout = |range| ui.fonts(|fonts| fonts.layout_job(slice_layout(job, range)));
+    ranges
+        .into_iter()
+        .map(|(row, indent)| {
+            let mut segments = Vec::new();
+            let mut segment_start = row.start;
+            for RowLink { range, url } in row_links(&job.text, row.clone(), &urls) {
+                let link = row.start + range.start..row.start + range.end;
+                if link.start > segment_start {
+                    segments.push((layout(segment_start..link.start), None));
+                }
+                segment_start = link.end;
+                segments.push((layout(link), Some(url)));
+            }
+            if segment_start < row.end || segments.is_empty() {
+                segments.push((layout(segment_start..row.end), None));
+            }
+            LayoutRow { indent, segments }
+        })
+        .collect()
+}
+
+/// Shows the laid out rows of a scrollback line.
+fn rows_ui(ui: &mut egui::Ui, rows: &[LayoutRow], direction: TextDirection) -> Response {
+    if let [row] = rows {
+        return row_ui(ui, row, direction);
+    }
+
+    ui.vertical(|ui| {
+        ui.spacing_mut().item_spacing.y = 0.0;
+        for row in rows {
+            row_ui(ui, row, direction);
+        }
+    })
+    .response
+}
+
+/// Shows a row of a scrollback line, aligned to the side the text direction starts on.
+fn row_ui(ui: &mut egui::Ui, row: &LayoutRow, direction: TextDirection) -> Response {
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+
+        let space = match direction {
+            TextDirection::LeftToRight => row.indent,
+            TextDirection::RightToLeft => {
+                (ui.available_width() - row.width() - row.indent).max(0.0)
+            }
+        };
+        if space > 0.0 {
+            ui.add_space(space);
+        }
+        for (galley, url) in &row.segments {
+            match url {
+                Some(url) => ui.add(Hyperlink::from_label_and_url(galley.clone(), url)),
+                None => ui.add(Label::new(galley.clone())),
+            };
+        }
+    })
+    .response
+}
+
+/// Shows the full text of a cut off line once it is clicked.
+fn expand_on_click(line: &mut ScrollbackLine, response: &Response) {
+    if line.truncated && response.interact(Sense::click()).clicked() {
+        line.expanded = true;
+    }
+}
+
+/// Part of a URL shown on a row, which opens the whole URL even if it wraps onto other rows.
+#[derive(Clone, Debug, PartialEq)]
+struct RowLink {
+    /// Byte range of the part in the row
+    range: Range<usize>,
+    /// The whole URL
+    url: String,
+}
+
+/// Returns the parts of the `urls` of a line shown on the row taking the `row` byte range of it.
+fn row_links(text: &str, row: Range<usize>, urls: &[Range<usize>]) -> Vec<RowLink> {
+    urls.iter()
+        .filter_map(|url| {
+            let start = url.start.max(row.start);
+            let end = url.end.min(row.end);
+            (start < end).then(|| RowLink {
+                range: start - row.start..end - row.start,
+                url: text[url.clone()].to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Finds the byte ranges of `http://` and `https://` URLs in `text`.
+fn find_urls(text: &str) -> Vec<Range<usize>> {
+    let mut urls = Vec::new();
+    let mut search_from = 0;
+
+    while let Some(offset) = text[search_from..].find("http") {
+        let start = search_from + offset;
+        let candidate = &text[start..];
+
+        if candidate.starts_with("http://") || candidate.starts_with("https://") {
+            let length = candidate
+                .find(|c: char| c.is_whitespace() || matches!(c, '"' | '<' | '>'))
+                .unwrap_or(candidate.len());
+            // Punctuation directly after a URL most likely belongs to the sentence around it
+            let url = candidate[..length].trim_end_matches(|c: char| ".,;:!?)]}'".contains(c));
+
+            if !url.ends_with("//") {
+                urls.push(start..start + url.len());
+                search_from = start + url.len();
+                continue;
+            }
+        }
+
+        search_from = start + "http".len();
+    }
+
+    urls
+}
+
+/// Copies the given byte range of a layout job, keeping the formatting of each section.
+fn slice_layout(job: &LayoutJob, range: Range<usize>) -> LayoutJob {
+    let mut slice = LayoutJob::default();
+    for section in &job.sections {
+        let start = section.byte_range.start.max(range.start);
+        let end = section.byte_range.end.min(range.end);
+        if start < end {
+            slice.append(&job.text[start..end], 0f32, section.format.clone());
+        }
+    }
+
+    // Keep the font of empty slices so they still take up a row
+    if slice.sections.is_empty() {
+        if let Some(section) = job.sections.first() {
+            slice.append("", 0f32, section.format.clone());
+        }
+    }
+
+    slice
+}
+
+/// Returns the cached layout of a scrollback line, building it if needed.
+fn line_layout<'a, M: ConsoleMarker>(
+    line: &'a mut ScrollbackLine,
+    font_id: &FontId,
+    config: &ConsoleConfiguration<M>,
+) -> &'a mut LineLayout {
+    let ScrollbackLine {
+        text,
+        count,
+        source,
+        kind,
+        expanded,
+        truncated,
+        layout,
+        ..
+    } = line;
+    let theme = &config.theme;
+    if layout
+        .as_ref()
+        .is_some_and(|layout| layout.count != *count || layout.expanded != *expanded)
+    {
+        *layout = None;
+    }
+    layout.get_or_insert_with(|| {
+        let color = match kind {
+            LineKind::Echo { .. } => theme.echo,
+            LineKind::Output => theme.text,
+        };
+        let text = text.ansi().to_string();
+        let (text, hidden_chars) = match truncate_ansi(&text, config.max_line_length) {
+            Some((kept, hidden_chars)) if !*expanded => (kept, hidden_chars),
+            _ => (text.as_str(), 0),
+        };
+        *truncated = hidden_chars > 0;
+
+        let prefix = match (&*kind, source) {
+            (LineKind::Echo { prompt }, _) => Some((prompt.clone(), color)),
+            (LineKind::Output, Some(source)) if config.show_sources => Some((
+                format!("[{source}] "),
+                theme.text.gamma_multiply(SECONDARY_TEXT_OPACITY),
+            )),
+            _ => None,
+        };
+
+        // The prefix is a separate run so it stays at the start side of the line
+        let mut job = LayoutJob::default();
+        match (prefix, config.text_direction) {
+            (Some((prefix, prefix_color)), TextDirection::LeftToRight) => {
+                let format = TextFormat::simple(font_id.clone(), prefix_color);
+                job.append(&prefix, 0f32, format);
+                append_ansi(&mut job, text, font_id, color, theme);
+            }
+            (Some((prefix, prefix_color)), TextDirection::RightToLeft) => {
+                append_ansi(&mut job, text, font_id, color, theme);
+                let format = TextFormat::simple(font_id.clone(), prefix_color);
+                job.append(&prefix, 0f32, format);
+            }
+            (None, _) => append_ansi(&mut job, text, font_id, color, theme),
+        }
+        if *count > 1 {
+            job.append(
+                &format!(" (x{count})"),
+                0f32,
+                TextFormat::simple(
+                    font_id.clone(),
+                    theme.text.gamma_multiply(SECONDARY_TEXT_OPACITY),
+                ),
+            );
+        }
+        if hidden_chars > 0 {
+            job.append(
+                &format!(" … (+{hidden_chars} chars, click to expand)"),
+                0f32,
+                TextFormat::simple(
+                    font_id.clone(),
+                    theme.text.gamma_multiply(SECONDARY_TEXT_OPACITY),
+                ),
+            );
+        }
+        if let LineKind::Echo { .. } = kind {
+            for section in &mut job.sections {
+                if section.format.background == Color32::TRANSPARENT {
+                    section.format.background = theme.echo_background;
+                }
+            }
+        }
+        LineLayout {
+            count: *count,
+            expanded: *expanded,
+            job,
+            rows: None,
+        }
+    })
+}
+
+/// Resolves the configured console font, falling back to monospace for unknown named families.
+fn console_font<M: ConsoleMarker>(ctx: &Context, config: &ConsoleConfiguration<M>) -> FontId {
+    let family = match &config.font {
+        FontChoice::Monospace => FontFamily::Monospace,
+        FontChoice::Proportional => FontFamily::Proportional,
+        FontChoice::Named(name) => {
+            let family = FontFamily::Name(name.as_str().into());
+            if ctx.fonts(|fonts| fonts.families().contains(&family)) {
+                family
+            } else {
+                FontFamily::Monospace
+            }
+        }
+    };
+
+    FontId::new(config.font_size * config.ui_scale, family)
 }
 
 fn console_key_pressed(
@@ -196,18 +1814,123 @@ fn console_key_pressed(
     false
 }
 
-fn set_cursor_pos(ctx: &Context, id: Id, pos: usize) {
+/// Id of an egui widget of the console `M`, told apart from the widgets of other consoles.
+fn console_id<M: ConsoleMarker>(name: &str) -> Id {
+    Id::new(name).with(type_name::<M>())
+}
+
+/// Moves the cursor of a text edit to the end of `text`.
+fn set_cursor_to_end(ctx: &Context, id: Id, text: &str) {
     if let Some(mut state) = TextEdit::load_state(ctx, id) {
-        state.set_ccursor_range(Some(CCursorRange::one(CCursor::new(pos))));
+        // Cursors index characters, not bytes
+        let end = CCursor::new(text.chars().count());
+        state.set_ccursor_range(Some(CCursorRange::one(end)));
         state.store(ctx, id);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use bevy::ecs::component::Tick;
     use bevy::input::ButtonState;
 
     use super::*;
+    use crate::dispatch::{parse_command_line, CommandLookup};
+    use crate::instance::DefaultConsole;
+    use crate::{CommandMeta, ConsoleAliases, ConsoleConfiguration, ConsoleGates};
+
+    /// Index of the commands and console variables, built right away.
+    fn completion_index(
+        config: &ConsoleConfiguration,
+        cvars: &ConsoleVariables,
+    ) -> CompletionIndex {
+        let mut index = CompletionIndex::default();
+        index.set_console_sources(config, cvars, [Tick::new(0); 2]);
+        index.index_all();
+        index
+    }
+
+    #[test]
+    fn test_find_urls() {
+        let text = "docs at https://bevyengine.org/learn. or (http://localhost:8080/report) here";
+
+        let urls = find_urls(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            urls,
+            vec![
+                "https://bevyengine.org/learn",
+                "http://localhost:8080/report"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_row_links_keep_wrapped_urls_whole() {
+        let text = "see https://bevyengine.org/learn now";
+        let urls = find_urls(text);
+        let url = "https://bevyengine.org/learn".to_owned();
+
+        // The line wraps in the middle of the URL
+        assert_eq!(
+            row_links(text, 0..17, &urls),
+            [RowLink {
+                range: 4..17,
+                url: url.clone()
+            }]
+        );
+        assert_eq!(
+            row_links(text, 17..text.len(), &urls),
+            [RowLink { range: 0..15, url }]
+        );
+        assert!(row_links(text, 0..4, &urls).is_empty());
+    }
+
+    #[test]
+    fn test_line_layout_is_cached_per_width() {
+        let config = ConsoleConfiguration::default();
+        let font_id = FontId::monospace(14.0);
+        let mut line = ScrollbackLine::new("see https://bevyengine.org/learn now");
+
+        let ctx = Context::default();
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let layout = line_layout(&mut line, &font_id, &config);
+                let rows = layout.rows(ui, None);
+                assert_eq!(rows.len(), 1);
+                let links = rows[0]
+                    .segments
+                    .iter()
+                    .filter_map(|(_, url)| url.as_deref());
+                assert!(links.eq(["https://bevyengine.org/learn"]));
+                let galley = rows[0].segments[0].0.clone();
+
+                // Hits keep the rows, other widths lay them out again
+                let rows = layout.rows(ui, None);
+                assert!(Arc::ptr_eq(&rows[0].segments[0].0, &galley));
+                let rows = layout.rows(ui, Some(100.0));
+                assert!(rows.len() > 1);
+                // Every part of the wrapped URL opens all of it
+                assert!(rows
+                    .iter()
+                    .flat_map(|row| &row.segments)
+                    .filter_map(|(_, url)| url.as_deref())
+                    .all(|url| url == "https://bevyengine.org/learn"));
+
+                line.count = 2;
+                let layout = line_layout(&mut line, &font_id, &config);
+                assert!(layout.job.text.ends_with("(x2)"));
+            });
+        });
+    }
+
+    #[test]
+    fn test_find_urls_ignores_bare_scheme() {
+        assert!(find_urls("http:// and https is not a link").is_empty());
+    }
 
     #[test]
     fn test_console_key_pressed_scan_code() {
@@ -283,4 +2006,313 @@ mod tests {
         let result = console_key_pressed(&input, &config);
         assert!(!result);
     }
+
+    #[test]
+    fn test_history_cursor_with_multibyte_entries() {
+        let ctx = Context::default();
+        let id = Id::new("console_input");
+        egui::text_edit::TextEditState::default().store(&ctx, id);
+
+        let mut state = ConsoleState::<DefaultConsole>::default();
+        for entry in ["🦀 crab", "日本語", "grüße"] {
+            state.history.insert(1, entry.into());
+        }
+
+        for expected in ["grüße", "日本語", "🦀 crab"] {
+            assert!(state.history_previous());
+            assert_eq!(state.buf, expected);
+
+            set_cursor_to_end(&ctx, id, &state.buf);
+            let cursor = TextEdit::load_state(&ctx, id)
+                .and_then(|text_state| text_state.ccursor_range())
+                .unwrap();
+            assert_eq!(cursor.primary.index, expected.chars().count());
+        }
+        assert!(!state.history_previous());
+
+        while state.history_next() {
+            set_cursor_to_end(&ctx, id, &state.buf);
+        }
+        assert!(state.buf.is_empty());
+    }
+
+    #[test]
+    fn test_window_layout_absolute_by_default() {
+        let config = ConsoleConfiguration::default();
+        let screen = Rect::from_min_size(Pos2::ZERO, vec2(1280.0, 720.0));
+
+        assert_eq!(window_layout(&config, screen), (None, None));
+    }
+
+    #[test]
+    fn test_window_layout_bottom_stretch() {
+        let config = ConsoleConfiguration {
+            anchor: Some(ConsoleAnchor::BottomStretch),
+            height_percent: Some(50.0),
+            inner_margin: 4.0,
+            ..Default::default()
+        };
+        let screen = Rect::from_min_size(Pos2::ZERO, vec2(800.0, 600.0));
+
+        assert_eq!(
+            window_layout(&config, screen),
+            (Some(pos2(0.0, 300.0)), Some(vec2(792.0, 292.0)))
+        );
+    }
+
+    #[test]
+    fn test_window_layout_anchored_absolute_size() {
+        let config = ConsoleConfiguration {
+            anchor: Some(ConsoleAnchor::BottomRight),
+            width: 392.0,
+            height: 192.0,
+            inner_margin: 4.0,
+            ..Default::default()
+        };
+        let screen = Rect::from_min_size(Pos2::ZERO, vec2(800.0, 600.0));
+
+        assert_eq!(
+            window_layout(&config, screen),
+            (Some(pos2(400.0, 400.0)), None)
+        );
+    }
+
+    #[test]
+    fn test_window_layout_scales_absolute_size() {
+        let config = ConsoleConfiguration {
+            anchor: Some(ConsoleAnchor::BottomRight),
+            width: 196.0,
+            height: 96.0,
+            inner_margin: 4.0,
+            ui_scale: 2.0,
+            ..Default::default()
+        };
+        let screen = Rect::from_min_size(Pos2::ZERO, vec2(800.0, 600.0));
+
+        // The margin is scaled along with the size
+        assert_eq!(
+            window_layout(&config, screen),
+            (Some(pos2(392.0, 392.0)), None)
+        );
+    }
+
+    #[test]
+    fn test_default_shortcuts_have_egui_keys() {
+        let shortcuts = ConsoleShortcuts::default();
+        let keys = [
+            shortcuts.submit,
+            shortcuts.complete,
+            shortcuts.hide_suggestions,
+            shortcuts.previous,
+            shortcuts.next,
+            shortcuts.page_up,
+            shortcuts.page_down,
+        ];
+        for key in keys.iter().chain(&shortcuts.accept_hint) {
+            assert!(egui_key(*key).is_some(), "{key:?} has no egui key");
+        }
+        assert_eq!(egui_key(KeyCode::Return), Some(egui::Key::Enter));
+        assert_eq!(egui_key(KeyCode::Numpad7), Some(egui::Key::Num7));
+        assert_eq!(egui_key(KeyCode::Grave), None);
+    }
+
+    #[test]
+    fn test_status_text() {
+        let config = ConsoleConfiguration {
+            scrollback_size: 100,
+            ..Default::default()
+        };
+        let mut state = ConsoleState::<DefaultConsole>::default();
+        state.scrollback.push(ScrollbackLine::new("line"));
+
+        assert_eq!(
+            status_text(&config, &state, None),
+            "0 commands | 1/100 lines"
+        );
+
+        state.history.insert(1, "first".into());
+        state.history.insert(1, "second".into());
+        state.history_previous();
+        assert_eq!(
+            status_text(&config, &state, Some(59.6)),
+            "0 commands | 1/100 lines | history 1/2 | 60 FPS"
+        );
+    }
+
+    #[test]
+    fn test_command_suggestions() {
+        let mut config = ConsoleConfiguration::default();
+        for name in ["clear", "close", "exit", "help", "log_clear"] {
+            config.register_raw_console_command(name, clap::Command::new(name));
+        }
+
+        let index = completion_index(&config, &ConsoleVariables::default());
+        let names = index.candidates();
+
+        assert_eq!(command_suggestions(names, "cl", 5), ["clear", "close"]);
+        assert_eq!(command_suggestions(names, "CL", 5), ["clear", "close"]);
+        assert_eq!(command_suggestions(names, "clr", 5), ["clear", "log_clear"]);
+        assert_eq!(command_suggestions(names, "cl", 1), ["clear"]);
+        assert!(command_suggestions(names, "clear ", 5).is_empty());
+        assert!(command_suggestions(names, "", 5).is_empty());
+    }
+
+    #[test]
+    fn test_subcommand_completion() {
+        let mut config = ConsoleConfiguration::default();
+        let entity = clap::Command::new("entity")
+            .subcommand(clap::Command::new("spawn").subcommand(clap::Command::new("cube")))
+            .subcommand(clap::Command::new("despawn"))
+            .subcommand(clap::Command::new("debug").hide(true));
+        config.register_raw_console_command("entity", entity);
+
+        let (path, typed) = split_completion("entity sp");
+        assert_eq!((path.as_slice(), typed), (["entity"].as_slice(), "sp"));
+        let names = argument_names(&config, &path);
+        assert_eq!(names.iter().collect::<Vec<_>>(), ["despawn", "spawn"]);
+        // `despawn` only matches fuzzily, and isn't offered while a subcommand starts with `sp`
+        assert_eq!(command_suggestions(&names, "entity sp", 5), ["spawn"]);
+        assert_eq!(command_suggestions(&names, "entity dsp", 5), ["despawn"]);
+        assert_eq!(
+            command_suggestions(&names, "entity pw", 5),
+            ["despawn", "spawn"]
+        );
+        assert_eq!(
+            ghost_completion(&names, &VecDeque::new(), "entity sp"),
+            Some("awn")
+        );
+
+        let names = argument_names(&config, &["entity", "spawn"]);
+        assert_eq!(names.iter().collect::<Vec<_>>(), ["cube"]);
+        assert_eq!(
+            argument_names(&config, &["entity", "cube"]),
+            Candidates::default()
+        );
+        assert_eq!(argument_names(&config, &["missing"]), Candidates::default());
+    }
+
+    #[test]
+    fn test_argument_completion() {
+        #[derive(Clone, Copy, clap::ValueEnum)]
+        enum Mode {
+            Fast,
+            Slow,
+            #[value(hide = true)]
+            Debug,
+        }
+
+        let mut config = ConsoleConfiguration::default();
+        let run = clap::Command::new("run")
+            .arg(
+                clap::Arg::new("mode")
+                    .long("mode")
+                    .value_parser(clap::value_parser!(Mode)),
+            )
+            .arg(clap::Arg::new("level").value_parser(["low", "high"]))
+            .arg(
+                clap::Arg::new("verbose")
+                    .short('v')
+                    .action(clap::ArgAction::SetTrue),
+            );
+        config.register_raw_console_command("run", run);
+        let names = |buf: &str| {
+            let (path, _) = split_completion(buf);
+            let names = argument_names(&config, &path);
+            names.iter().map(str::to_owned).collect::<Vec<_>>()
+        };
+
+        assert_eq!(names("run "), ["--mode", "-v", "high", "low"]);
+        assert_eq!(names("run --mode "), ["fast", "slow"]);
+        assert_eq!(names("run --mode=f"), ["fast", "slow"]);
+        assert_eq!(split_completion("run --mode=f").1, "f");
+        assert_eq!(names("run -v "), ["--mode", "-v", "high", "low"]);
+        assert_eq!(names("run --mode fast low "), ["--mode", "-v"]);
+    }
+
+    #[test]
+    fn test_argument_hint() {
+        let mut config = ConsoleConfiguration::default();
+        let teleport = clap::Command::new("teleport")
+            .arg(clap::Arg::new("x").required(true))
+            .arg(clap::Arg::new("y").required(true))
+            .arg(clap::Arg::new("z"))
+            .arg(clap::Arg::new("speed").long("speed"))
+            .arg(
+                clap::Arg::new("relative")
+                    .short('r')
+                    .action(clap::ArgAction::SetTrue),
+            );
+        config.register_raw_console_command("teleport", teleport);
+        let hint = |buf: &str| argument_hint(&config, buf);
+
+        assert_eq!(hint("teleport"), None);
+        assert_eq!(hint("teleport "), Some("<x> <y> [z]".to_owned()));
+        assert_eq!(hint("teleport 1"), Some(" <y> [z]".to_owned()));
+        assert_eq!(hint("teleport -r 1 "), Some("<y> [z]".to_owned()));
+        assert_eq!(hint("teleport --speed 2 1 "), Some("<y> [z]".to_owned()));
+        assert_eq!(hint("teleport 1 2 "), None);
+        assert_eq!(hint("teleport --speed "), None);
+        assert_eq!(hint("teleport 1 -"), Some(" --speed <speed> -r".to_owned()));
+        assert_eq!(hint("teleport 1 --s"), Some(" --speed <speed>".to_owned()));
+    }
+
+    #[test]
+    fn test_ghost_completion_prefers_recent_commands() {
+        let mut config = ConsoleConfiguration::default();
+        for name in ["spawn", "spawn_enemy", "speed"] {
+            config.register_raw_console_command(name, clap::Command::new(name));
+        }
+        let mut cvars = ConsoleVariables::default();
+        cvars.register_cvar("speed_limit", 10);
+        let index = completion_index(&config, &cvars);
+        let names = index.candidates();
+        let mut history = VecDeque::from([StyledStr::new()]);
+
+        assert_eq!(ghost_completion(names, &history, "sp"), Some("awn"));
+
+        history.insert(1, "spawn_enemy 3".into());
+        history.insert(1, "speed 2".into());
+        assert_eq!(ghost_completion(names, &history, "sp"), Some("eed"));
+        assert_eq!(ghost_completion(names, &history, "spa"), Some("wn_enemy"));
+        assert_eq!(ghost_completion(names, &history, "speed"), Some("_limit"));
+        assert_eq!(ghost_completion(names, &history, "speed "), None);
+    }
+
+    #[test]
+    fn test_gated_commands() {
+        let mut config = ConsoleConfiguration::default();
+        let meta = CommandMeta {
+            hidden: true,
+            requires: Some("cheats".to_owned()),
+            ..default()
+        };
+        config.register_raw_console_command_with("noclip", clap::Command::new("noclip"), meta);
+        let aliases = ConsoleAliases::default();
+        let mut cvars = ConsoleVariables::default();
+        let mut gates = ConsoleGates::default();
+        let index = completion_index(&config, &cvars);
+        assert_eq!(index.candidates(), &Candidates::default());
+
+        let parse = |cvars: &ConsoleVariables, gates: &ConsoleGates| {
+            let lookup = CommandLookup {
+                config: &config,
+                aliases: &aliases,
+                cvars,
+                gates,
+            };
+            parse_command_line("noclip", &lookup)
+                .map(|command| command.is_some())
+                .map_err(|err| err.to_string())
+        };
+        assert_eq!(
+            parse(&cvars, &gates),
+            Err("error: command 'noclip' is unavailable, it requires cheats".to_owned())
+        );
+        gates.gates.insert("cheats".to_owned(), true);
+        assert_eq!(parse(&cvars, &gates), Ok(true));
+
+        gates.gates.clear();
+        cvars.register_cvar("cheats", true);
+        assert_eq!(parse(&cvars, &gates), Ok(true));
+    }
 }